@@ -6,22 +6,26 @@
 //! smart contract.
 //!
 //! The smart contract keeps track of the current highest bidder as well as
-//! the CCD amount of the highest bid. The CCD balance of the smart contract
-//! represents the highest bid. When a new highest bid is accepted by the smart
-//! contract, the smart contract refunds the old highest bidder.
+//! the CCD amount of the highest bid. The highest bid is stored in the state
+//! rather than derived from the CCD balance, which plain transfers can inflate.
+//! When a new highest bid is accepted by the smart contract, the smart contract
+//! refunds the old highest bidder.
 //!
 //! Bids have to be placed before the auction ends. The participant with the
 //! highest bid (the last bidder) wins the auction.
 //!
-//! After the auction ends, any account can finalize the auction. The owner of
-//! the smart contract instance receives the highest bid (the balance of this
-//! contract) when the auction is finalized. This can be done only once.
+//! After the auction ends, any account can settle the auction, which records
+//! the winner once and for all. The seller, by default the account that
+//! created the auction, receives the highest bid once the proceeds are claimed,
+//! and the winner claims an escrowed item separately.
 //!
 //! Terminology: `Accounts` are derived from a public/private key pair.
 //! `Contract` instances are created by deploying a smart contract
 //! module and initializing it.
 
+use concordium_cis2::*;
 use concordium_std::*;
+use core::cmp::Reverse;
 use core::fmt::Debug;
 
 // The state of the auction either done or continues
@@ -29,247 +33,11539 @@ use core::fmt::Debug;
 pub enum AuctionState {
     // still accepting bids
     Continue,
-    Sold(AccountAddress), //item has been sold the highest bid's owner
+    Sold(AccountAddress),           //item has been sold the highest bid's owner
+    NotSold,                        // auction ended without a sale, e.g. nobody bid
+    SoldUnits(Vec<AccountAddress>), // units of a multi-unit auction sold to these bidders
+    SoldLots(Vec<PackageAward>),    // packages of a combinatorial auction and who won them
+    Cancelled,                      // the seller called the auction off before it ended
+    ReserveNotMet,                  // the highest bid was below the reserve, bid and item went back
 }
 
-// the state of the smart contract
-// this state can be viewed by querying the node
+// the highest protocol fee that can be configured, 10%
+const MAX_FEE_BPS: u64 = 1000;
 
-#[derive(Debug, Serialize, SchemaType, Clone)]
-pub struct State {
-    // auction state
-    auction_state: AuctionState,
-    // highest bid's owner gets the item
-    // could be none if noone has bidded yes
-    highest_bidder: Option<AccountAddress>,
-    //what we are gonna send it back as a item
-    item: String,
-    // when auction ends
-    end: Timestamp,
-}
+// duration limits new auctions start out with, the admin can change them
+const DEFAULT_MIN_DURATION: Duration = Duration::from_millis(0);
+const DEFAULT_MAX_DURATION: Duration = Duration::from_millis(365 * 24 * 60 * 60 * 1000);
 
-// constructor / init function input struct
-#[derive(Serialize, SchemaType)]
-struct InitParameter {
-    item: String,   //specify while starting the auction
-    end: Timestamp, // when auction end
+// time between declaring an emergency withdrawal and executing it, 7 days
+const EMERGENCY_WITHDRAW_DELAY: Duration = Duration::from_millis(7 * 24 * 60 * 60 * 1000);
+
+// time after the end the seller has to reveal a candle auction's secret, 7
+// days. After that anyone can close it unsold and refund the bids.
+const CANDLE_REVEAL_TIMEOUT: Duration = Duration::from_millis(7 * 24 * 60 * 60 * 1000);
+
+// the given basis points of an amount, rounded down
+fn bps_of(amount: Amount, bps: u64) -> Amount {
+    let part = u128::from(amount.micro_ccd) * u128::from(bps) / 10_000;
+    // bps are at most 10_000 wherever this is used, so the part fits in u64
+    Amount::from_micro_ccd(part as u64)
 }
 
-// special errors
-#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
-enum BidError {
-    OnlyAccount,               // contracts cant bid
-    BidMore,                   // only higher bids accepted, raised when amount is low
-    BidTooLate,                // raised when auction ends if someone tries to bid
-    AuctionFinalizedButBidded, // Auction finalized but someone tries to bid
+// the highest buyer's premium that can be configured, 25%
+const MAX_PREMIUM_BPS: u64 = 2500;
+
+// the bid contained in an amount that also pays a premium of the given basis
+// points on top of the bid, rounded down so the premium never comes up short
+fn without_premium(amount: Amount, premium_bps: u64) -> Amount {
+    let bid = u128::from(amount.micro_ccd) * 10_000 / (10_000 + u128::from(premium_bps));
+    Amount::from_micro_ccd(bid as u64)
 }
 
-// finalize function errors
-#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
-enum FinalizeError {
-    AuctionStillActive, // raised when owner tries to finalize before it's end time
-    AuctionAlreadyFinalized, // raised when trying to finalize already finalized one
+// ask the verifier contract whether the account may bid, anything but a clear
+// yes counts as a no
+fn is_verified<S: HasStateApi>(
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    verifier: &ContractAddress,
+    account: AccountAddress,
+) -> bool {
+    match host.invoke_contract(
+        verifier,
+        &account,
+        EntrypointName::new_unchecked("isVerified"),
+        Amount::zero(),
+    ) {
+        Ok((_, Some(mut return_value))) => return_value.get().unwrap_or(false),
+        _ => false,
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Reject, SchemaType)]
-enum BlacklistedBidder {
-    Blacklisted, // raised either someone from blacklisted accounts or owner
-    Allowed,
+// whether the credential the account presented is active in the registry
+fn has_active_credential<S: HasStateApi>(
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    registry: &ContractAddress,
+    account: AccountAddress,
+) -> bool {
+    let holder_id = match host.state().current().credentials.get(&account) {
+        Some(holder_id) => *holder_id,
+        None => return false,
+    };
+    match host.invoke_contract(
+        registry,
+        &holder_id,
+        EntrypointName::new_unchecked("credentialStatus"),
+        Amount::zero(),
+    ) {
+        Ok((_, Some(mut return_value))) => return_value.get() == Ok(CredentialStatus::Active),
+        _ => false,
+    }
 }
 
-// contract init function every initialize operation invokes this
-// acts like a constructor which returns the contract state
-#[init(contract = "auction", parameter = "InitParameter")] //initParam
-fn auction_init<S: HasStateApi>(
-    _ctx: &impl HasInitContext,
-    _state_builder: &mut StateBuilder<S>, //can change the state
-) -> InitResult<State> {
-    //Get input params
-    let param: InitParameter = _ctx.parameter_cursor().get()?; //result error handling
-    /// create state of contract
-    let state = State {
-        auction_state: AuctionState::Continue,
-        highest_bidder: None,
-        item: param.item,
-        end: param.end,
+// whether the account holds enough of the gate token, asked with balanceOf
+fn holds_gate_token<S: HasStateApi>(
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    gate: TokenGate,
+    account: AccountAddress,
+) -> bool {
+    let query = BalanceOfQueryParams {
+        queries: vec![BalanceOfQuery {
+            token_id: gate.token_id,
+            address: Address::Account(account),
+        }],
     };
-    Ok(state)
+    match host.invoke_contract(
+        &gate.contract,
+        &query,
+        EntrypointName::new_unchecked("balanceOf"),
+        Amount::zero(),
+    ) {
+        Ok((_, Some(mut return_value))) => {
+            return_value
+                .get()
+                .is_ok_and(|balances: BalanceOfQueryResponse<ContractTokenAmount>| {
+                    balances
+                        .0
+                        .first()
+                        .is_some_and(|balance| balance.0 >= gate.min_balance.0)
+                })
+        }
+        _ => false,
+    }
 }
-//receive = accepts input from outside
-// contract name, function name to invoke
-#[receive(contract = "auction", name = "bid", payable, mutable)]
-fn auction_bid<S: HasStateApi>(
-    ctx: &impl HasReceiveContext,
-    host: &mut impl HasHost<State, StateApiType = S>,
-    amount: Amount,
-) -> Result<(), BidError> {
-    // first ensure auction continue
-    ensure_eq!(
-        host.state_mut().auction_state,
-        AuctionState::Continue,
-        BidError::AuctionFinalizedButBidded
-    );
-    // not filter user, everybody can bid maybe except for the owner
-    // ensure_eq!(ctx.sender, ctx.owner, BidError::Blacklisted);
 
-    // check time when bid arrives and auction still continue
-    let slot_time = ctx.metadata().slot_time();
+// aliases of an account share the first 29 bytes of the address
+fn is_alias(account: &AccountAddress, other: &AccountAddress) -> bool {
+    account.0[..29] == other.0[..29]
+}
 
-    ensure!(slot_time <= host.state_mut().end, BidError::BidTooLate);
+// how much a new bid has to beat the highest bid by
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub enum BidIncrement {
+    Absolute(Amount), // fixed raise, e.g. 5 CCD
+    BasisPoints(u64), // raise relative to the highest bid, 100 bps = 1%
+}
 
-    // ensure only accounts can bid not contracts
-    let sender_address = match ctx.sender() {
-        Address::Contract(_) => bail!(BidError::OnlyAccount),
-        Address::Account(account_address) => account_address,
-    };
+impl BidIncrement {
+    // smallest bid accepted on top of the given highest bid, none on overflow
+    fn min_next_bid(&self, highest_bid: Amount) -> Option<Amount> {
+        let increment = match self {
+            BidIncrement::Absolute(amount) => amount.micro_ccd,
+            BidIncrement::BasisPoints(bps) => highest_bid.micro_ccd.checked_mul(*bps)? / 10_000,
+        };
+        highest_bid
+            .micro_ccd
+            .checked_add(increment)
+            .map(Amount::from_micro_ccd)
+    }
+}
 
-    // contract balance
-    let balance = host.self_balance();
+// how the dutch auction price goes down over time
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub enum DecayCurve {
+    // drops by the same amount every second
+    Linear {
+        decay_per_second: Amount,
+    },
+    // drops by a fixed amount once every step
+    Stepped {
+        step: Duration,
+        decay_per_step: Amount,
+    },
+    // drops by a percentage (in basis points) of the current price every period
+    Exponential {
+        period: Duration,
+        decay_bps: u64,
+    },
+}
+
+// number of whole periods in the elapsed time, zero length periods never pass
+fn periods(elapsed: Duration, period: Duration) -> u64 {
+    elapsed.millis().checked_div(period.millis()).unwrap_or(0)
+}
 
-    let balance_before_latest_bid = balance - amount; //amaount given as parameter
+// (1 - decay_bps)^periods scaled to 10_000, computed by squaring
+fn decay_factor(decay_bps: u64, periods: u64) -> u128 {
+    let mut factor: u128 = 10_000;
+    let mut base = u128::from(10_000u64.saturating_sub(decay_bps));
+    let mut exponent = periods;
+    while exponent > 0 && factor > 0 {
+        if exponent & 1 == 1 {
+            factor = factor * base / 10_000;
+        }
+        base = base * base / 10_000;
+        exponent >>= 1;
+    }
+    factor
+}
 
-    ensure!(amount > balance_before_latest_bid, BidError::BidMore);
+// descending price auction settings
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub struct DutchAuction {
+    start_price: Amount, // price when bidding opens
+    floor_price: Amount, // price never drops below this
+    curve: DecayCurve,   // how the price drops
+}
 
-    if let Some(account_address) = host.state_mut().highest_bidder.replace(sender_address) {
-        host.invoke_transfer(&account_address, balance_before_latest_bid)
-            .unwrap_abort();
+impl DutchAuction {
+    // the price a bidder has to pay at the given time
+    fn current_price(&self, start: Timestamp, now: Timestamp) -> Amount {
+        let elapsed = now
+            .duration_since(start)
+            .unwrap_or(Duration::from_millis(0));
+        let start_price = self.start_price.micro_ccd;
+        let price = match self.curve {
+            DecayCurve::Linear { decay_per_second } => {
+                let decay = decay_per_second
+                    .micro_ccd
+                    .saturating_mul(elapsed.millis() / 1000);
+                start_price.saturating_sub(decay)
+            }
+            DecayCurve::Stepped {
+                step,
+                decay_per_step,
+            } => {
+                let decay = decay_per_step
+                    .micro_ccd
+                    .saturating_mul(periods(elapsed, step));
+                start_price.saturating_sub(decay)
+            }
+            DecayCurve::Exponential { period, decay_bps } => {
+                let factor = decay_factor(decay_bps, periods(elapsed, period));
+                // factor is at most 10_000 so the result fits in u64
+                (u128::from(start_price) * factor / 10_000) as u64
+            }
+        };
+        Amount::from_micro_ccd(price.max(self.floor_price.micro_ccd))
     }
+}
 
-    Ok(())
+// kind of auction, selected at init
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub enum AuctionType {
+    English,             // ascending bids, highest bid at the end wins
+    Dutch(DutchAuction), // descending price, first bid paying the price wins
+    // bids are committed as hashes until `end` and revealed until `reveal_end`
+    SealedBid {
+        reveal_end: Timestamp,
+    },
+    // the real end is picked at finalize somewhere in the last `closing_window`
+    // before `end`, from a secret whose hash is committed here
+    Candle {
+        closing_window: Duration,
+        seed_hash: HashSha2256,
+    },
+    // several named lots, bidders bid on packages of lots with bidPackage
+    Combinatorial,
 }
 
-// view function
+// token ids and amounts of the cis2 contract holding the auctioned item
+type ContractTokenId = TokenIdVec;
+type ContractTokenAmount = TokenAmountU64;
 
-#[receive(contract = "auction", name = "view", return_value = "State")]
-fn view<'a, 'b, S: HasStateApi>(
-    ctx: &'a impl HasReceiveContext,
-    host: &'b impl HasHost<State, StateApiType = S>,
-) -> ReceiveResult<&'b State> {
-    Ok((host.state()))
+// cis2 token being auctioned, the seller deposits every token of the bundle
+// before bidding opens
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone)]
+pub struct TokenItem {
+    contract: ContractAddress,
+    token_id: ContractTokenId,
+    amount: ContractTokenAmount, // 1 for an nft
 }
-// view highest bid
-#[receive(contract = "auction", name = "viewHighestBid", return_value = "Amount")]
-fn view_highest_bid<S: HasStateApi>(
-    ctx: &impl HasReceiveContext,
-    host: &impl HasHost<State, StateApiType = S>,
-) -> ReceiveResult<Amount> {
-    Ok(host.self_balance())
+
+// cis2 token bids can be paid in, token units are counted in the micro ccd of
+// an `Amount` so the same bid logic works for both
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone)]
+pub struct PaymentToken {
+    contract: ContractAddress,
+    token_id: ContractTokenId,
+    min_bid: Amount, // opening bid in this token
 }
 
-// finalize the auction, send the highest bid to the contract owner
-// of the contract instance. In the next version there will be NFT transfer
-// to the highest bidder.
+// parameter of the wccd contract's unwrap entrypoint
+#[derive(Serialize)]
+struct UnwrapParams {
+    amount: ContractTokenAmount,
+    owner: Address,
+    receiver: Receiver,
+    data: AdditionalData,
+}
 
-#[receive(contract = "auction", name = "finalize", mutable)]
-fn auction_finalize<S: HasStateApi>(
-    ctx: &impl HasReceiveContext,
-    host: &mut impl HasHost<State, StateApiType = S>,
-) -> Result<(), FinalizeError> {
-    let state = host.state();
-    // ensure auction still continues
+// index of the currency in the payment tokens, auctions with a single currency,
+// ccd or one token, only use this one
+const BASE_CURRENCY: u32 = 0;
 
-    ensure_eq!(
-        state.auction_state,
-        AuctionState::Continue,
-        FinalizeError::AuctionAlreadyFinalized
-    );
+// the most lots a combinatorial auction can have, settlement looks at every subset
+const MAX_LOTS: u32 = 10;
 
-    let slot_time = ctx.metadata().slot_time();
-    // Ensure the auction has ended already
-    ensure!(slot_time > state.end, FinalizeError::AuctionStillActive);
+// the most packages a combinatorial auction takes bids on, settlement goes
+// through every package bid for each subset of lots
+const MAX_PACKAGE_BIDS: usize = 32;
 
-    if let Some(account_address) = state.highest_bidder {
-        // mark the auction end
-        host.state_mut().auction_state = AuctionState::Sold(account_address);
-        let owner = ctx.owner();
+// a bid on a package of lots, kept in escrow until finalize
+#[derive(Debug, Serialize, Clone)]
+struct PackageBid {
+    bidder: AccountAddress,
+    lots: u32, // bitmask of lot ids
+    amount: Amount,
+}
+
+// a package won in a combinatorial auction
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone)]
+pub struct PackageAward {
+    bidder: AccountAddress,
+    lots: Vec<u32>,
+    price: Amount,
+}
+
+// a standing bid in the ledger of a multi-unit auction
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub struct LedgerBid {
+    bidder: AccountAddress,
+    amount: Amount,
+}
+
+// a bid kept in escrow until a candle auction is finalized
+#[derive(Debug, Serialize, Clone, Copy)]
+struct CandleBid {
+    bidder: AccountAddress,
+    amount: Amount,
+    timestamp: Timestamp,
+}
+
+// an accepted bid, kept whether it wins or not
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone)]
+struct BidRecord {
+    bidder: AccountAddress,
+    amount: Amount,
+    timestamp: Timestamp,
+    memo: Vec<u8>, // free bytes from the bidder, e.g. an order reference
+}
+
+// a sealed bid, the hash is sha256 of the serialized auction id, bidder and
+// `RevealParameter`, so a copied hash can't be revealed by anyone else
+#[derive(Debug, Serialize, Clone)]
+struct Commitment {
+    hash: HashSha2256,
+    deposit: Amount,          // has to cover the bid
+    revealed: Option<Amount>, // the bid once it's revealed
+    order: u32,               // commit order, the earlier commit wins a tie
+}
+
+// a blinded bid, the hash is sha256 of the serialized auction id and
+// `BlindedReveal`
+#[derive(Debug, Serialize, Clone)]
+struct BlindedCommitment {
+    depositor: AccountAddress, // refunded if the bid is never revealed
+    deposit: Amount,
+    order: u32, // commit order, kept by the revealed commitment
+}
+
+// protocol fee settings, returned by viewFeeConfig
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq)]
+pub struct FeeConfig {
+    fee_bps: u64,
+    fee_receiver: AccountAddress,
+}
+
+// where the auction is in time, reported by the view
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub enum AuctionPhase {
+    Pending, // start time not reached yet
+    Active,  // accepting bids
+    Ended,   // end time passed or auction finalized
+}
+
+// logged for every accepted bid
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq)]
+pub struct BidEvent {
+    bidder: AccountAddress,
+    amount: Amount,       // the bid, without any buyer's premium
+    new_end: Timestamp,   // end of the auction after the bid, later if it was extended
+    timestamp: Timestamp, // when the bid was placed
+    memo: Vec<u8>,        // the bidder's memo, empty if none
+}
+
+// events logged by the contract
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq)]
+enum AuctionEvent {
+    // a bid was accepted
+    Bid(BidEvent),
+    // a late bid pushed the end of the auction
+    AuctionExtended {
+        old_end: Timestamp,
+        new_end: Timestamp,
+        triggered_by: AccountAddress, // the bidder whose bid extended it
+    },
+    // the creator was paid a royalty from the sale
+    Royalty {
+        creator: AccountAddress,
+        amount: Amount,
+    },
+    // part of the sale was donated to the charity
+    Donated {
+        charity: AccountAddress,
+        amount: Amount,
+    },
+    // the first bid in the latest round opened the next overtime round
+    OvertimeRound {
+        round: u32,         // the first overtime round is round 1
+        ends_at: Timestamp, // new end of the auction
+        triggered_by: AccountAddress,
+    },
+    // a bid reached the price cap and won the auction before its end
+    InstantWin {
+        winner: AccountAddress,
+        price: Amount, // the cap, the rest of the bid was refunded
+    },
+    // the seller listed an unsold auction again
+    AuctionRelisted {
+        auction_id: u64,
+        round: u32,
+        end: Timestamp,
+    },
+    // the seller cancelled the auction and the bids were refunded
+    AuctionCancelled {
+        auction_id: u64,
+    },
+    // an account was barred from bidding in the auction
+    Blacklisted {
+        auction_id: u64,
+        account: AccountAddress,
+    },
+    // a blacklisted account may bid again
+    Unblacklisted {
+        auction_id: u64,
+        account: AccountAddress,
+    },
+    // an auction was listed
+    AuctionCreated {
+        auction_id: u64,
+        seller: AccountAddress,
+    },
+    // an auction was settled, once per winner or with no winner if unsold
+    AuctionFinalized {
+        auction_id: u64,
+        winner: Option<AccountAddress>,
+        price: Amount,
+    },
+    // a bid was given back, either credited for withdraw or sent straight away
+    RefundIssued {
+        account: AccountAddress,
+        amount: Amount,
+        reason: RefundReason,
+    },
+    // a winner disputed the sale, the proceeds are frozen
+    DisputeOpened {
+        auction_id: u64,
+        winner: AccountAddress,
+    },
+    // the winner received the item, the proceeds were released
+    DeliveryConfirmed {
+        auction_id: u64,
+        winner: AccountAddress,
+    },
+    // the winner dropped the dispute, the proceeds can be claimed again
+    DisputeClosed {
+        auction_id: u64,
+    },
+    // the arbitrator split the disputed proceeds, premium included
+    DisputeResolved {
+        auction_id: u64,
+        arbitrator: AccountAddress,
+        winner: AccountAddress,
+        winner_bps: u64,       // the winner's share of the proceeds in basis points
+        winner_amount: Amount, // given back to the winner, its share of the premium included
+        seller_amount: Amount, // paid out like normal proceeds, fees included
+    },
+    // the admin declared an emergency withdrawal, executable after the delay
+    EmergencyWithdrawDeclared(EmergencyWithdrawal),
+    // the admin dropped the declared emergency withdrawal
+    EmergencyWithdrawCancelled,
+    // the declared emergency withdrawal was paid out
+    EmergencyWithdrawn {
+        to: AccountAddress,
+        amount: Amount,
+    },
+    // a bid on a package of lots was accepted
+    PackageBidPlaced {
+        auction_id: u64,
+        bidder: AccountAddress,
+        lots: u32, // bitmask of lot ids
+        amount: Amount,
+    },
+    // a sealed bid was committed, the bid stays hidden until it's revealed
+    BidCommitted {
+        auction_id: u64,
+        depositor: AccountAddress, // the bidder, or whoever funded a blinded bid
+        deposit: Amount,
+    },
+    // a sealed bid was revealed
+    BidRevealed {
+        auction_id: u64,
+        bidder: AccountAddress,
+        amount: Amount,
+    },
+}
+
+// every event is logged with its position in the contract's event stream
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq)]
+struct SequencedEvent {
+    seq: u64,
+    event: AuctionEvent,
+}
+
+// why a refund was issued
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+enum RefundReason {
+    // a higher bid took the place of this one
+    Outbid,
+    // the auction ended below the reserve price
+    ReserveNotMet,
+    // the part of a winning bid above the price cap
+    AbovePriceCap,
+    // the part of a dutch bid above the current price
+    AboveCurrentPrice,
+}
+
+// creator royalty taken from the sale price
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub struct Royalty {
+    creator: AccountAddress,
+    bps: u64, // share of the sale price in basis points
+}
+
+// part of the sale price donated to a charity
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub struct Charity {
+    account: AccountAddress,
+    bps: u64, // share of the sale price in basis points
+}
+
+// what is being auctioned, shown by frontends. The hash lets them check the
+// content behind the url wasn't changed.
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone)]
+pub struct ItemMetadata {
+    name: String,
+    description: String,
+    url: String,
+    hash: Option<HashSha2256>, // sha256 of the content at `url`
+}
 
-        let balance = host.self_balance(); // contract balance
-        host.invoke_transfer(&owner, balance).unwrap_abort();
+// byte limits of the item fields, keep the state small and views cheap
+const MAX_ITEM_NAME_LEN: usize = 100;
+const MAX_ITEM_DESCRIPTION_LEN: usize = 1000;
+const MAX_ITEM_URL_LEN: usize = 256;
+
+impl ItemMetadata {
+    // check the fields are within limits and the url, if any, looks like one
+    fn validate(&self) -> Result<(), InitError> {
+        ensure!(!self.name.trim().is_empty(), InitError::EmptyItemName);
+        ensure!(
+            self.name.len() <= MAX_ITEM_NAME_LEN
+                && self.description.len() <= MAX_ITEM_DESCRIPTION_LEN
+                && self.url.len() <= MAX_ITEM_URL_LEN,
+            InitError::ItemTooLarge
+        );
+        // no url means no off-chain content, so nothing to hash either
+        if self.url.is_empty() {
+            ensure!(self.hash.is_none(), InitError::InvalidItemUrl);
+            return Ok(());
+        }
+        let (scheme, rest) = self
+            .url
+            .split_once("://")
+            .ok_or(InitError::InvalidItemUrl)?;
+        ensure!(
+            !scheme.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+                && !rest.is_empty()
+                && !rest.chars().any(|c| c.is_whitespace() || c.is_control()),
+            InitError::InvalidItemUrl
+        );
+        Ok(())
     }
-    Ok(())
 }
 
-#[concordium_cfg_test]
-mod tests {
-    use super::*;
-    use std::sync::atomic::{AtomicU8, Ordering};
-    use test_infrastructure::*;
+// the root state, tagged with its layout version so a later release can
+// change the layout of the stored state. a new layout gets a new variant, the
+// accessors below move to it and a migration converts the old variant
+#[derive(Debug, Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+pub enum VersionedState<S: HasStateApi> {
+    V1(StateV1<S>),
+    V2(State<S>),
+}
 
-    // a counter for generating new accounts
-    static ADDRESS_COUNTER: AtomicU8 = AtomicU8::new(0);
-    const AUCTION_END: u64 = 1;
-    const ITEM: &str = "Starry night by Van Gogh";
+impl<S: HasStateApi> VersionedState<S> {
+    // the state in the current layout. upgrade migrates the state in the same
+    // transaction, so an old layout is never seen here
+    fn current(&self) -> &State<S> {
+        match self {
+            VersionedState::V1(_) => trap(),
+            VersionedState::V2(state) => state,
+        }
+    }
 
-    fn expect_error<E, T>(expr: Result<T, E>, err: E, msg: &str)
-    where
-        E: Eq + Debug,
-        T: Debug,
-    {
-        let actual = expr.expect_err_report(msg);
-        claim_eq!(actual, err)
+    fn current_mut(&mut self) -> &mut State<S> {
+        match self {
+            VersionedState::V1(_) => trap(),
+            VersionedState::V2(state) => state,
+        }
     }
 
-    fn item_and_param() -> InitParameter {
-        InitParameter {
-            item: ITEM.into(),
-            end: Timestamp::from_timestamp_millis(AUCTION_END),
+    // the admin, whatever the layout, it authorizes upgrades and migrations
+    fn admin(&self) -> Address {
+        match self {
+            VersionedState::V1(state) => state.admin,
+            VersionedState::V2(state) => state.admin,
         }
     }
 
-    fn create_parameter_bytes(parameter: &InitParameter) -> Vec<u8> {
-        to_bytes(parameter)
+    // ccd the state owes, counted the way its layout counted it
+    fn ccd_owed(&self) -> Amount {
+        match self {
+            VersionedState::V1(state) => state.ccd_owed(),
+            VersionedState::V2(state) => state.ccd_owed(),
+        }
     }
 
-    fn parametrized_init_ctx(parameter_bytes: &[u8]) -> TestInitContext {
-        let mut ctx = TestInitContext::empty();
-        ctx.set_parameter(parameter_bytes);
-        ctx
+    // convert the state from whichever layout it is stored in to the current one
+    fn migrate(self, state_builder: &mut StateBuilder<S>) -> State<S> {
+        match self {
+            VersionedState::V1(state) => state.migrate(state_builder),
+            VersionedState::V2(state) => state,
+        }
     }
+}
 
-    fn new_account() -> AccountAddress {
-        let account = AccountAddress([ADDRESS_COUNTER.load(Ordering::SeqCst); 32]);
-        ADDRESS_COUNTER.fetch_add(1, Ordering::SeqCst);
-        account
+// the state as stored by the first versioned release, before permits,
+// deposits, duration limits and emergency withdrawals
+#[derive(Debug, Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+pub struct StateV1<S: HasStateApi> {
+    auctions: StateMap<u64, AuctionV1<S>, S>,
+    auctions_by_seller: StateMap<AccountAddress, StateSet<u64, S>, S>,
+    paused: bool,
+    admin: Address,
+    pending_admin: Option<Address>,
+    roles: StateMap<Address, StateSet<Role, S>, S>,
+    operators: StateSet<(AccountAddress, Address), S>,
+    credentials: StateMap<AccountAddress, PublicKeyEd25519, S>,
+    next_auction_id: u64,
+    next_event_seq: u64,
+}
+
+impl<S: HasStateApi> StateV1<S> {
+    fn ccd_owed(&self) -> Amount {
+        self.auctions
+            .iter()
+            .fold(Amount::zero(), |sum, (_, auction)| sum + auction.ccd_owed())
     }
 
-    fn new_account_ctx<'a>() -> (AccountAddress, TestReceiveContext<'a>) {
-        let account = new_account();
-        let ctx = new_ctx(account, account, AUCTION_END);
-        (account, ctx)
+    // the new settings start out off, the new lists empty
+    fn migrate(mut self, state_builder: &mut StateBuilder<S>) -> State<S> {
+        // the fee was set per auction, the first auction's becomes the contract's
+        let (fee_bps, fee_receiver) = self
+            .auctions
+            .get(&0)
+            .map_or((0, AccountAddress([0; 32])), |auction| {
+                (auction.fee_bps, auction.fee_receiver)
+            });
+        let mut auctions = state_builder.new_map();
+        for auction_id in 0..self.next_auction_id {
+            if let Some(auction) = self.auctions.remove_and_get(&auction_id) {
+                auctions.insert(auction_id, auction.migrate(state_builder));
+            }
+        }
+        State {
+            auctions,
+            auctions_by_seller: self.auctions_by_seller,
+            paused: self.paused,
+            admin: self.admin,
+            pending_admin: self.pending_admin,
+            roles: self.roles,
+            operators: self.operators,
+            credentials: self.credentials,
+            permit_keys: state_builder.new_map(),
+            nonces: state_builder.new_map(),
+            deposits: state_builder.new_map(),
+            min_duration: DEFAULT_MIN_DURATION,
+            max_duration: DEFAULT_MAX_DURATION,
+            emergency_withdrawal: None,
+            fee_bps,
+            fee_receiver,
+            next_auction_id: self.next_auction_id,
+            next_event_seq: self.next_event_seq,
+        }
     }
+}
 
-    fn new_ctx<'a>(
-        owner: AccountAddress,
-        sender: AccountAddress,
-        slot_time: u64,
-    ) -> TestReceiveContext<'a> {
-        let mut ctx = TestReceiveContext::empty();
-        ctx.set_sender(Address::Account(sender));
-        ctx.set_owner(owner);
-        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(slot_time));
-        ctx
+// an auction as stored in `StateV1`, the fields mean what they mean in `Auction`
+#[derive(Debug, Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+pub struct AuctionV1<S: HasStateApi> {
+    auction_state: AuctionState,
+    auction_type: AuctionType,
+    highest_bidder: Option<AccountAddress>,
+    item: StateBox<ItemMetadata, S>,
+    seller: AccountAddress,
+    seller_can_bid: bool,
+    verifier: Option<ContractAddress>,
+    credential_registry: Option<ContractAddress>,
+    token_gate: Option<TokenGate>,
+    start: Timestamp,
+    end: Timestamp,
+    blacklist: StateSet<AccountAddress, S>,
+    whitelist_only: bool,
+    whitelist: StateSet<AccountAddress, S>,
+    reserve: Option<Amount>,
+    min_bid: Amount,
+    min_increment: BidIncrement,
+    buy_now: Option<Amount>,
+    extension_window: Duration,
+    commitments: StateMap<AccountAddress, CommitmentV1, S>,
+    candle_bids: StateMap<u32, CandleBid, S>,
+    candle_bid_count: u32,
+    quantity: u32,
+    ledger: Vec<LedgerBid>,
+    uniform_price: bool,
+    all_pay: bool,
+    highest_bid: Amount,
+    total_raised: Amount,
+    lots: StateMap<u32, String, S>,
+    lot_count: u32,
+    package_bids: StateMap<u32, PackageBid, S>,
+    package_bid_count: u32,
+    fee_bps: u64,
+    fee_receiver: AccountAddress,
+    royalty: Option<Royalty>,
+    charity: Option<Charity>,
+    item_tokens: Vec<TokenItem>,
+    tokens_deposited: Vec<bool>,
+    item_escrowed: bool,
+    fractional: bool,
+    fraction_shares: StateMap<AccountAddress, ContractTokenAmount, S>,
+    payment_tokens: Vec<PaymentToken>,
+    highest_currency: u32,
+    wccd: Option<ContractAddress>,
+    unwrap_proceeds: bool,
+    payees: Vec<(AccountAddress, u64)>,
+    pending_refunds: StateMap<(AccountAddress, u32), Amount, S>,
+    buyer_premium_bps: u64,
+    highest_premium: Amount,
+    bid_history: StateMap<u32, BidRecordV1, S>,
+    bid_history_count: u32,
+    bids_by_bidder: StateMap<(AccountAddress, u32), u32, S>,
+    bidder_bid_counts: StateMap<AccountAddress, u32, S>,
+    unique_bidders: u32,
+    total_volume: Amount,
+}
+
+// a sealed bid as stored in `StateV1`, before commit order
+#[derive(Debug, Serialize, Clone)]
+struct CommitmentV1 {
+    hash: HashSha2256,
+    deposit: Amount,
+    revealed: Option<Amount>,
+}
+
+// a bid history entry as stored in `StateV1`, before memos
+#[derive(Debug, Serialize, Eq, PartialEq, Clone, Copy)]
+struct BidRecordV1 {
+    bidder: AccountAddress,
+    amount: Amount,
+    timestamp: Timestamp,
+}
+
+impl<S: HasStateApi> AuctionV1<S> {
+    // what `Auction::ccd_owed` counted in this layout, there were no blinded
+    // bids, unclaimed proceeds or bounties yet
+    fn ccd_owed(&self) -> Amount {
+        let owed = self
+            .pending_refunds
+            .iter()
+            .filter(|(key, _)| key.1 == BASE_CURRENCY)
+            .fold(Amount::zero(), |sum, (_, amount)| sum + *amount);
+        if self.auction_state != AuctionState::Continue || self.highest_currency != BASE_CURRENCY {
+            return owed;
+        }
+        if self.all_pay {
+            return owed + self.total_raised;
+        }
+        match self.auction_type {
+            AuctionType::SealedBid { .. } => self
+                .commitments
+                .iter()
+                .fold(owed, |sum, (_, commitment)| sum + commitment.deposit),
+            AuctionType::Candle { .. } => self
+                .candle_bids
+                .iter()
+                .fold(owed, |sum, (_, bid)| sum + bid.amount),
+            AuctionType::Combinatorial => self
+                .package_bids
+                .iter()
+                .fold(owed, |sum, (_, bid)| sum + bid.amount),
+            _ if self.quantity > 1 => self
+                .ledger
+                .iter()
+                .fold(owed, |sum, entry| sum + entry.amount),
+            _ if self.highest_bidder.is_some() => owed + self.highest_bid + self.highest_premium,
+            _ => owed,
+        }
     }
 
-    fn bid(
-        host: &mut TestHost<State>,
-        ctx: &TestContext<TestReceiveOnlyData>,
-        amount: Amount,
-        current_contract_balance: Amount,
-    ) {
-        //set balance
-        // initial + bid
-        host.set_self_balance(amount + current_contract_balance);
+    fn migrate(mut self, state_builder: &mut StateBuilder<S>) -> Auction<S> {
+        // the history moves to a map of the new records, the old entries are
+        // removed so they don't linger in the state
+        let mut bid_history = state_builder.new_map();
+        for index in 0..self.bid_history_count {
+            if let Some(record) = self.bid_history.remove_and_get(&index) {
+                bid_history.insert(
+                    index,
+                    BidRecord {
+                        bidder: record.bidder,
+                        amount: record.amount,
+                        timestamp: record.timestamp,
+                        memo: Vec::new(),
+                    },
+                );
+            }
+        }
+        // the sealed bids get a commit order, the order the map lists them in
+        let bidders: Vec<AccountAddress> =
+            self.commitments.iter().map(|(bidder, _)| *bidder).collect();
+        let mut commitments = state_builder.new_map();
+        let mut commit_count = 0;
+        for bidder in bidders {
+            if let Some(commitment) = self.commitments.remove_and_get(&bidder) {
+                commitments.insert(
+                    bidder,
+                    Commitment {
+                        hash: commitment.hash,
+                        deposit: commitment.deposit,
+                        revealed: commitment.revealed,
+                        order: commit_count,
+                    },
+                );
+                commit_count += 1;
+            }
+        }
+        Auction {
+            auction_state: self.auction_state,
+            auction_type: self.auction_type,
+            highest_bidder: self.highest_bidder,
+            item: self.item,
+            seller: self.seller,
+            seller_can_bid: self.seller_can_bid,
+            verifier: self.verifier,
+            credential_registry: self.credential_registry,
+            token_gate: self.token_gate,
+            start: self.start,
+            end: self.end,
+            blacklist: self.blacklist,
+            whitelist_only: self.whitelist_only,
+            whitelist: self.whitelist,
+            reserve: self.reserve,
+            min_bid: self.min_bid,
+            min_increment: self.min_increment,
+            buy_now: self.buy_now,
+            extension_window: self.extension_window,
+            commitments,
+            commit_count,
+            candle_bids: self.candle_bids,
+            candle_bid_count: self.candle_bid_count,
+            quantity: self.quantity,
+            ledger: state_builder.new_box(self.ledger),
+            uniform_price: self.uniform_price,
+            all_pay: self.all_pay,
+            highest_bid: self.highest_bid,
+            total_raised: self.total_raised,
+            lots: self.lots,
+            lot_count: self.lot_count,
+            package_bids: self.package_bids,
+            package_bid_count: self.package_bid_count,
+            fee_bps: self.fee_bps,
+            royalty: self.royalty,
+            charity: self.charity,
+            item_tokens: state_builder.new_box(self.item_tokens),
+            tokens_deposited: state_builder.new_box(self.tokens_deposited),
+            item_escrowed: self.item_escrowed,
+            fractional: self.fractional,
+            fraction_shares: self.fraction_shares,
+            payment_tokens: state_builder.new_box(self.payment_tokens),
+            highest_currency: self.highest_currency,
+            wccd: self.wccd,
+            unwrap_proceeds: self.unwrap_proceeds,
+            payees: state_builder.new_box(self.payees),
+            pending_refunds: self.pending_refunds,
+            buyer_premium_bps: self.buyer_premium_bps,
+            highest_premium: self.highest_premium,
+            bid_history,
+            bid_history_count: self.bid_history_count,
+            bids_by_bidder: self.bids_by_bidder,
+            bidder_bid_counts: self.bidder_bid_counts,
+            unique_bidders: self.unique_bidders,
+            total_volume: self.total_volume,
+            price_cap: None,
+            overtime_round: Duration::from_millis(0),
+            overtime_rounds: 0,
+            last_bid_at: None,
+            settle_grace_period: Duration::from_millis(0),
+            terms_hash: None,
+            aggregate_bids: false,
+            anonymous_bids: false,
+            blinded_commitments: state_builder.new_map(),
+            // the first layout paid out at finalization, nothing is left to claim
+            unclaimed_proceeds: Amount::zero(),
+            unclaimed_premium: Amount::zero(),
+            keeper_bounty: Amount::zero(),
+            dispute_window: Duration::from_millis(0),
+            settled_at: None,
+            disputant: None,
+            arbitrator: None,
+            delivery_timeout: Duration::from_millis(0),
+            delivery_confirmed: false,
+            payout_delay: Duration::from_millis(0),
+            round: 1,
+            rounds: state_builder.new_map(),
+            auto_relist: None,
+            relists_left: 0,
+        }
+    }
+}
+
+// the state of the smart contract
+// this state can be viewed by querying the node
+
+#[derive(Debug, Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+pub struct State<S: HasStateApi> {
+    // every auction hosted by this instance, by auction id
+    auctions: StateMap<u64, Auction<S>, S>,
+    // ids of the auctions of each seller
+    auctions_by_seller: StateMap<AccountAddress, StateSet<u64, S>, S>,
+    // bidding and finalizing are stopped while the contract is paused
+    paused: bool,
+    // account or contract allowed to pause, manage the lists and the fee
+    admin: Address,
+    // nominated admin, takes over the role once it accepts
+    pending_admin: Option<Address>,
+    // roles granted by the admin, who holds all of them
+    roles: StateMap<Address, StateSet<Role, S>, S>,
+    // (account, operator) pairs, an operator can bid on behalf of the account
+    operators: StateSet<(AccountAddress, Address), S>,
+    // credential holder id each account presented, checked in credential gated
+    // auctions
+    credentials: StateMap<AccountAddress, PublicKeyEd25519, S>,
+    // key each account signs permits with
+    permit_keys: StateMap<AccountAddress, PublicKeyEd25519, S>,
+    // next permit nonce of each account, missing means zero
+    nonces: StateMap<AccountAddress, u64, S>,
+    // ccd each account deposited to pay its signed bid orders
+    deposits: StateMap<AccountAddress, Amount, S>,
+    // shortest and longest time an auction created here can run
+    min_duration: Duration,
+    max_duration: Duration,
+    // emergency withdrawal the admin declared, it can run once the delay is over
+    emergency_withdrawal: Option<EmergencyWithdrawal>,
+    // protocol fee in basis points taken by auctions created from now on, at most
+    // `MAX_FEE_BPS`
+    fee_bps: u64,
+    // account collecting the protocol fee and the buyer's premium
+    fee_receiver: AccountAddress,
+    // id given to the next auction
+    next_auction_id: u64,
+    // sequence number of the next logged event, lets indexers spot missed events
+    next_event_seq: u64,
+}
 
-        auction_bid(ctx, host, amount).expect_report("Bidding should pass");
+impl<S: HasStateApi> State<S> {
+    // ccd the contract owes bidders across all auctions, deposits included
+    fn ccd_owed(&self) -> Amount {
+        let deposits = self
+            .deposits
+            .iter()
+            .fold(Amount::zero(), |sum, (_, balance)| sum + *balance);
+        self.auctions
+            .iter()
+            .fold(deposits, |sum, (_, auction)| sum + auction.ccd_owed())
     }
 
-    #[concordium_test]
-    fn test_init() {
-        let parameter_bytes = create_parameter_bytes(&item_and_param());
-        let ctx = parametrized_init_ctx(&parameter_bytes);
-        let mut state_builder = TestStateBuilder::new();
-        let state_result = auction_init(&ctx, &mut state_builder);
-        state_result.expect_report("Contract initialize error");
+    // whether the address may call the entrypoints needing the role
+    fn has_role(&self, address: &Address, role: Role) -> bool {
+        *address == self.admin
+            || self
+                .roles
+                .get(address)
+                .is_some_and(|roles| roles.contains(&role))
+    }
+
+    // store a new auction under the next id, returns the id
+    fn add_auction(&mut self, auction: Auction<S>, state_builder: &mut StateBuilder<S>) -> u64 {
+        let auction_id = self.next_auction_id;
+        let mut seller_auctions = self
+            .auctions_by_seller
+            .remove_and_get(&auction.seller)
+            .unwrap_or_else(|| state_builder.new_set());
+        seller_auctions.insert(auction_id);
+        self.auctions_by_seller
+            .insert(auction.seller, seller_auctions);
+        self.auctions.insert(auction_id, auction);
+        self.next_auction_id += 1;
+        auction_id
+    }
+
+    // log the event under the next sequence number
+    fn log_event(
+        &mut self,
+        logger: &mut impl HasLogger,
+        event: AuctionEvent,
+    ) -> Result<(), LogError> {
+        let seq = self.next_event_seq;
+        self.next_event_seq += 1;
+        logger.log(&SequencedEvent { seq, event })
+    }
+}
+
+// ccd the admin can pull out of the contract after a bug, declared a week ahead
+// so users can react
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub struct EmergencyWithdrawal {
+    to: AccountAddress,
+    amount: Amount,
+    executable_at: Timestamp, // declaration time plus `EMERGENCY_WITHDRAW_DELAY`
+}
+
+// a single auction hosted by the contract
+#[derive(Debug, Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+pub struct Auction<S: HasStateApi> {
+    // auction state
+    auction_state: AuctionState,
+    // english or dutch auction
+    auction_type: AuctionType,
+    // highest bid's owner gets the item
+    // could be none if noone has bidded yes
+    highest_bidder: Option<AccountAddress>,
+    //what we are gonna send it back as a item
+    // boxed so bids don't load the metadata, only the views read it
+    item: StateBox<ItemMetadata, S>,
+    // account selling the item, gets the proceeds and escrows the token
+    seller: AccountAddress,
+    // when false the seller and its aliases can't bid
+    seller_can_bid: bool,
+    // contract checking bidders, e.g. for kyc, none lets everyone bid
+    verifier: Option<ContractAddress>,
+    // cis4 registry, bidders need an active credential issued in it
+    credential_registry: Option<ContractAddress>,
+    // token bidders have to hold, none lets everyone bid
+    token_gate: Option<TokenGate>,
+    // when bidding opens
+    start: Timestamp,
+    // when auction ends
+    end: Timestamp,
+    // accounts that are not allowed to bid, managed by the admin
+    blacklist: StateSet<AccountAddress, S>,
+    // when true only whitelisted accounts can bid
+    whitelist_only: bool,
+    // pre-approved bidders, only checked in whitelist mode
+    whitelist: StateSet<AccountAddress, S>,
+    // lowest price the item is sold for, none means no reserve
+    reserve: Option<Amount>,
+    // the first bid has to be at least this much
+    min_bid: Amount,
+    // a new bid has to beat the highest bid by at least this much
+    min_increment: BidIncrement,
+    // a bid of at least this much wins right away
+    buy_now: Option<Amount>,
+    // a bid reaching this much wins right away at this price, the rest is refunded
+    price_cap: Option<Amount>,
+    // bids this close to the end push the end forward by the same amount
+    extension_window: Duration,
+    // length of an overtime round. The first bid in the latest round opens
+    // another one after it, bidding before the end opens the first.
+    overtime_round: Duration,
+    // overtime rounds opened so far, the end moved by this many rounds
+    overtime_rounds: u32,
+    // when the last accepted bid came in
+    last_bid_at: Option<Timestamp>,
+    // time after the end only the seller, the instance owner and finalizers can settle
+    settle_grace_period: Duration,
+    // hash of the terms of sale every bid has to echo, none if there are no terms
+    terms_hash: Option<HashSha2256>,
+    // a bid adds to the account's standing bid instead of replacing it
+    aggregate_bids: bool,
+    // sealed bids are committed blinded and the bidder is only known at reveal
+    anonymous_bids: bool,
+    // sealed bids by account, only used in sealed-bid auctions
+    commitments: StateMap<AccountAddress, Commitment, S>,
+    // number of sealed and blinded bids committed, orders the commitments
+    commit_count: u32,
+    // unrevealed blinded bids by hash, only used with `anonymous_bids`
+    blinded_commitments: StateMap<HashSha2256, BlindedCommitment, S>,
+    // every accepted bid in order, only used in candle auctions
+    candle_bids: StateMap<u32, CandleBid, S>,
+    // number of bids in `candle_bids`
+    candle_bid_count: u32,
+    // number of identical items for sale
+    quantity: u32,
+    // the top `quantity` bids ordered from highest to lowest, only used when
+    // more than one item is for sale. The lists below are boxed so a call
+    // only loads the ones it reads
+    ledger: StateBox<Vec<LedgerBid>, S>,
+    // when true every winner of a multi-unit auction pays the lowest winning bid
+    uniform_price: bool,
+    // when true every bid is kept, winning or not
+    all_pay: bool,
+    // amount of the highest bid, zero until someone bids
+    highest_bid: Amount,
+    // sum of all bids of an all-pay auction
+    total_raised: Amount,
+    // lot names by lot id, only used in combinatorial auctions
+    lots: StateMap<u32, String, S>,
+    // number of lots, ids go from 0 to `lot_count - 1`
+    lot_count: u32,
+    // package bids in the order they were placed
+    package_bids: StateMap<u32, PackageBid, S>,
+    // index of the next package bid, outbid packages leave gaps in `package_bids`
+    package_bid_count: u32,
+    // protocol fee taken from the sale price, in basis points, the contract's
+    // fee when the auction was created
+    fee_bps: u64,
+    // royalty paid to the item's creator before the seller gets paid
+    royalty: Option<Royalty>,
+    // charity receiving a share of the sale price
+    charity: Option<Charity>,
+    // cis2 tokens auctioned as one bundle, empty for an off-chain item
+    item_tokens: StateBox<Vec<TokenItem>, S>,
+    // which tokens of the bundle the contract holds, by index in `item_tokens`
+    tokens_deposited: StateBox<Vec<bool>, S>,
+    // true once the seller deposited the whole bundle
+    item_escrowed: bool,
+    // the winners split the supply of a single fungible item token
+    fractional: bool,
+    // share of the item token each winner of a fractional sale can claim
+    fraction_shares: StateMap<AccountAddress, ContractTokenAmount, S>,
+    // tokens bids can be made in, refunds and payouts use the token of the
+    // bid, empty for ccd
+    payment_tokens: StateBox<Vec<PaymentToken>, S>,
+    // index in `payment_tokens` of the token the highest bid was made in
+    highest_currency: u32,
+    // wccd contract, also listed in `payment_tokens`
+    wccd: Option<ContractAddress>,
+    // the seller gets wccd proceeds unwrapped to ccd
+    unwrap_proceeds: bool,
+    // accounts sharing the proceeds with their share in basis points, empty
+    // means everything goes to the seller
+    payees: StateBox<Vec<(AccountAddress, u64)>, S>,
+    // amounts owed to outbid bidders and refunds that couldn't be sent, by
+    // account and currency, collected with withdraw
+    pending_refunds: StateMap<(AccountAddress, u32), Amount, S>,
+    // buyer's premium in basis points the winner pays on top of the bid
+    buyer_premium_bps: u64,
+    // premium paid along with the highest bid, goes to the fee receiver
+    highest_premium: Amount,
+    // every accepted bid in order, never pruned
+    bid_history: StateMap<u32, BidRecord, S>,
+    // number of bids in `bid_history`
+    bid_history_count: u32,
+    // position in `bid_history` of the n-th bid of an account, by (account, n),
+    // one entry per bid so a bid costs the same however often the account bid
+    bids_by_bidder: StateMap<(AccountAddress, u32), u32, S>,
+    // number of bids of each account that bid at least once
+    bidder_bid_counts: StateMap<AccountAddress, u32, S>,
+    // number of accounts in `bidder_bid_counts`
+    unique_bidders: u32,
+    // sum of all accepted bids, winning or not
+    total_volume: Amount,
+    // proceeds recorded at settlement, paid out with claimProceeds
+    unclaimed_proceeds: Amount,
+    // winner's premium recorded at settlement, paid out with claimProceeds
+    unclaimed_premium: Amount,
+    // ccd the seller funded at creation for whoever settles the auction
+    keeper_bounty: Amount,
+    // time after settlement the proceeds stay in escrow, a winner can dispute meanwhile
+    dispute_window: Duration,
+    // when the sale was settled, the dispute window starts then
+    settled_at: Option<Timestamp>,
+    // the winner disputing the sale, the proceeds are frozen until it's resolved
+    disputant: Option<AccountAddress>,
+    // decides disputes, none leaves them to the winner to close
+    arbitrator: Option<AccountAddress>,
+    // time after settlement the proceeds wait for the winner to confirm delivery,
+    // zero if the item isn't delivered
+    delivery_timeout: Duration,
+    // the winner confirmed the item was delivered
+    delivery_confirmed: bool,
+    // time after settlement before the proceeds can be claimed at all
+    payout_delay: Duration,
+    // how many times the auction was listed, the first listing is round 1
+    round: u32,
+    // outcome of every round that ended, by round
+    rounds: StateMap<u32, RoundOutcome, S>,
+    // reopen the auction by itself when the reserve isn't met
+    auto_relist: Option<AutoRelist>,
+    // automatic relistings still allowed
+    relists_left: u32,
+}
+
+impl<S: HasStateApi> Auction<S> {
+    // the payment token bids in the currency are made in, none for ccd
+    fn payment_token(&self, currency: u32) -> Option<&PaymentToken> {
+        self.payment_tokens.get().get(currency as usize)
+    }
+
+    // time left until the end, or past it
+    fn time_remaining(&self, slot_time: Timestamp) -> TimeRemaining {
+        // bids are accepted up to and including the end
+        match self.end.duration_since(slot_time) {
+            Some(remaining) => TimeRemaining::Remaining(remaining.millis()),
+            None => TimeRemaining::Overtime(
+                slot_time
+                    .duration_since(self.end)
+                    .map_or(0, |overtime| overtime.millis()),
+            ),
+        }
+    }
+
+    // smallest bid accepted at the given time, none when the auction takes no
+    // plain bids and an error when no amount can beat the highest bid
+    fn next_bid(&self, slot_time: Timestamp) -> Result<Option<NextBid>, ContractError> {
+        if self.auction_state != AuctionState::Continue || slot_time > self.end {
+            return Ok(None);
+        }
+
+        // mirrors the checks in place_bid
+        match self.auction_type {
+            AuctionType::SealedBid { .. } | AuctionType::Combinatorial => return Ok(None),
+            // a dutch auction is won by paying the current price, no premium
+            AuctionType::Dutch(dutch) => {
+                let minimum = dutch.current_price(self.start, slot_time);
+                return Ok(Some(NextBid {
+                    minimum,
+                    reserve_met: self.reserve.is_none_or(|reserve| minimum >= reserve),
+                }));
+            }
+            _ => (),
+        }
+        let candle = matches!(self.auction_type, AuctionType::Candle { .. });
+        let multi_unit = self.quantity > 1;
+        let ledger_full = self.ledger.len() >= self.quantity as usize;
+        let (outbidding, highest_bid) = if multi_unit {
+            match self.ledger.last() {
+                Some(lowest) if ledger_full => (true, lowest.amount),
+                _ => (false, Amount::zero()),
+            }
+        } else {
+            (self.highest_bidder.is_some(), self.highest_bid)
+        };
+        // bids have to beat the highest bid, by the increment once there is one
+        let above_highest = highest_bid
+            .micro_ccd
+            .checked_add(1)
+            .map(Amount::from_micro_ccd)
+            .ok_or(ContractError::Overflow)?;
+        let mut bid = if outbidding {
+            self.min_increment
+                .min_next_bid(highest_bid)
+                .ok_or(ContractError::Overflow)?
+                .max(above_highest)
+        } else {
+            self.min_bid.max(above_highest)
+        };
+        // the buy-now price and the cap are accepted regardless of the increment
+        if !candle && !multi_unit && !self.all_pay {
+            for price in [self.buy_now, self.price_cap].into_iter().flatten() {
+                if price > highest_bid && price < bid {
+                    bid = price;
+                }
+            }
+        }
+
+        // the amount sent has to cover the premium on top of the bid, rounded up
+        let premium_factor = 10_000 + u128::from(self.buyer_premium_bps);
+        let minimum = (u128::from(bid.micro_ccd) * premium_factor).div_ceil(10_000);
+        let minimum = u64::try_from(minimum).map_err(|_| ContractError::Overflow)?;
+        Ok(Some(NextBid {
+            minimum: Amount::from_micro_ccd(minimum),
+            reserve_met: self.reserve.is_none_or(|reserve| bid >= reserve),
+        }))
+    }
+
+    // every bid the auction holds, wherever the auction type keeps them
+    fn held_bids(&self) -> Vec<(AccountAddress, Amount)> {
+        match self.auction_type {
+            AuctionType::SealedBid { .. } => self
+                .commitments
+                .iter()
+                .map(|(account, commitment)| (*account, commitment.deposit))
+                .chain(
+                    self.blinded_commitments
+                        .iter()
+                        .map(|(_, blinded)| (blinded.depositor, blinded.deposit)),
+                )
+                .collect(),
+            AuctionType::Candle { .. } => self
+                .candle_bids
+                .iter()
+                .map(|(_, bid)| (bid.bidder, bid.amount))
+                .collect(),
+            AuctionType::Combinatorial => self
+                .package_bids
+                .iter()
+                .map(|(_, bid)| (bid.bidder, bid.amount))
+                .collect(),
+            _ if self.quantity > 1 => self
+                .ledger
+                .iter()
+                .map(|entry| (entry.bidder, entry.amount))
+                .collect(),
+            _ => self
+                .highest_bidder
+                .map(|bidder| (bidder, self.highest_bid + self.highest_premium))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    // ccd the contract owes for this auction, the bids of a running auction,
+    // the refunds waiting to be withdrawn and the unclaimed proceeds
+    fn ccd_owed(&self) -> Amount {
+        let mut owed = self
+            .pending_refunds
+            .iter()
+            .filter(|(key, _)| key.1 == BASE_CURRENCY)
+            .fold(Amount::zero(), |sum, (_, amount)| sum + *amount);
+        if self.auction_state == AuctionState::Continue && self.highest_currency == BASE_CURRENCY {
+            owed = if self.all_pay {
+                owed + self.total_raised
+            } else {
+                self.held_bids()
+                    .iter()
+                    .fold(owed, |sum, (_, amount)| sum + *amount)
+            };
+        }
+        if self.highest_currency == BASE_CURRENCY {
+            owed = owed + self.unclaimed_proceeds + self.unclaimed_premium;
+        }
+        // the bounty is ccd whatever the auction is paid in
+        owed + self.keeper_bounty
+    }
+
+    // append the bid to the history
+    fn record_bid(
+        &mut self,
+        bidder: AccountAddress,
+        amount: Amount,
+        timestamp: Timestamp,
+        memo: Vec<u8>,
+    ) {
+        self.bid_history.insert(
+            self.bid_history_count,
+            BidRecord {
+                bidder,
+                amount,
+                timestamp,
+                memo,
+            },
+        );
+        let bidder_bids = self
+            .bidder_bid_counts
+            .get(&bidder)
+            .map_or(0, |count| *count);
+        if bidder_bids == 0 {
+            self.unique_bidders += 1;
+        }
+        self.bids_by_bidder
+            .insert((bidder, bidder_bids), self.bid_history_count);
+        self.bidder_bid_counts.insert(bidder, bidder_bids + 1);
+        self.bid_history_count += 1;
+        self.total_volume += amount;
+        self.last_bid_at = Some(timestamp);
+    }
+
+    // start a new round from scratch, the item and the settings stay
+    fn reopen(&mut self, start: Timestamp, end: Timestamp, reserve: Option<Amount>) {
+        self.auction_state = AuctionState::Continue;
+        self.start = start;
+        self.end = end;
+        self.reserve = reserve;
+        self.highest_bidder = None;
+        self.highest_bid = Amount::zero();
+        self.highest_premium = Amount::zero();
+        self.highest_currency = BASE_CURRENCY;
+        self.total_raised = Amount::zero();
+        // bids of the last round were refunded at settlement
+        self.commitments.clear();
+        self.commit_count = 0;
+        self.blinded_commitments.clear();
+        self.ledger.clear();
+        self.package_bids.clear();
+        self.package_bid_count = 0;
+        self.settled_at = None;
+        self.delivery_confirmed = false;
+        self.overtime_rounds = 0;
+        self.last_bid_at = None;
+        self.round += 1;
+    }
+
+    // keep how the current round ended
+    fn record_round(&mut self, closed_at: Timestamp) {
+        let outcome = RoundOutcome {
+            round: self.round,
+            start: self.start,
+            end: self.end,
+            closed_at,
+            outcome: self.auction_state.clone(),
+            highest_bid: self.highest_bid,
+        };
+        self.rounds.insert(self.round, outcome);
+    }
+
+    // end of the dispute window, none before settlement or without a window
+    fn dispute_closes_at(&self) -> Option<Timestamp> {
+        if self.dispute_window == Duration::from_millis(0) {
+            return None;
+        }
+        self.settled_at
+            .and_then(|settled_at| settled_at.checked_add(self.dispute_window))
+    }
+
+    // when the proceeds can first be claimed, none before settlement or
+    // without a delay
+    fn payout_unlocks_at(&self) -> Option<Timestamp> {
+        if self.payout_delay == Duration::from_millis(0) {
+            return None;
+        }
+        self.settled_at
+            .and_then(|settled_at| settled_at.checked_add(self.payout_delay))
+    }
+
+    // end of the wait for the delivery, none before settlement or for items
+    // that aren't delivered
+    fn delivery_release_at(&self) -> Option<Timestamp> {
+        if self.delivery_timeout == Duration::from_millis(0) {
+            return None;
+        }
+        self.settled_at
+            .and_then(|settled_at| settled_at.checked_add(self.delivery_timeout))
+    }
+
+    // whether the account won the item, a unit or a package
+    fn is_winner(&self, account: AccountAddress) -> bool {
+        match &self.auction_state {
+            AuctionState::Sold(winner) => *winner == account,
+            AuctionState::SoldUnits(winners) => winners.contains(&account),
+            AuctionState::SoldLots(awards) => awards.iter().any(|award| award.bidder == account),
+            _ => false,
+        }
+    }
+
+    // what the account has in the auction in ccd: its highest bid if that's
+    // still winning and its outbid bids not withdrawn yet
+    fn standing_bid(&self, account: AccountAddress) -> Amount {
+        let refundable = self
+            .pending_refunds
+            .get(&(account, BASE_CURRENCY))
+            .map_or(Amount::zero(), |owed| *owed);
+        if self.highest_bidder == Some(account) {
+            refundable + self.highest_bid + self.highest_premium
+        } else {
+            refundable
+        }
+    }
+
+    // credit an amount the account can withdraw later
+    fn credit_refund(&mut self, account: AccountAddress, amount: Amount, currency: u32) {
+        let key = (account, currency);
+        let owed = self
+            .pending_refunds
+            .get(&key)
+            .map_or(Amount::zero(), |owed| *owed);
+        self.pending_refunds.insert(key, owed + amount);
+    }
+}
+
+// layout version of `ViewState`, bumped whenever its fields change so clients
+// can tell which layout they got
+const VIEW_STATE_VERSION: u32 = 1;
+
+// what the view function returns, the blacklist is left out. built from the
+// auction rather than being it, so the storage layout can change without
+// breaking clients
+#[derive(Debug, Serialize, SchemaType, Clone)]
+pub struct ViewState {
+    version: u32, // `VIEW_STATE_VERSION`
+    auction_state: AuctionState,
+    auction_type: AuctionType,
+    highest_bidder: Option<AccountAddress>,
+    highest_bid: Amount,
+    item: ItemMetadata,
+    seller: AccountAddress,
+    start: Timestamp,
+    end: Timestamp,
+    phase: AuctionPhase,
+    whitelist_only: bool,
+    reserve: Option<Amount>,
+    min_bid: Amount,
+    min_increment: BidIncrement,
+    buy_now: Option<Amount>,
+    extension_window: Duration,
+    quantity: u32,
+    ledger: Vec<LedgerBid>,
+    uniform_price: bool,
+    all_pay: bool,
+    total_raised: Amount,
+    lots: Vec<String>,
+    buyer_premium_bps: u64,
+    item_tokens: Vec<TokenItem>,
+    item_escrowed: bool,
+    payment_tokens: Vec<PaymentToken>,
+    highest_currency: u32,
+    time_remaining: TimeRemaining,
+}
+
+// what viewConfig returns, the parameters the auction was set up with, apart
+// from the end which moves with late bids and the fee receiver the fee manager
+// can change
+#[derive(Debug, Serialize, SchemaType, Clone)]
+pub struct AuctionConfig {
+    auction_type: AuctionType,
+    item: ItemMetadata,
+    seller: AccountAddress,
+    start: Timestamp,
+    end: Timestamp,
+    round: u32,
+    reserve: Option<Amount>,
+    min_bid: Amount,
+    min_increment: BidIncrement,
+    buy_now: Option<Amount>,
+    price_cap: Option<Amount>,
+    extension_window: Duration,
+    overtime_round: Duration,
+    settle_grace_period: Duration,
+    terms_hash: Option<HashSha2256>,
+    aggregate_bids: bool,
+    anonymous_bids: bool,
+    keeper_bounty: Amount,
+    dispute_window: Duration,
+    arbitrator: Option<AccountAddress>,
+    delivery_timeout: Duration,
+    payout_delay: Duration,
+    auto_relist: Option<AutoRelist>,
+    quantity: u32,
+    lots: Vec<String>,
+    fee_bps: u64,
+    buyer_premium_bps: u64,
+    royalty: Option<Royalty>,
+    charity: Option<Charity>,
+    payees: Vec<(AccountAddress, u64)>,
+    item_tokens: Vec<TokenItem>,
+    payment_tokens: Vec<PaymentToken>,
+    wccd: Option<ContractAddress>,
+    verifier: Option<ContractAddress>,
+    credential_registry: Option<ContractAddress>,
+    token_gate: Option<TokenGate>,
+    whitelist_only: bool,
+    uniform_price: bool,
+    all_pay: bool,
+    fractional: bool,
+    unwrap_proceeds: bool,
+    seller_can_bid: bool,
+}
+
+// privileges the admin can grant to other addresses
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub enum Role {
+    Pauser,     // pauses and unpauses the contract
+    Lister,     // manages the blacklists and whitelists
+    Finalizer,  // can settle during the seller's grace period
+    FeeManager, // sets the protocol fee and its receiver
+    Settler,    // submits batches of signed bid orders
+}
+
+// grantRole and revokeRole input
+#[derive(Serialize, SchemaType)]
+struct RoleParameter {
+    address: Address,
+    role: Role,
+}
+
+// one auction in the list returned by viewAuctions
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone)]
+pub struct AuctionSummary {
+    auction_id: u64,
+    item: ItemMetadata,
+    highest_bid: Amount,
+    end: Timestamp,
+    auction_state: AuctionState,
+}
+
+// most entries viewAuctions and viewBidHistory return at once, and most
+// auctions viewBidsBy looks through
+const MAX_PAGE_SIZE: u32 = 50;
+
+// viewAuctions input, auctions are listed by id
+#[derive(Serialize, SchemaType)]
+struct ViewAuctionsParameter {
+    skip: u64,  // number of auctions to skip
+    limit: u32, // number of auctions to return, at most `MAX_PAGE_SIZE`
+}
+
+// reopening an auction whose reserve wasn't met, for another fixed period
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub struct AutoRelist {
+    period: Duration,                // each new round runs this long from settlement
+    lowered_reserve: Option<Amount>, // reserve of the new rounds, none keeps the old one
+    max_retries: u32,                // most times the auction reopens
+}
+
+// how a round of the auction ended, kept across relistings
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone)]
+pub struct RoundOutcome {
+    round: u32,
+    start: Timestamp,
+    end: Timestamp,
+    closed_at: Timestamp, // when the round was settled or cancelled
+    outcome: AuctionState,
+    highest_bid: Amount,
+}
+
+// bidding activity returned by viewStats
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq)]
+pub struct AuctionStats {
+    bid_count: u32,
+    unique_bidders: u32,
+    total_volume: Amount,
+}
+
+// time left returned by viewTimeRemaining, in milliseconds
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+pub enum TimeRemaining {
+    Remaining(u64), // time until the end
+    Overtime(u64),  // time since the end, bidding is closed
+}
+
+// returned by viewMinimumNextBid
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq)]
+pub struct NextBid {
+    minimum: Amount,   // smallest amount to send, buyer's premium included
+    reserve_met: bool, // whether a bid of `minimum` would reach the reserve
+}
+
+// returned by canBid, why a bid would be rejected
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq)]
+pub enum BidEligibility {
+    Eligible,
+    Paused,          // the contract is paused
+    AuctionClosed,   // the auction is finalized or cancelled
+    NotStarted,      // bidding hasn't opened yet
+    TooLate,         // the auction already ended
+    SellerCannotBid, // the seller isn't allowed to bid on its own auction
+    Blacklisted,     // the account is on the blacklist
+    NotWhitelisted,  // whitelist mode and the account isn't on it
+    NoPlainBids,     // sealed-bid or combinatorial, bids go through their own entrypoints
+    TooLow(Amount),  // below the smallest accepted amount, premium included
+}
+
+// viewBidHistory input, bids are listed oldest first
+#[derive(Serialize, SchemaType)]
+struct ViewBidHistoryParameter {
+    auction_id: u64,
+    skip: u32,  // number of bids to skip
+    limit: u32, // number of bids to return, at most `MAX_PAGE_SIZE`
+}
+
+// viewBidsBy input, a page of auctions is searched for the account's bids
+#[derive(Serialize, SchemaType)]
+struct ViewBidsByParameter {
+    account: AccountAddress,
+    skip: u64,  // number of auctions to skip
+    limit: u32, // number of auctions to search, at most `MAX_PAGE_SIZE`
+}
+
+// bid input. An exchange or custodian the end user made its operator names the
+// user as beneficiary, who becomes the bidder, wins the item and gets the
+// refunds
+#[derive(Serial, SchemaType)]
+struct BidParams {
+    auction_id: u64,
+    beneficiary: Option<AccountAddress>, // none bids for the sender
+    memo: Vec<u8>, // kept with the bid and logged, at most `MAX_BID_MEMO_LEN` bytes
+    terms: Option<HashSha2256>, // the auction's terms hash, accepting the terms of sale
+}
+
+// most bytes a bid memo can have
+const MAX_BID_MEMO_LEN: usize = 64;
+
+// older clients send just the auction id, or no memo or terms, so missing
+// trailing fields mean none
+impl Deserial for BidParams {
+    fn deserial<R: Read>(source: &mut R) -> ParseResult<Self> {
+        let auction_id = source.get()?;
+        let beneficiary = match source.read_u8() {
+            Err(_) => {
+                return Ok(BidParams {
+                    auction_id,
+                    beneficiary: None,
+                    memo: Vec::new(),
+                    terms: None,
+                })
+            }
+            Ok(0) => None,
+            Ok(1) => Some(source.get()?),
+            Ok(_) => return Err(ParseError::default()),
+        };
+        let memo = match source.read_u32() {
+            Err(_) => Vec::new(),
+            Ok(len) => (0..len)
+                .map(|_| source.read_u8())
+                .collect::<ParseResult<_>>()?,
+        };
+        let terms = match source.read_u8() {
+            Err(_) | Ok(0) => None,
+            Ok(1) => Some(source.get()?),
+            Ok(_) => return Err(ParseError::default()),
+        };
+        Ok(BidParams {
+            auction_id,
+            beneficiary,
+            memo,
+            terms,
+        })
+    }
+}
+
+// constructor / init function input struct
+#[derive(Serialize, SchemaType)]
+struct InitParameter {
+    auction_type: AuctionType, // english or dutch auction
+    item: ItemMetadata,        // specify while starting the auction
+    start: Timestamp,          // when bidding opens
+    end: Timestamp,            // when auction end
+    whitelist_only: bool,      // only whitelisted accounts can bid
+    reserve: Option<Amount>,   // optional reserve price
+    min_bid: Amount,           // minimum opening bid, zero means no floor
+    // minimum raise over the highest bid, absolute or relative
+    min_increment: BidIncrement,
+    // optional price that ends the auction immediately
+    buy_now: Option<Amount>,
+    // optional ceiling, a bid reaching it wins at the cap and gets the rest back
+    price_cap: Option<Amount>,
+    // anti-sniping window, e.g. 5 minutes, zero disables it
+    extension_window: Duration,
+    // soft close, bidding goes on in rounds this long while each one gets a bid, zero disables it
+    overtime_round: Duration,
+    // time after the end only the seller, owner and finalizers can settle, zero lets anyone
+    settle_grace_period: Duration,
+    // sha256 of the terms of sale, bids have to send it back to accept them
+    terms_hash: Option<HashSha2256>,
+    // bids add up per account, single-unit english auctions paid in ccd only
+    aggregate_bids: bool,
+    // sealed bids go through commitBlinded and revealBlinded, sealed-bid auctions only
+    anonymous_bids: bool,
+    // proceeds stay in escrow this long after settlement, zero pays right away
+    dispute_window: Duration,
+    // splits disputed proceeds between winner and seller
+    arbitrator: Option<AccountAddress>,
+    // physical items, proceeds wait this long for confirmDelivery, zero disables it
+    delivery_timeout: Duration,
+    // proceeds are locked this long after settlement, zero disables it
+    payout_delay: Duration,
+    // reopen when the reserve isn't met, single-unit english auctions only
+    auto_relist: Option<AutoRelist>,
+    // number of identical items, more than one needs an english auction
+    quantity: u32,
+    // multi-unit winners all pay the lowest winning bid
+    uniform_price: bool,
+    // keep every bid, e.g. for charity, needs a single-unit english auction
+    all_pay: bool,
+    // lot names of a combinatorial auction, empty otherwise
+    lots: Vec<String>,
+    // proceeds split in basis points summing to 10_000, empty pays the seller
+    payees: Vec<(AccountAddress, u64)>,
+    royalty: Option<Royalty>, // optional creator royalty
+    // premium sent on top of each bid, e.g. 1000 for 10%, single-unit english only
+    buyer_premium_bps: u64,
+    charity: Option<Charity>, // optional charity donation
+    // cis2 tokens to escrow as a bundle, bids are refused until all are deposited
+    item_tokens: Vec<TokenItem>,
+    // cis2 tokens to bid in instead of ccd, english or dutch only
+    payment_tokens: Vec<PaymentToken>,
+    wccd: Option<ContractAddress>, // wccd contract to accept as a payment token
+    unwrap_proceeds: bool,         // unwrap wccd proceeds to ccd for the seller
+    // top `quantity` bidders split the item token supply by bid
+    fractional: bool,
+    // account paid for the item, defaults to the creator
+    seller: Option<AccountAddress>,
+    seller_can_bid: bool, // allow the seller to bid on its own item
+    // kyc contract whose isVerified has to approve bidders
+    verifier: Option<ContractAddress>,
+    // cis4 registry bidders need an active credential of
+    credential_registry: Option<ContractAddress>,
+    // cis2 token bidders have to hold, e.g. for member-only drops
+    token_gate: Option<TokenGate>,
+}
+
+// errors of bidding and settling, shared so a reject code means the same thing
+// whichever entrypoint raised it
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum ContractError {
+    OnlyAccount,                                // contracts cant bid
+    BidTooLow { minimum_required: Amount }, // only higher bids accepted, carries the smallest accepted bid
+    BidTooLate { ended_at: Timestamp },     // raised when auction ends if someone tries to bid
+    AuctionFinalizedButBidded,              // Auction finalized but someone tries to bid
+    AuctionNotStarted { starts_at: Timestamp }, // raised when someone bids before the start time
+    BelowCurrentPrice(Amount), // dutch bid is below the current price, carries the price
+    Blacklisted,               // raised when a blacklisted account tries to bid
+    NotWhitelisted,            // raised in whitelist mode for accounts not on the list
+    BelowMinimum(Amount),      // first bid is below the opening bid, carries the required amount
+    IncrementTooSmall(Amount), // raise is below the minimum increment, carries the smallest accepted bid
+    Overflow,                  // raised when computing the next minimum bid overflows
+    LogFull,                   // raised when the event log is full
+    LogMalformed,              // raised when the event is malformed
+    SealedAuction,             // raised when bidding openly in a sealed-bid auction, use commit
+    CombinatorialAuction,      // raised when bidding on the whole auction instead of packages
+    NotCombinatorial,          // raised when bidding on a package in another auction type
+    ParseParams,               // raised when the parameter can't be parsed
+    UnknownLot,                // raised when a package is empty or names a lot that doesn't exist
+    TransferFailed,            // raised when paying the owner fails
+    ItemNotEscrowed,           // raised when bidding before the seller deposited the token
+    TokenAuction,              // raised when bidding ccd in an auction paid in a cis2 token
+    UnknownAuction,            // raised when no auction has the given id
+    ContractPaused,            // raised while the admin has paused the contract
+    NotOperator,               // raised when bidding for an account that didn't approve the sender
+    SellerCannotBid,           // raised when the seller bids and the auction doesn't allow it
+    NotVerified,               // raised when the verifier contract didn't approve the bidder
+    NoActiveCredential,        // raised when the bidder's presented credential isn't active
+    NotTokenHolder,            // raised when the bidder doesn't hold enough of the gate token
+    AuctionStillActive { ends_at: Timestamp }, // raised when owner tries to settle before it's end time
+    AuctionAlreadyFinalized, // raised when trying to settle an already settled one
+    NotCandleAuction,        // raised when finalizeCandle is used for another auction type
+    CandleAuction,           // raised when settle is used for a candle auction
+    InvalidSecret,           // secret doesn't match the committed seed hash
+    SellerGracePeriod { open_at: Timestamp }, // raised when others settle before the grace period is over
+    TopUpUnsupported, // raised when topping up outside a single-unit english auction paid in ccd
+    NothingToTopUp,   // raised when the sender has no standing bid to add to
+    MemoTooLong,      // raised when the bid memo is above `MAX_BID_MEMO_LEN`
+    TermsNotAccepted, // raised when the bid doesn't echo the auction's terms hash
+    TooManyPackages,  // raised when a bid names a new package and `MAX_PACKAGE_BIDS` are taken
+    CandleRevealPending { open_at: Timestamp }, // raised when the seller can still reveal the candle
+}
+
+impl From<TransferError> for ContractError {
+    fn from(_: TransferError) -> Self {
+        ContractError::TransferFailed
+    }
+}
+
+impl From<ParseError> for ContractError {
+    fn from(_: ParseError) -> Self {
+        ContractError::ParseParams
+    }
+}
+
+impl From<LogError> for ContractError {
+    fn from(le: LogError) -> Self {
+        match le {
+            LogError::Full => ContractError::LogFull,
+            LogError::Malformed => ContractError::LogMalformed,
+        }
+    }
+}
+
+// commit and reveal errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum SealedBidError {
+    ParseParams,          // raised when the parameter can't be parsed
+    NotSealedAuction,     // raised when the auction isn't a sealed-bid auction
+    OnlyAccount,          // contracts cant bid
+    AuctionFinalized,     // raised when the auction is already finalized
+    CommitNotActive,      // raised when committing outside the bidding time
+    RevealNotActive,      // raised when revealing outside the reveal window
+    Blacklisted,          // raised when a blacklisted account commits
+    NotWhitelisted,       // raised in whitelist mode for accounts not on the list
+    AlreadyCommitted,     // only one commitment per account
+    NoCommitment,         // raised when revealing without a commitment
+    AlreadyRevealed,      // raised when revealing twice
+    InvalidReveal,        // revealed bid doesn't match the commitment
+    DepositTooLow,        // deposit doesn't cover the revealed bid
+    BelowMinimum(Amount), // revealed bid is below the minimum bid, carries the minimum
+    ItemNotEscrowed,      // raised when committing before the seller deposited the token
+    UnknownAuction,       // raised when no auction has the given id
+    SellerCannotBid,      // raised when the seller commits and the auction doesn't allow it
+    NotVerified,          // raised when the verifier contract didn't approve the bidder
+    NoActiveCredential,   // raised when the bidder's presented credential isn't active
+    NotTokenHolder,       // raised when the bidder doesn't hold enough of the gate token
+    NotAnonymous, // raised when committing a blinded bid in an auction without anonymous bids
+    ContractPaused, // raised while the admin has paused the contract
+    LogFull,      // raised when the event log is full
+    LogMalformed, // raised when the event is malformed
+}
+
+impl From<ParseError> for SealedBidError {
+    fn from(_: ParseError) -> Self {
+        SealedBidError::ParseParams
+    }
+}
+
+impl From<LogError> for SealedBidError {
+    fn from(le: LogError) -> Self {
+        match le {
+            LogError::Full => SealedBidError::LogFull,
+            LogError::Malformed => SealedBidError::LogMalformed,
+        }
+    }
+}
+
+// revealBlinded input, hashed to check it against the blinded commitment
+#[derive(Serialize, SchemaType)]
+struct BlindedReveal {
+    bidder: AccountAddress, // wins the item and gets the deposit back
+    amount: Amount,         // the sealed bid
+    salt: Vec<u8>,          // random bytes chosen by the bidder at commit time
+}
+
+// reveal function input, hashed to check it against the commitment
+#[derive(Serialize, SchemaType)]
+struct RevealParameter {
+    amount: Amount, // the sealed bid
+    salt: Vec<u8>,  // random bytes chosen by the bidder at commit time
+}
+
+// withdraw function errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum WithdrawError {
+    OnlyAccount,       // contracts don't get refunds
+    NothingToWithdraw, // raised when nothing is owed to the sender
+    TransferFailed,    // raised when sending the refund fails
+    ParseParams,       // raised when the parameter can't be parsed
+    UnknownAuction,    // raised when no auction has the given id
+}
+
+impl From<TransferError> for WithdrawError {
+    fn from(_: TransferError) -> Self {
+        WithdrawError::TransferFailed
+    }
+}
+
+impl From<ParseError> for WithdrawError {
+    fn from(_: ParseError) -> Self {
+        WithdrawError::ParseParams
+    }
+}
+
+// init and createAuction errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum InitError {
+    ParseParams,       // raised when the parameter can't be parsed
+    OnlyAccount,       // raised when a contract creates an auction, the seller gets paid
+    EndInPast,         // raised when the end isn't after the current time
+    EndBeforeStart,    // raised when the end isn't after the start
+    DurationTooShort,  // raised when the auction would run for less than the minimum duration
+    DurationTooLong,   // raised when the auction would run for longer than the maximum duration
+    RevealBeforeEnd,   // raised when a sealed-bid reveal phase ends before bidding does
+    UnwrapWithoutWccd, // raised when unwrapping proceeds without a wccd contract
+    InvalidQuantity,   // raised when several units are sold in anything but an english auction
+    InvalidAllPay,     // raised when all-pay isn't a single-unit english auction
+    CutsTooHigh,       // raised when fee, royalty and donation add up to more than the price
+    InvalidIncrement,  // raised when a relative increment is above 100%
+    BuyNowTooLow,      // raised when the buy-now price is below the minimum bid or the reserve
+    // raised when the cap is unreachable, not above buy-now or not on a plain
+    // single-unit english auction
+    InvalidPriceCap,
+    // raised when overtime rounds are set with an extension window or outside an english auction
+    InvalidOvertime,
+    PremiumTooHigh, // raised when the buyer's premium is above `MAX_PREMIUM_BPS`
+    // raised when a premium is set on anything but a single-unit english auction
+    InvalidPremium,
+    InvalidItemTokens,    // raised when a token bundle is sold in units or lots
+    InvalidFractional,    // raised when a fractional sale isn't a single token sold in units
+    InvalidPaymentTokens, // raised when payment tokens don't fit the auction type
+    InvalidPayees,        // raised when the payee shares don't add up to 100%
+    // raised when lots are missing, too many or given outside a combinatorial auction
+    InvalidLots,
+    // raised when bids add up outside a single-unit english auction paid in ccd
+    InvalidAggregateBids,
+    InvalidAnonymousBids, // raised when anonymous bids are set on anything but a sealed-bid auction
+    // raised when terms are set on an auction that doesn't take bids through `bid`
+    InvalidTerms,
+    // raised when delivery is confirmed in an auction with more than one winner
+    InvalidDelivery,
+    // raised when relisting by itself outside a single-unit english auction with a reserve
+    InvalidAutoRelist,
+    EmptyItemName,  // raised when the item has no name
+    ItemTooLarge,   // raised when an item field is above its byte limit
+    InvalidItemUrl, // raised when the item url isn't a url, or a hash is given without one
+    // raised when a dispute window is set without an arbitrator to resolve disputes
+    MissingArbitrator,
+    LogFull,      // raised when the event log is full
+    LogMalformed, // raised when the event is malformed
+}
+
+impl From<ParseError> for InitError {
+    fn from(_: ParseError) -> Self {
+        InitError::ParseParams
+    }
+}
+
+impl From<LogError> for InitError {
+    fn from(le: LogError) -> Self {
+        match le {
+            LogError::Full => InitError::LogFull,
+            LogError::Malformed => InitError::LogMalformed,
+        }
+    }
+}
+
+// owner only entrypoint errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum AdminError {
+    ParseParams,           // raised when the parameter can't be parsed
+    OnlyAdmin,             // raised when someone other than the admin calls it
+    NotNominated,          // raised when someone other than the nominee accepts the admin role
+    MissingRole,           // raised when the sender doesn't have the role the entrypoint needs
+    UnknownAuction,        // raised when no auction has the given id
+    UpgradeFailed,         // raised when the new module is missing or has no `auction` contract
+    MigrationFailed,       // raised when the migration entrypoint of the new module rejects
+    InvalidLimits,         // raised when the minimum duration is above the maximum
+    NoEmergencyWithdrawal, // raised when no emergency withdrawal was declared
+    EmergencyTimelock, // raised when executing the emergency withdrawal before its delay is over
+    TransferFailed,    // raised when paying out the emergency withdrawal fails
+    Overflow,          // raised when the execution time overflows
+    FeeTooHigh,        // raised when the fee is above `MAX_FEE_BPS`
+    LogFull,           // raised when the event log is full
+    LogMalformed,      // raised when the event is malformed
+}
+
+// onReceivingCIS2 errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum EscrowError {
+    ParseParams,        // raised when the parameter can't be parsed
+    NoTokenItem,        // raised when the auction isn't for a cis2 token
+    UnknownToken,       // raised when the token or amount isn't the auctioned one
+    NotSeller,          // raised when someone other than the seller sends the token
+    AlreadyEscrowed,    // raised when the token was already deposited
+    Bid(ContractError), // raised when a bid paid in the payment token is rejected
+    UnknownAuction,     // raised when no auction has the given id
+}
+
+impl From<ContractError> for EscrowError {
+    fn from(ce: ContractError) -> Self {
+        EscrowError::Bid(ce)
+    }
+}
+
+impl From<ParseError> for EscrowError {
+    fn from(_: ParseError) -> Self {
+        EscrowError::ParseParams
+    }
+}
+
+// claimProceeds errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum ProceedsClaimError {
+    ParseParams,                               // raised when the parameter can't be parsed
+    NothingToClaim, // raised before settlement, or once the proceeds were paid
+    DisputeWindow { closes_at: Timestamp }, // raised while a winner can still dispute the sale
+    Disputed,       // raised while a dispute freezes the proceeds
+    DeliveryPending { release_at: Timestamp }, // raised before the winner confirmed delivery or the timeout
+    NotWinner, // raised when someone other than the winner confirms delivery
+    PayoutLocked { unlocks_at: Timestamp }, // raised before the payout delay after settlement is over
+    TransferFailed,                         // raised when one of the payouts fails
+    LogFull,                                // raised when the event log is full
+    LogMalformed,                           // raised when the event is malformed
+    UnknownAuction,                         // raised when no auction has the given id
+}
+
+impl From<LogError> for ProceedsClaimError {
+    fn from(le: LogError) -> Self {
+        match le {
+            LogError::Full => ProceedsClaimError::LogFull,
+            LogError::Malformed => ProceedsClaimError::LogMalformed,
+        }
+    }
+}
+
+impl From<TransferError> for ProceedsClaimError {
+    fn from(_: TransferError) -> Self {
+        ProceedsClaimError::TransferFailed
+    }
+}
+
+impl From<ParseError> for ProceedsClaimError {
+    fn from(_: ParseError) -> Self {
+        ProceedsClaimError::ParseParams
+    }
+}
+
+// openDispute and closeDispute errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum DisputeError {
+    ParseParams,     // raised when the parameter can't be parsed
+    UnknownAuction,  // raised when no auction has the given id
+    NotWinner,       // raised when someone other than a winner disputes the sale
+    WindowClosed,    // raised when the dispute window is over or the proceeds were paid
+    AlreadyDisputed, // raised when the sale is already disputed
+    NotDisputed,     // raised when closing or resolving a dispute that isn't open
+    NotArbitrator,   // raised when someone other than the arbitrator resolves a dispute
+    InvalidShare,    // raised when the winner's share is above 100%
+    TransferFailed,  // raised when paying out the seller's share fails
+    LogFull,         // raised when the event log is full
+    LogMalformed,    // raised when the event is malformed
+}
+
+impl From<LogError> for DisputeError {
+    fn from(le: LogError) -> Self {
+        match le {
+            LogError::Full => DisputeError::LogFull,
+            LogError::Malformed => DisputeError::LogMalformed,
+        }
+    }
+}
+
+impl From<TransferError> for DisputeError {
+    fn from(_: TransferError) -> Self {
+        DisputeError::TransferFailed
+    }
+}
+
+impl From<ParseError> for DisputeError {
+    fn from(_: ParseError) -> Self {
+        DisputeError::ParseParams
+    }
+}
+
+// claimItem and reclaimItem errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum ItemClaimError {
+    NotEscrowed,        // raised when there's no token held, or it was already claimed
+    NotWinner,          // raised when someone other than the winner claims the token
+    NotUnsold,          // raised when the seller reclaims before the auction ended unsold
+    OnlySeller,         // raised when someone other than the seller reclaims the token
+    ItemTransferFailed, // raised when the token contract rejects the transfer
+    ParseParams,        // raised when the parameter can't be parsed
+    UnknownAuction,     // raised when no auction has the given id
+}
+
+impl<T> From<CallContractError<T>> for ItemClaimError {
+    fn from(_: CallContractError<T>) -> Self {
+        ItemClaimError::ItemTransferFailed
+    }
+}
+
+impl From<ParseError> for ItemClaimError {
+    fn from(_: ParseError) -> Self {
+        ItemClaimError::ParseParams
+    }
+}
+
+// bidders have to hold at least `min_balance` of this token
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone)]
+pub struct TokenGate {
+    contract: ContractAddress,
+    token_id: ContractTokenId,
+    min_balance: ContractTokenAmount,
+}
+
+// status of a credential as returned by a cis4 registry
+#[derive(Debug, Serialize, SchemaType, Eq, PartialEq, Clone, Copy)]
+enum CredentialStatus {
+    Active,
+    Revoked,
+    Expired,
+    NotActivated,
+}
+
+// presentCredential input, the holder key signs the contract address and the
+// account so a credential can't be claimed by someone else
+#[derive(Serialize, SchemaType)]
+struct PresentCredentialParameter {
+    holder_id: PublicKeyEd25519,
+    signature: SignatureEd25519,
+}
+
+// presentCredential errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum CredentialError {
+    ParseParams,      // raised when the parameter can't be parsed
+    OnlyAccount,      // only accounts bid, so only they present credentials
+    InvalidSignature, // raised when the holder key didn't sign for the sender
+}
+
+// permit, setPermitKey, settleBids and deposit errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum PermitError {
+    ParseParams,             // raised when the parameter or the payload can't be parsed
+    OnlyAccount,             // only accounts sign permits
+    WrongContract,           // raised when the permit was signed for another contract
+    Expired,                 // raised when the permit's expiry has passed
+    WrongNonce,              // raised when the nonce isn't the signer's next one, e.g. a replay
+    NoPermitKey,             // raised when the signer never set a permit key
+    InvalidSignature,        // raised when the signer's permit key didn't sign the message
+    UnsupportedEntrypoint,   // raised when the entrypoint can't be called with a permit
+    AmountMismatch,          // raised when the amount sent isn't the amount the signer bid
+    UnknownAuction,          // raised when no auction has the given id
+    Bid(ContractError),      // raised when the bid itself is rejected
+    MissingRole,             // raised when settling bids without the settler role
+    InsufficientDeposit,     // raised when the deposit doesn't cover the bid or the withdrawal
+    TransferFailed,          // raised when sending a withdrawn deposit fails
+    Withdraw(WithdrawError), // raised when claiming the signer's refunds fails
+}
+
+impl From<ParseError> for PermitError {
+    fn from(_: ParseError) -> Self {
+        PermitError::ParseParams
+    }
+}
+
+impl From<ContractError> for PermitError {
+    fn from(ce: ContractError) -> Self {
+        PermitError::Bid(ce)
+    }
+}
+
+impl From<ParseError> for CredentialError {
+    fn from(_: ParseError) -> Self {
+        CredentialError::ParseParams
+    }
+}
+
+// updateOperator errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum OperatorError {
+    ParseParams, // raised when the parameter can't be parsed
+    OnlyAccount, // only accounts can have operators
+}
+
+impl From<ParseError> for OperatorError {
+    fn from(_: ParseError) -> Self {
+        OperatorError::ParseParams
+    }
+}
+
+// cancelAuction errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum CancelError {
+    ParseParams,      // raised when the parameter can't be parsed
+    OnlySeller,       // raised when someone other than the seller cancels
+    AuctionFinalized, // raised when the auction is already finalized or cancelled
+    AuctionEnded,     // raised when cancelling after the end time
+    AllPayBids,       // all-pay bids are only kept as a total and can't be refunded
+    UnknownAuction,   // raised when no auction has the given id
+    LogFull,          // raised when the event log is full
+    LogMalformed,     // raised when the event is malformed
+}
+
+// relist errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum RelistError {
+    ParseParams,      // raised when the parameter can't be parsed
+    OnlySeller,       // raised when someone other than the seller relists
+    NotUnsold,        // raised when the auction didn't end unsold
+    CandleAuction,    // raised for candle auctions, their seed was revealed at settlement
+    EndInPast,        // raised when the new end isn't in the future
+    DurationTooShort, // raised when the new run is shorter than the minimum duration
+    DurationTooLong,  // raised when the new run is longer than the maximum duration
+    UnknownAuction,   // raised when no auction has the given id
+    LogFull,          // raised when the event log is full
+    LogMalformed,     // raised when the event is malformed
+}
+
+impl From<ParseError> for RelistError {
+    fn from(_: ParseError) -> Self {
+        RelistError::ParseParams
+    }
+}
+
+impl From<LogError> for RelistError {
+    fn from(le: LogError) -> Self {
+        match le {
+            LogError::Full => RelistError::LogFull,
+            LogError::Malformed => RelistError::LogMalformed,
+        }
+    }
+}
+
+impl From<ParseError> for CancelError {
+    fn from(_: ParseError) -> Self {
+        CancelError::ParseParams
+    }
+}
+
+impl From<LogError> for CancelError {
+    fn from(le: LogError) -> Self {
+        match le {
+            LogError::Full => CancelError::LogFull,
+            LogError::Malformed => CancelError::LogMalformed,
+        }
+    }
+}
+
+impl From<ParseError> for AdminError {
+    fn from(_: ParseError) -> Self {
+        AdminError::ParseParams
+    }
+}
+
+impl From<LogError> for AdminError {
+    fn from(le: LogError) -> Self {
+        match le {
+            LogError::Full => AdminError::LogFull,
+            LogError::Malformed => AdminError::LogMalformed,
+        }
+    }
+}
+
+// migrate errors
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serial, SchemaType)]
+enum MigrateError {
+    ParseParams,  // raised when the stored state can't be parsed
+    Unauthorized, // raised when called by anyone but the instance itself or the admin
+    FundsLost,    // raised when the migrated state owes bidders a different amount
+    Underfunded,  // raised when the balance doesn't cover what the state owes
+}
+
+impl From<ParseError> for MigrateError {
+    fn from(_: ParseError) -> Self {
+        MigrateError::ParseParams
+    }
+}
+
+// contract init function every initialize operation invokes this
+// acts like a constructor which returns the contract state
+// the ccd sent along is the keeper bounty of the first auction
+#[init(
+    contract = "auction",
+    parameter = "InitParameter",
+    error = "InitError",
+    payable,
+    enable_logger
+)] //initParam
+fn auction_init<S: HasStateApi>(
+    _ctx: &impl HasInitContext,
+    _state_builder: &mut StateBuilder<S>, //can change the state
+    amount: Amount,
+    logger: &mut impl HasLogger,
+) -> Result<VersionedState<S>, InitError> {
+    //Get input params
+    let param: InitParameter = _ctx.parameter_cursor().get()?; //result error handling
+
+    // the instance starts out hosting a single auction with id 0
+    // no fee until the fee manager sets one
+    let auction = new_auction(
+        param,
+        _ctx.init_origin(),
+        _ctx.metadata().slot_time(),
+        (DEFAULT_MIN_DURATION, DEFAULT_MAX_DURATION),
+        0,
+        amount,
+        _state_builder,
+    )?;
+    let mut state = State {
+        auctions: _state_builder.new_map(),
+        auctions_by_seller: _state_builder.new_map(),
+        paused: false,
+        admin: Address::Account(_ctx.init_origin()),
+        pending_admin: None,
+        roles: _state_builder.new_map(),
+        operators: _state_builder.new_set(),
+        credentials: _state_builder.new_map(),
+        permit_keys: _state_builder.new_map(),
+        nonces: _state_builder.new_map(),
+        deposits: _state_builder.new_map(),
+        min_duration: DEFAULT_MIN_DURATION,
+        max_duration: DEFAULT_MAX_DURATION,
+        emergency_withdrawal: None,
+        fee_bps: 0,
+        fee_receiver: _ctx.init_origin(),
+        next_auction_id: 0,
+        next_event_seq: 0,
+    };
+    let seller = auction.seller;
+    let auction_id = state.add_auction(auction, _state_builder);
+    state.log_event(logger, AuctionEvent::AuctionCreated { auction_id, seller })?;
+    Ok(VersionedState::V2(state))
+}
+
+// check the auction parameters and build a new auction from them
+fn new_auction<S: HasStateApi>(
+    mut param: InitParameter,
+    creator: AccountAddress,
+    slot_time: Timestamp,
+    duration_limits: (Duration, Duration),
+    fee_bps: u64,
+    keeper_bounty: Amount,
+    state_builder: &mut StateBuilder<S>,
+) -> Result<Auction<S>, InitError> {
+    // wccd is one more payment token, its units are micro ccd
+    if let Some(wccd) = param.wccd {
+        param.payment_tokens.push(PaymentToken {
+            contract: wccd,
+            token_id: TokenIdVec(Vec::new()),
+            min_bid: param.min_bid,
+        });
+    }
+    param.item.validate()?;
+    // the auction has to end in the future and run within the duration limits
+    ensure!(param.end > slot_time, InitError::EndInPast);
+    ensure!(param.end > param.start, InitError::EndBeforeStart);
+    let (min_duration, max_duration) = duration_limits;
+    let duration = param
+        .end
+        .duration_since(param.start.max(slot_time))
+        .unwrap_or(Duration::from_millis(0));
+    ensure!(duration >= min_duration, InitError::DurationTooShort);
+    ensure!(duration <= max_duration, InitError::DurationTooLong);
+    if let AuctionType::SealedBid { reveal_end } = param.auction_type {
+        ensure!(reveal_end > param.end, InitError::RevealBeforeEnd);
+    }
+
+    ensure!(
+        !param.unwrap_proceeds || param.wccd.is_some(),
+        InitError::UnwrapWithoutWccd
+    );
+    ensure!(
+        param.quantity == 1 || (param.quantity > 1 && param.auction_type == AuctionType::English),
+        InitError::InvalidQuantity
+    );
+    ensure!(
+        !param.all_pay || (param.quantity == 1 && param.auction_type == AuctionType::English),
+        InitError::InvalidAllPay
+    );
+    // fee, royalty and donation can't take more than the sale price
+    let royalty_bps = param.royalty.map_or(0, |royalty| royalty.bps);
+    let charity_bps = param.charity.map_or(0, |charity| charity.bps);
+    ensure!(royalty_bps <= 10_000 - fee_bps, InitError::CutsTooHigh);
+    ensure!(
+        charity_bps <= 10_000 - fee_bps - royalty_bps,
+        InitError::CutsTooHigh
+    );
+    // a relative increment above 100% is a typo rather than a real setting
+    if let BidIncrement::BasisPoints(bps) = param.min_increment {
+        ensure!(bps <= 10_000, InitError::InvalidIncrement);
+    }
+    // a buy-now price below the floor could never be reached by a valid bid
+    if let Some(buy_now) = param.buy_now {
+        ensure!(
+            buy_now >= param.min_bid && param.reserve.is_none_or(|reserve| buy_now >= reserve),
+            InitError::BuyNowTooLow
+        );
+    }
+    // the cap has to be reachable and above the buy-now price, and the
+    // refunded excess can't carry a premium
+    if let Some(price_cap) = param.price_cap {
+        ensure!(
+            param.auction_type == AuctionType::English
+                && param.quantity == 1
+                && !param.all_pay
+                && param.buyer_premium_bps == 0
+                && price_cap >= param.min_bid
+                && param.reserve.is_none_or(|reserve| price_cap >= reserve)
+                && param.buy_now.is_none_or(|buy_now| price_cap > buy_now),
+            InitError::InvalidPriceCap
+        );
+    }
+    // overtime rounds replace the extension window
+    ensure!(
+        param.overtime_round == Duration::from_millis(0)
+            || (param.auction_type == AuctionType::English
+                && param.extension_window == Duration::from_millis(0)),
+        InitError::InvalidOvertime
+    );
+    // the premium is only supported for a plain single-unit english auction
+    ensure!(
+        param.buyer_premium_bps <= MAX_PREMIUM_BPS,
+        InitError::PremiumTooHigh
+    );
+    ensure!(
+        param.buyer_premium_bps == 0
+            || (param.auction_type == AuctionType::English
+                && param.quantity == 1
+                && !param.all_pay),
+        InitError::InvalidPremium
+    );
+    // a token bundle is a single unit sold to a single winner, unless it's a
+    // fungible supply split among several winners
+    ensure!(
+        param.item_tokens.is_empty()
+            || (param.quantity == 1 && param.auction_type != AuctionType::Combinatorial)
+            || param.fractional,
+        InitError::InvalidItemTokens
+    );
+    // winners of a fractional sale pay their own bid for their share
+    ensure!(
+        !param.fractional
+            || (param.quantity > 1 && param.item_tokens.len() == 1 && !param.uniform_price),
+        InitError::InvalidFractional
+    );
+    // bids only add up where topUp works
+    ensure!(
+        !param.aggregate_bids
+            || (param.auction_type == AuctionType::English
+                && param.quantity == 1
+                && !param.all_pay
+                && param.payment_tokens.is_empty()),
+        InitError::InvalidAggregateBids
+    );
+    // blinded bids are revealed like sealed bids
+    ensure!(
+        !param.anonymous_bids || matches!(param.auction_type, AuctionType::SealedBid { .. }),
+        InitError::InvalidAnonymousBids
+    );
+    // commit and bidPackage only take ccd
+    ensure!(
+        param.payment_tokens.is_empty()
+            || matches!(
+                param.auction_type,
+                AuctionType::English | AuctionType::Dutch(_)
+            ),
+        InitError::InvalidPaymentTokens
+    );
+    // bids in different tokens can only compete for a single item
+    ensure!(
+        param.payment_tokens.len() <= 1 || (param.quantity == 1 && !param.all_pay),
+        InitError::InvalidPaymentTokens
+    );
+    // only `bid` carries the terms back, sealed, package and token bids can't
+    ensure!(
+        param.terms_hash.is_none()
+            || (param.payment_tokens.is_empty()
+                && !matches!(
+                    param.auction_type,
+                    AuctionType::SealedBid { .. } | AuctionType::Combinatorial
+                )),
+        InitError::InvalidTerms
+    );
+    // an open dispute only ends when the arbitrator resolves it, without one
+    // the winner could hold the proceeds forever
+    ensure!(
+        param.dispute_window == Duration::from_millis(0) || param.arbitrator.is_some(),
+        InitError::MissingArbitrator
+    );
+    // a single winner confirms the delivery
+    ensure!(
+        param.delivery_timeout == Duration::from_millis(0)
+            || (param.quantity == 1 && param.auction_type != AuctionType::Combinatorial),
+        InitError::InvalidDelivery
+    );
+    // only the single-unit english settlement checks the reserve against one bid
+    if let Some(auto_relist) = param.auto_relist {
+        ensure!(
+            param.auction_type == AuctionType::English
+                && param.quantity == 1
+                && !param.all_pay
+                && param.reserve.is_some()
+                && auto_relist.period > Duration::from_millis(0),
+            InitError::InvalidAutoRelist
+        );
+    }
+    // payee shares have to add up to exactly 100%
+    if !param.payees.is_empty() {
+        let total_shares = param
+            .payees
+            .iter()
+            .try_fold(0u64, |total, (_, share_bps)| total.checked_add(*share_bps));
+        ensure_eq!(total_shares, Some(10_000), InitError::InvalidPayees);
+    }
+    // lots only make sense for a combinatorial auction, which needs at least one
+    let lot_count = param.lots.len() as u32;
+    if param.auction_type == AuctionType::Combinatorial {
+        ensure!((1..=MAX_LOTS).contains(&lot_count), InitError::InvalidLots);
+    } else {
+        ensure!(lot_count == 0, InitError::InvalidLots);
+    }
+    let mut lots = state_builder.new_map();
+    for (lot_id, lot) in param.lots.into_iter().enumerate() {
+        lots.insert(lot_id as u32, lot);
+    }
+
+    // create the auction
+    let auction = Auction {
+        auction_state: AuctionState::Continue,
+        auction_type: param.auction_type,
+        highest_bidder: None,
+        item: state_builder.new_box(param.item),
+        seller: param.seller.unwrap_or(creator),
+        seller_can_bid: param.seller_can_bid,
+        verifier: param.verifier,
+        credential_registry: param.credential_registry,
+        token_gate: param.token_gate,
+        start: param.start,
+        end: param.end,
+        blacklist: state_builder.new_set(),
+        whitelist_only: param.whitelist_only,
+        whitelist: state_builder.new_set(),
+        reserve: param.reserve,
+        min_bid: param.min_bid,
+        min_increment: param.min_increment,
+        buy_now: param.buy_now,
+        price_cap: param.price_cap,
+        extension_window: param.extension_window,
+        overtime_round: param.overtime_round,
+        overtime_rounds: 0,
+        last_bid_at: None,
+        settle_grace_period: param.settle_grace_period,
+        terms_hash: param.terms_hash,
+        aggregate_bids: param.aggregate_bids,
+        anonymous_bids: param.anonymous_bids,
+        commitments: state_builder.new_map(),
+        commit_count: 0,
+        blinded_commitments: state_builder.new_map(),
+        candle_bids: state_builder.new_map(),
+        candle_bid_count: 0,
+        quantity: param.quantity,
+        ledger: state_builder.new_box(Vec::new()),
+        uniform_price: param.uniform_price,
+        all_pay: param.all_pay,
+        highest_bid: Amount::zero(),
+        total_raised: Amount::zero(),
+        lots,
+        lot_count,
+        package_bids: state_builder.new_map(),
+        package_bid_count: 0,
+        fee_bps,
+        royalty: param.royalty,
+        charity: param.charity,
+        tokens_deposited: state_builder.new_box(vec![false; param.item_tokens.len()]),
+        item_tokens: state_builder.new_box(param.item_tokens),
+        item_escrowed: false,
+        fractional: param.fractional,
+        fraction_shares: state_builder.new_map(),
+        payment_tokens: state_builder.new_box(param.payment_tokens),
+        highest_currency: BASE_CURRENCY,
+        wccd: param.wccd,
+        unwrap_proceeds: param.unwrap_proceeds,
+        payees: state_builder.new_box(param.payees),
+        pending_refunds: state_builder.new_map(),
+        buyer_premium_bps: param.buyer_premium_bps,
+        highest_premium: Amount::zero(),
+        bid_history: state_builder.new_map(),
+        bid_history_count: 0,
+        bids_by_bidder: state_builder.new_map(),
+        bidder_bid_counts: state_builder.new_map(),
+        unique_bidders: 0,
+        total_volume: Amount::zero(),
+        unclaimed_proceeds: Amount::zero(),
+        unclaimed_premium: Amount::zero(),
+        keeper_bounty,
+        dispute_window: param.dispute_window,
+        settled_at: None,
+        disputant: None,
+        arbitrator: param.arbitrator,
+        delivery_timeout: param.delivery_timeout,
+        delivery_confirmed: false,
+        payout_delay: param.payout_delay,
+        round: 1,
+        rounds: state_builder.new_map(),
+        auto_relist: param.auto_relist,
+        relists_left: param
+            .auto_relist
+            .map_or(0, |auto_relist| auto_relist.max_retries),
+    };
+    Ok(auction)
+}
+
+// take an auction out of the state for the call and put it back afterwards, so
+// it can be changed while the host makes transfers
+fn with_auction<S: HasStateApi, H: HasHost<VersionedState<S>, StateApiType = S>, T, E>(
+    host: &mut H,
+    auction_id: u64,
+    unknown: E,
+    f: impl FnOnce(&mut H, &mut Auction<S>) -> Result<T, E>,
+) -> Result<T, E> {
+    let mut auction = match host
+        .state_mut()
+        .current_mut()
+        .auctions
+        .remove_and_get(&auction_id)
+    {
+        Some(auction) => auction,
+        None => return Err(unknown),
+    };
+    let result = f(host, &mut auction);
+    host.state_mut()
+        .current_mut()
+        .auctions
+        .insert(auction_id, auction);
+    result
+}
+
+// list a new auction, anyone can do this with their own parameters. The ccd
+// sent along is paid to whoever settles the auction. Returns the id of the new
+// auction.
+#[receive(
+    contract = "auction",
+    name = "createAuction",
+    parameter = "InitParameter",
+    return_value = "u64",
+    error = "InitError",
+    payable,
+    mutable,
+    enable_logger
+)]
+fn create_auction<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+) -> Result<u64, InitError> {
+    // the creator is paid, so it has to be an account
+    let creator = match ctx.sender() {
+        Address::Contract(_) => bail!(InitError::OnlyAccount),
+        Address::Account(account_address) => account_address,
+    };
+    let param: InitParameter = ctx.parameter_cursor().get()?;
+    let (versioned, state_builder) = host.state_and_builder();
+    let state = versioned.current_mut();
+    let duration_limits = (state.min_duration, state.max_duration);
+    let auction = new_auction(
+        param,
+        creator,
+        ctx.metadata().slot_time(),
+        duration_limits,
+        state.fee_bps,
+        amount,
+        state_builder,
+    )?;
+    let seller = auction.seller;
+    let auction_id = state.add_auction(auction, state_builder);
+    state.log_event(logger, AuctionEvent::AuctionCreated { auction_id, seller })?;
+    Ok(auction_id)
+}
+//receive = accepts input from outside
+// contract name, function name to invoke
+#[receive(
+    contract = "auction",
+    name = "bid",
+    parameter = "BidParams",
+    error = "ContractError",
+    payable,
+    mutable,
+    enable_logger
+)]
+fn auction_bid<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+) -> Result<(), ContractError> {
+    let params: BidParams = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        params.auction_id,
+        ContractError::UnknownAuction,
+        |host, state| {
+            // token auctions take bids through the cis2 receive hook
+            ensure!(state.payment_tokens.is_empty(), ContractError::TokenAuction);
+            // the bid has to accept the auction's current terms of sale
+            ensure!(
+                state.terms_hash.is_none() || params.terms == state.terms_hash,
+                ContractError::TermsNotAccepted
+            );
+
+            // the bidder is paid refunds, so it has to be an account. A
+            // contract can still bid for one that approved it as operator
+            let sender_address = match (params.beneficiary, ctx.sender()) {
+                (Some(beneficiary), sender) => {
+                    ensure!(
+                        sender.matches_account(&beneficiary)
+                            || host
+                                .state()
+                                .current()
+                                .operators
+                                .contains(&(beneficiary, sender)),
+                        ContractError::NotOperator
+                    );
+                    // an excluded account can't bid through someone else
+                    if let Address::Account(account) = sender {
+                        ensure_eligible(host, state, account)?;
+                    }
+                    beneficiary
+                }
+                (None, Address::Contract(_)) => bail!(ContractError::OnlyAccount),
+                (None, Address::Account(account_address)) => account_address,
+            };
+            // each bid adds to what the account already has in the auction
+            if state.aggregate_bids {
+                return add_to_standing_bid(
+                    ctx,
+                    host,
+                    state,
+                    sender_address,
+                    amount,
+                    params.memo,
+                    logger,
+                );
+            }
+            place_bid(
+                ctx,
+                host,
+                state,
+                sender_address,
+                amount,
+                Amount::zero(),
+                BASE_CURRENCY,
+                params.memo,
+                logger,
+            )
+        },
+    )
+}
+
+// add the ccd sent to the sender's standing bid instead of sending a whole new
+// bid. The standing bid is the sender's highest bid if it's still winning,
+// plus any outbid bids not withdrawn yet. Only single-unit english auctions
+// paid in ccd take top-ups.
+#[receive(
+    contract = "auction",
+    name = "topUp",
+    parameter = "u64",
+    error = "ContractError",
+    payable,
+    mutable,
+    enable_logger
+)]
+fn auction_top_up<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+) -> Result<(), ContractError> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        ContractError::UnknownAuction,
+        |host, state| {
+            ensure!(
+                state.auction_type == AuctionType::English
+                    && state.quantity == 1
+                    && !state.all_pay
+                    && state.payment_tokens.is_empty(),
+                ContractError::TopUpUnsupported
+            );
+            let sender_address = match ctx.sender() {
+                Address::Contract(_) => bail!(ContractError::OnlyAccount),
+                Address::Account(account_address) => account_address,
+            };
+            ensure!(
+                state.standing_bid(sender_address) > Amount::zero(),
+                ContractError::NothingToTopUp
+            );
+            add_to_standing_bid(ctx, host, state, sender_address, amount, Vec::new(), logger)
+        },
+    )
+}
+
+// bid the account's standing bid plus the amount, the standing bid is spent on
+// the new one so the account isn't refunded its own bid when it's replaced
+fn add_to_standing_bid<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    sender_address: AccountAddress,
+    amount: Amount,
+    memo: Vec<u8>,
+    logger: &mut impl HasLogger,
+) -> Result<(), ContractError> {
+    let standing = state.standing_bid(sender_address);
+    place_bid(
+        ctx,
+        host,
+        state,
+        sender_address,
+        amount,
+        standing,
+        BASE_CURRENCY,
+        memo,
+        logger,
+    )
+}
+
+// view the bid an account can raise with topUp, premium included
+#[receive(
+    contract = "auction",
+    name = "viewStandingBid",
+    parameter = "(u64, AccountAddress)",
+    return_value = "Amount"
+)]
+fn view_standing_bid<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<Amount> {
+    let (auction_id, account): (u64, AccountAddress) = ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    Ok(state.standing_bid(account))
+}
+
+// bid on behalf of an account that made the sender its operator. The account
+// is the bidder, it wins the item and gets the refunds.
+#[receive(
+    contract = "auction",
+    name = "bidFor",
+    parameter = "(u64, AccountAddress)",
+    payable,
+    mutable,
+    enable_logger
+)]
+fn auction_bid_for<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+) -> Result<(), ContractError> {
+    let (auction_id, beneficiary): (u64, AccountAddress) = ctx.parameter_cursor().get()?;
+    ensure!(
+        host.state()
+            .current()
+            .operators
+            .contains(&(beneficiary, ctx.sender())),
+        ContractError::NotOperator
+    );
+    with_auction(
+        host,
+        auction_id,
+        ContractError::UnknownAuction,
+        |host, state| {
+            ensure!(state.payment_tokens.is_empty(), ContractError::TokenAuction);
+            // the operator can't accept the terms for the account
+            ensure!(state.terms_hash.is_none(), ContractError::TermsNotAccepted);
+            // an excluded account can't bid through someone else
+            if let Address::Account(account) = ctx.sender() {
+                ensure_eligible(host, state, account)?;
+            }
+            place_bid(
+                ctx,
+                host,
+                state,
+                beneficiary,
+                amount,
+                Amount::zero(),
+                BASE_CURRENCY,
+                Vec::new(),
+                logger,
+            )
+        },
+    )
+}
+
+// present the web3 id credential the sender bids with in credential gated
+// auctions, its status is checked in the registry on every bid
+#[receive(
+    contract = "auction",
+    name = "presentCredential",
+    parameter = "PresentCredentialParameter",
+    mutable,
+    crypto_primitives
+)]
+fn present_credential<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), CredentialError> {
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(CredentialError::OnlyAccount),
+        Address::Account(account_address) => account_address,
+    };
+    let param: PresentCredentialParameter = ctx.parameter_cursor().get()?;
+    let message = to_bytes(&(ctx.self_address(), sender_address));
+    ensure!(
+        crypto_primitives.verify_ed25519_signature(param.holder_id, param.signature, &message),
+        CredentialError::InvalidSignature
+    );
+    host.state_mut()
+        .current_mut()
+        .credentials
+        .insert(sender_address, param.holder_id);
+    Ok(())
+}
+
+// cis3 permit message, the signer signs its serialization
+#[derive(Serialize, SchemaType)]
+struct PermitMessage {
+    contract_address: ContractAddress, // this contract, so the permit can't be used elsewhere
+    nonce: u64,                        // the signer's next nonce, see viewNonce
+    timestamp: Timestamp,              // expiry of the permit
+    entry_point: OwnedEntrypointName,  // entrypoint to call on behalf of the signer
+    payload: Vec<u8>,                  // parameter of the entrypoint
+}
+
+// entrypoints a permit can call
+fn bid_entrypoint() -> EntrypointName<'static> {
+    EntrypointName::new_unchecked("bid")
+}
+
+fn claim_refund_entrypoint() -> EntrypointName<'static> {
+    EntrypointName::new_unchecked("claimRefund")
+}
+
+// supportsPermit input
+#[derive(Serialize, SchemaType)]
+struct SupportsPermitQueryParams {
+    queries: Vec<OwnedEntrypointName>,
+}
+
+// permit input
+#[derive(Serialize, SchemaType)]
+struct PermitParam {
+    signature: SignatureEd25519,
+    signer: AccountAddress,
+    message: PermitMessage,
+}
+
+// payload of a permit for bid, the amount is the ccd the sponsor has to send
+#[derive(Serialize, SchemaType)]
+struct PermitBidPayload {
+    auction_id: u64,
+    amount: Amount,
+}
+
+// set the key the sender signs permits with
+#[receive(
+    contract = "auction",
+    name = "setPermitKey",
+    parameter = "PublicKeyEd25519",
+    mutable
+)]
+fn set_permit_key<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), PermitError> {
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(PermitError::OnlyAccount),
+        Address::Account(account_address) => account_address,
+    };
+    let key: PublicKeyEd25519 = ctx.parameter_cursor().get()?;
+    host.state_mut()
+        .current_mut()
+        .permit_keys
+        .insert(sender_address, key);
+    Ok(())
+}
+
+// execute a message signed by an account, so a sponsor pays the transaction
+// fee. For bid the sponsor sends the amount, the signer is the bidder and gets
+// the refunds.
+#[receive(
+    contract = "auction",
+    name = "permit",
+    parameter = "PermitParam",
+    payable,
+    mutable,
+    enable_logger,
+    crypto_primitives
+)]
+fn permit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), PermitError> {
+    let param: PermitParam = ctx.parameter_cursor().get()?;
+    let signer = param.signer;
+    check_permit(
+        ctx,
+        host.state_mut().current_mut(),
+        crypto_primitives,
+        &param,
+    )?;
+
+    let message = param.message;
+    let entry_point = message.entry_point.as_entrypoint_name();
+    if entry_point == claim_refund_entrypoint() {
+        // the refunds go to the signer, the sponsor only pays the fee
+        let auction_id: u64 = from_bytes(&message.payload)?;
+        ensure_eq!(amount, Amount::zero(), PermitError::AmountMismatch);
+        return with_auction(
+            host,
+            auction_id,
+            PermitError::UnknownAuction,
+            |host, state| {
+                claim_pending_refund(ctx, host, state, signer).map_err(PermitError::Withdraw)
+            },
+        );
+    }
+    ensure!(
+        entry_point == bid_entrypoint(),
+        PermitError::UnsupportedEntrypoint
+    );
+    let payload: PermitBidPayload = from_bytes(&message.payload)?;
+    ensure_eq!(amount, payload.amount, PermitError::AmountMismatch);
+    with_auction(
+        host,
+        payload.auction_id,
+        PermitError::UnknownAuction,
+        |host, state| {
+            ensure!(
+                state.payment_tokens.is_empty(),
+                PermitError::Bid(ContractError::TokenAuction)
+            );
+            // the signed payload doesn't carry the terms
+            ensure!(
+                state.terms_hash.is_none(),
+                PermitError::Bid(ContractError::TermsNotAccepted)
+            );
+            place_bid(
+                ctx,
+                host,
+                state,
+                signer,
+                amount,
+                Amount::zero(),
+                BASE_CURRENCY,
+                Vec::new(),
+                logger,
+            )?;
+            Ok(())
+        },
+    )
+}
+
+// check the permit was signed by the signer for this contract and is still
+// valid, and use up its nonce so it can't be replayed
+fn check_permit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    state: &mut State<S>,
+    crypto_primitives: &impl HasCryptoPrimitives,
+    param: &PermitParam,
+) -> Result<(), PermitError> {
+    let message = &param.message;
+    let signer = param.signer;
+    ensure_eq!(
+        message.contract_address,
+        ctx.self_address(),
+        PermitError::WrongContract
+    );
+    ensure!(
+        ctx.metadata().slot_time() <= message.timestamp,
+        PermitError::Expired
+    );
+    let nonce = state.nonces.get(&signer).map_or(0, |nonce| *nonce);
+    ensure_eq!(message.nonce, nonce, PermitError::WrongNonce);
+    let key = state
+        .permit_keys
+        .get(&signer)
+        .map(|key| *key)
+        .ok_or(PermitError::NoPermitKey)?;
+    ensure!(
+        crypto_primitives.verify_ed25519_signature(key, param.signature, &to_bytes(message)),
+        PermitError::InvalidSignature
+    );
+    state.nonces.insert(signer, nonce + 1);
+    Ok(())
+}
+
+// add to the deposit of an account, signed bid orders of the account are paid
+// from it. Anyone can fund an account's deposit.
+#[receive(
+    contract = "auction",
+    name = "deposit",
+    parameter = "AccountAddress",
+    payable,
+    mutable
+)]
+fn auction_deposit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    amount: Amount,
+) -> Result<(), PermitError> {
+    let account: AccountAddress = ctx.parameter_cursor().get()?;
+    let state = host.state_mut().current_mut();
+    let balance = state
+        .deposits
+        .get(&account)
+        .map_or(Amount::zero(), |balance| *balance);
+    state.deposits.insert(account, balance + amount);
+    Ok(())
+}
+
+// take back part of the sender's deposit
+#[receive(
+    contract = "auction",
+    name = "withdrawDeposit",
+    parameter = "Amount",
+    mutable
+)]
+fn withdraw_deposit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), PermitError> {
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(PermitError::OnlyAccount),
+        Address::Account(account_address) => account_address,
+    };
+    let amount: Amount = ctx.parameter_cursor().get()?;
+    let state = host.state_mut().current_mut();
+    let balance = state
+        .deposits
+        .get(&sender_address)
+        .map_or(Amount::zero(), |balance| *balance);
+    ensure!(balance >= amount, PermitError::InsufficientDeposit);
+    state.deposits.insert(sender_address, balance - amount);
+    host.invoke_transfer(&sender_address, amount)
+        .map_err(|_| PermitError::TransferFailed)?;
+    Ok(())
+}
+
+// place a batch of bid orders signed off-chain, in order, each paid from the
+// signer's deposit. One invalid order rejects the whole batch.
+#[receive(
+    contract = "auction",
+    name = "settleBids",
+    parameter = "Vec<PermitParam>",
+    mutable,
+    enable_logger,
+    crypto_primitives
+)]
+fn settle_bids<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), PermitError> {
+    ensure!(
+        host.state()
+            .current()
+            .has_role(&ctx.sender(), Role::Settler),
+        PermitError::MissingRole
+    );
+    let orders: Vec<PermitParam> = ctx.parameter_cursor().get()?;
+    for order in orders {
+        let signer = order.signer;
+        let state = host.state_mut().current_mut();
+        check_permit(ctx, state, crypto_primitives, &order)?;
+        ensure!(
+            order.message.entry_point.as_entrypoint_name() == bid_entrypoint(),
+            PermitError::UnsupportedEntrypoint
+        );
+        let payload: PermitBidPayload = from_bytes(&order.message.payload)?;
+        let balance = state
+            .deposits
+            .get(&signer)
+            .map_or(Amount::zero(), |balance| *balance);
+        ensure!(balance >= payload.amount, PermitError::InsufficientDeposit);
+        state.deposits.insert(signer, balance - payload.amount);
+        with_auction(
+            host,
+            payload.auction_id,
+            PermitError::UnknownAuction,
+            |host, state| {
+                ensure!(
+                    state.payment_tokens.is_empty(),
+                    PermitError::Bid(ContractError::TokenAuction)
+                );
+                ensure!(
+                    state.terms_hash.is_none(),
+                    PermitError::Bid(ContractError::TermsNotAccepted)
+                );
+                place_bid(
+                    ctx,
+                    host,
+                    state,
+                    signer,
+                    payload.amount,
+                    Amount::zero(),
+                    BASE_CURRENCY,
+                    Vec::new(),
+                    logger,
+                )?;
+                Ok(())
+            },
+        )?;
+    }
+    Ok(())
+}
+
+// cis3, tell wallets which entrypoints permit can call
+#[receive(
+    contract = "auction",
+    name = "supportsPermit",
+    parameter = "SupportsPermitQueryParams",
+    return_value = "SupportsQueryResponse"
+)]
+fn supports_permit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    _host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<SupportsQueryResponse> {
+    let params: SupportsPermitQueryParams = ctx.parameter_cursor().get()?;
+    let response: Vec<SupportResult> = params
+        .queries
+        .iter()
+        .map(|entry_point| {
+            let entry_point = entry_point.as_entrypoint_name();
+            if entry_point == bid_entrypoint() || entry_point == claim_refund_entrypoint() {
+                SupportResult::Support
+            } else {
+                SupportResult::NoSupport
+            }
+        })
+        .collect();
+    Ok(SupportsQueryResponse::from(response))
+}
+
+// view the nonce the account's next permit has to carry
+#[receive(
+    contract = "auction",
+    name = "viewNonce",
+    parameter = "AccountAddress",
+    return_value = "u64"
+)]
+fn view_nonce<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<u64> {
+    let account: AccountAddress = ctx.parameter_cursor().get()?;
+    Ok(host
+        .state()
+        .current()
+        .nonces
+        .get(&account)
+        .map_or(0, |nonce| *nonce))
+}
+
+// add or remove operators of the sender, cis2 style. Operators can bid for the
+// sender with bidFor, or with bid naming it as beneficiary.
+#[receive(
+    contract = "auction",
+    name = "updateOperator",
+    parameter = "UpdateOperatorParams",
+    mutable
+)]
+fn update_operator<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), OperatorError> {
+    let owner = match ctx.sender() {
+        Address::Contract(_) => bail!(OperatorError::OnlyAccount),
+        Address::Account(account_address) => account_address,
+    };
+    let UpdateOperatorParams(updates) = ctx.parameter_cursor().get()?;
+    let operators = &mut host.state_mut().current_mut().operators;
+    for update in updates {
+        match update.update {
+            OperatorUpdate::Add => operators.insert((owner, update.operator)),
+            OperatorUpdate::Remove => operators.remove(&(owner, update.operator)),
+        };
+    }
+    Ok(())
+}
+
+// the checks on who may bid in the auction. They apply to the bidder and to an
+// account sending a bid for someone else.
+fn ensure_eligible<S: HasStateApi>(
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &Auction<S>,
+    account: AccountAddress,
+) -> Result<(), ContractError> {
+    // the seller can't drive the price up with its own bids, unless allowed
+    ensure!(
+        state.seller_can_bid || !is_alias(&account, &state.seller),
+        ContractError::SellerCannotBid
+    );
+    // regulated auctions only take bids from accounts the verifier approved
+    if let Some(verifier) = state.verifier {
+        ensure!(
+            is_verified(host, &verifier, account),
+            ContractError::NotVerified
+        );
+    }
+    // and accounts holding an active credential of the registry
+    if let Some(registry) = state.credential_registry {
+        ensure!(
+            has_active_credential(host, &registry, account),
+            ContractError::NoActiveCredential
+        );
+    }
+    // member-only auctions need a balance of the gate token
+    if let Some(gate) = state.token_gate.clone() {
+        ensure!(
+            holds_gate_token(host, gate, account),
+            ContractError::NotTokenHolder
+        );
+    }
+    // blacklisted accounts can't bid
+    ensure!(
+        !state.blacklist.contains(&account),
+        ContractError::Blacklisted
+    );
+    // in whitelist mode only pre-approved accounts can bid
+    ensure!(
+        !state.whitelist_only || state.whitelist.contains(&account),
+        ContractError::NotWhitelisted
+    );
+    Ok(())
+}
+
+// check and record a bid, paid in ccd or in one of the payment tokens
+#[allow(clippy::too_many_arguments)]
+fn place_bid<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    sender_address: AccountAddress,
+    amount: Amount,
+    standing: Amount, // the bidder's ccd already held, spent on this bid too
+    currency: u32,
+    memo: Vec<u8>,
+    logger: &mut impl HasLogger,
+) -> Result<(), ContractError> {
+    ensure!(
+        !host.state().current().paused,
+        ContractError::ContractPaused
+    );
+    let amount = standing + amount;
+    ensure!(memo.len() <= MAX_BID_MEMO_LEN, ContractError::MemoTooLong);
+    // first ensure auction continue
+    ensure_eq!(
+        state.auction_state,
+        AuctionState::Continue,
+        ContractError::AuctionFinalizedButBidded
+    );
+    ensure_eligible(host, state, sender_address)?;
+
+    // check time when bid arrives and auction still continue
+    let slot_time = ctx.metadata().slot_time();
+
+    ensure!(
+        slot_time >= state.start,
+        ContractError::AuctionNotStarted {
+            starts_at: state.start
+        }
+    );
+    ensure!(
+        slot_time <= state.end,
+        ContractError::BidTooLate {
+            ended_at: state.end
+        }
+    );
+
+    // a token auction only opens once the seller deposited the token
+    ensure!(
+        state.item_tokens.is_empty() || state.item_escrowed,
+        ContractError::ItemNotEscrowed
+    );
+
+    // sealed bids go through commit and reveal
+    ensure!(
+        !matches!(state.auction_type, AuctionType::SealedBid { .. }),
+        ContractError::SealedAuction
+    );
+    // lots are bid on as packages
+    ensure!(
+        state.auction_type != AuctionType::Combinatorial,
+        ContractError::CombinatorialAuction
+    );
+
+    // in a dutch auction the first bid paying the current price wins
+    if let AuctionType::Dutch(dutch) = state.auction_type {
+        let price = dutch.current_price(state.start, slot_time);
+        ensure!(amount >= price, ContractError::BelowCurrentPrice(price));
+
+        // the winner pays the current price, anything sent above it is refunded
+        state.highest_bidder = Some(sender_address);
+        state.highest_bid = price;
+        state.highest_currency = currency;
+        state.auction_state = AuctionState::Sold(sender_address);
+        state.record_bid(sender_address, price, slot_time, memo.clone());
+        host.state_mut().current_mut().log_event(
+            logger,
+            AuctionEvent::Bid(BidEvent {
+                bidder: sender_address,
+                amount: price,
+                new_end: state.end,
+                timestamp: slot_time,
+                memo,
+            }),
+        )?;
+        let excess = amount - price;
+        if excess > Amount::zero() {
+            state.credit_refund(sender_address, excess, currency);
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::RefundIssued {
+                    account: sender_address,
+                    amount: excess,
+                    reason: RefundReason::AboveCurrentPrice,
+                },
+            )?;
+        }
+        settle_instant_sale(ctx, host, state, price);
+        return Ok(());
+    }
+
+    let candle = matches!(state.auction_type, AuctionType::Candle { .. });
+    let multi_unit = state.quantity > 1;
+    let all_pay = state.all_pay;
+
+    // with a buyer's premium the amount sent covers the bid plus the premium
+    let bid_amount = without_premium(amount, state.buyer_premium_bps);
+    let premium = amount - bid_amount;
+
+    // with several units the bid to beat is the lowest one in the ledger, once
+    // every unit has a bid. Raising its own standing bid isn't outbidding
+    let ledger_full = state.ledger.len() >= state.quantity as usize;
+    let raises_own = standing > Amount::zero() && state.highest_bidder == Some(sender_address);
+    let outbidding = if multi_unit {
+        ledger_full
+    } else {
+        state.highest_bidder.is_some() && !raises_own
+    };
+
+    let highest_bid = if multi_unit {
+        match state.ledger.last() {
+            Some(lowest) if ledger_full => lowest.amount,
+            _ => Amount::zero(),
+        }
+    } else {
+        state.highest_bid
+    };
+
+    // the opening bid has to reach the minimum, each payment token has its own
+    let min_bid = state
+        .payment_token(currency)
+        .map_or(state.min_bid, |token| token.min_bid);
+    ensure!(
+        outbidding || bid_amount >= min_bid,
+        ContractError::BelowMinimum(min_bid)
+    );
+
+    if bid_amount <= highest_bid {
+        // anything above the highest bid, the increment is checked below
+        let minimum_required = highest_bid
+            .micro_ccd
+            .checked_add(1)
+            .map(Amount::from_micro_ccd)
+            .ok_or(ContractError::Overflow)?;
+        bail!(ContractError::BidTooLow { minimum_required });
+    }
+
+    // a bid reaching the price cap wins at the cap, the rest of it is refunded
+    let capped = state.price_cap.filter(|cap| bid_amount >= *cap);
+    let excess = capped.map_or(Amount::zero(), |cap| bid_amount - cap);
+    let bid_amount = capped.unwrap_or(bid_amount);
+
+    // a bid reaching the buy-now price wins regardless of the increment
+    let buys_now = capped.is_none()
+        && !candle
+        && !multi_unit
+        && !all_pay
+        && state.buy_now.is_some_and(|price| bid_amount >= price);
+
+    // outbidding has to raise the highest bid by the minimum increment
+    if !buys_now && capped.is_none() && outbidding {
+        let min_next_bid = state
+            .min_increment
+            .min_next_bid(highest_bid)
+            .ok_or(ContractError::Overflow)?;
+        ensure!(
+            bid_amount >= min_next_bid,
+            ContractError::IncrementTooSmall(min_next_bid)
+        );
+    }
+
+    // the bid is accepted, the standing bid it spends is no longer owed back
+    if standing > Amount::zero() {
+        state
+            .pending_refunds
+            .remove(&(sender_address, BASE_CURRENCY));
+        if raises_own {
+            state.highest_bidder = None;
+        }
+    }
+
+    if multi_unit {
+        // insert the bid in order, the lowest one drops out once all units are taken
+        let position = state
+            .ledger
+            .iter()
+            .position(|entry| entry.amount < bid_amount)
+            .unwrap_or(state.ledger.len());
+        state.ledger.insert(
+            position,
+            LedgerBid {
+                bidder: sender_address,
+                amount: bid_amount,
+            },
+        );
+        state.highest_bidder = state.ledger.first().map(|entry| entry.bidder);
+        state.highest_bid = state
+            .ledger
+            .first()
+            .map_or(Amount::zero(), |entry| entry.amount);
+        let dropped = if state.ledger.len() > state.quantity as usize {
+            state.ledger.pop()
+        } else {
+            None
+        };
+        if let Some(dropped) = dropped {
+            state.credit_refund(dropped.bidder, dropped.amount, BASE_CURRENCY);
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::RefundIssued {
+                    account: dropped.bidder,
+                    amount: dropped.amount,
+                    reason: RefundReason::Outbid,
+                },
+            )?;
+        }
+    } else {
+        let previous_bidder = state.highest_bidder.replace(sender_address);
+        let previous_bid = state.highest_bid;
+        let previous_premium = state.highest_premium;
+        let previous_currency = state.highest_currency;
+        state.highest_bid = bid_amount;
+        state.highest_premium = premium;
+        state.highest_currency = currency;
+        if candle {
+            // keep the bid, refunds are done at finalize once the real end is known
+            let index = state.candle_bid_count;
+            state.candle_bids.insert(
+                index,
+                CandleBid {
+                    bidder: sender_address,
+                    amount: bid_amount,
+                    timestamp: slot_time,
+                },
+            );
+            state.candle_bid_count += 1;
+        } else if all_pay {
+            // every bid is kept, nobody is refunded
+            state.total_raised += bid_amount;
+        } else if let Some(account_address) = previous_bidder {
+            // the outbid bidder collects the bid and premium with withdraw
+            let owed = previous_bid + previous_premium;
+            state.credit_refund(account_address, owed, previous_currency);
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::RefundIssued {
+                    account: account_address,
+                    amount: owed,
+                    reason: RefundReason::Outbid,
+                },
+            )?;
+        }
+    }
+
+    // buy-now and the cap sell the item without waiting for the end, the
+    // seller claims the proceeds
+    if buys_now || capped.is_some() {
+        state.auction_state = AuctionState::Sold(sender_address);
+        state.record_bid(sender_address, bid_amount, slot_time, memo.clone());
+        host.state_mut().current_mut().log_event(
+            logger,
+            AuctionEvent::Bid(BidEvent {
+                bidder: sender_address,
+                amount: bid_amount,
+                new_end: state.end,
+                timestamp: slot_time,
+                memo,
+            }),
+        )?;
+        if capped.is_some() {
+            if excess > Amount::zero() {
+                state.credit_refund(sender_address, excess, currency);
+                host.state_mut().current_mut().log_event(
+                    logger,
+                    AuctionEvent::RefundIssued {
+                        account: sender_address,
+                        amount: excess,
+                        reason: RefundReason::AbovePriceCap,
+                    },
+                )?;
+            }
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::InstantWin {
+                    winner: sender_address,
+                    price: bid_amount,
+                },
+            )?;
+        }
+        settle_instant_sale(ctx, host, state, bid_amount);
+        return Ok(());
+    }
+
+    // a bid within the extension window pushes the end forward
+    let remaining = state
+        .end
+        .duration_since(slot_time)
+        .unwrap_or(Duration::from_millis(0));
+    if remaining < state.extension_window {
+        let old_end = state.end;
+        state.end = old_end
+            .checked_add(state.extension_window)
+            .ok_or(ContractError::Overflow)?;
+        host.state_mut().current_mut().log_event(
+            logger,
+            AuctionEvent::AuctionExtended {
+                old_end,
+                new_end: state.end,
+                triggered_by: sender_address,
+            },
+        )?;
+    }
+    // bidding before the end is round 0, after it bidding goes on in rounds
+    // and closes at the end of the first round without a bid. The first bid
+    // in the latest round opens the next one.
+    if state.overtime_round > Duration::from_millis(0) {
+        let round_millis = state.overtime_round.millis();
+        let first_round_start =
+            state.end.timestamp_millis() - u64::from(state.overtime_rounds) * round_millis;
+        // a round takes bids up to and including its end
+        let round = slot_time
+            .timestamp_millis()
+            .saturating_sub(first_round_start)
+            .div_ceil(round_millis);
+        if round == u64::from(state.overtime_rounds) {
+            state.end = state
+                .end
+                .checked_add(state.overtime_round)
+                .ok_or(ContractError::Overflow)?;
+            state.overtime_rounds += 1;
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::OvertimeRound {
+                    round: state.overtime_rounds,
+                    ends_at: state.end,
+                    triggered_by: sender_address,
+                },
+            )?;
+        }
+    }
+    state.record_bid(sender_address, bid_amount, slot_time, memo.clone());
+    host.state_mut().current_mut().log_event(
+        logger,
+        AuctionEvent::Bid(BidEvent {
+            bidder: sender_address,
+            amount: bid_amount,
+            new_end: state.end,
+            timestamp: slot_time,
+            memo,
+        }),
+    )?;
+
+    Ok(())
+}
+
+// view function
+
+#[receive(
+    contract = "auction",
+    name = "view",
+    parameter = "u64",
+    return_value = "ViewState"
+)]
+fn view<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<ViewState> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    let slot_time = ctx.metadata().slot_time();
+    let phase = if state.auction_state != AuctionState::Continue || slot_time > state.end {
+        AuctionPhase::Ended
+    } else if slot_time < state.start {
+        AuctionPhase::Pending
+    } else {
+        AuctionPhase::Active
+    };
+    Ok(ViewState {
+        version: VIEW_STATE_VERSION,
+        auction_state: state.auction_state.clone(),
+        auction_type: state.auction_type,
+        highest_bidder: state.highest_bidder,
+        highest_bid: state.highest_bid,
+        item: state.item.get().clone(),
+        seller: state.seller,
+        start: state.start,
+        end: state.end,
+        phase,
+        whitelist_only: state.whitelist_only,
+        reserve: state.reserve,
+        min_bid: state.min_bid,
+        min_increment: state.min_increment,
+        buy_now: state.buy_now,
+        extension_window: state.extension_window,
+        quantity: state.quantity,
+        ledger: state.ledger.clone(),
+        uniform_price: state.uniform_price,
+        all_pay: state.all_pay,
+        total_raised: state.total_raised,
+        buyer_premium_bps: state.buyer_premium_bps,
+        item_tokens: state.item_tokens.clone(),
+        item_escrowed: state.item_escrowed,
+        payment_tokens: state.payment_tokens.clone(),
+        highest_currency: state.highest_currency,
+        time_remaining: state.time_remaining(slot_time),
+        lots: (0..state.lot_count)
+            .filter_map(|lot_id| state.lots.get(&lot_id).map(|lot| lot.clone()))
+            .collect(),
+    })
+}
+// view the item metadata
+#[receive(
+    contract = "auction",
+    name = "viewItem",
+    parameter = "u64",
+    return_value = "ItemMetadata"
+)]
+fn view_item<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<ItemMetadata> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    Ok(state.item.get().clone())
+}
+
+// view a page of auctions, so a listing doesn't need the whole state
+#[receive(
+    contract = "auction",
+    name = "viewAuctions",
+    parameter = "ViewAuctionsParameter",
+    return_value = "Vec<AuctionSummary>"
+)]
+fn view_auctions<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<AuctionSummary>> {
+    let param: ViewAuctionsParameter = ctx.parameter_cursor().get()?;
+    let state = host.state().current();
+    let limit = param.limit.min(MAX_PAGE_SIZE) as usize;
+    Ok((param.skip..state.next_auction_id)
+        .filter_map(|auction_id| {
+            state
+                .auctions
+                .get(&auction_id)
+                .map(|auction| AuctionSummary {
+                    auction_id,
+                    item: auction.item.get().clone(),
+                    highest_bid: auction.highest_bid,
+                    end: auction.end,
+                    auction_state: auction.auction_state.clone(),
+                })
+        })
+        .take(limit)
+        .collect())
+}
+
+// view the ids of the auctions listed by a seller
+#[receive(
+    contract = "auction",
+    name = "viewAuctionsBySeller",
+    parameter = "AccountAddress",
+    return_value = "Vec<u64>"
+)]
+fn view_auctions_by_seller<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<u64>> {
+    let seller: AccountAddress = ctx.parameter_cursor().get()?;
+    let mut auction_ids: Vec<u64> = host
+        .state()
+        .current()
+        .auctions_by_seller
+        .get(&seller)
+        .map_or(Vec::new(), |ids| ids.iter().map(|id| *id).collect());
+    // the set is ordered by the serialized id, not by value
+    auction_ids.sort_unstable();
+    Ok(auction_ids)
+}
+
+// view a page of the bids placed on the auction, including the losing ones
+#[receive(
+    contract = "auction",
+    name = "viewBidHistory",
+    parameter = "ViewBidHistoryParameter",
+    return_value = "Vec<BidRecord>"
+)]
+fn view_bid_history<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<BidRecord>> {
+    let param: ViewBidHistoryParameter = ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&param.auction_id)
+        .ok_or_else(Reject::default)?;
+    let limit = param.limit.min(MAX_PAGE_SIZE) as usize;
+    Ok((param.skip..state.bid_history_count)
+        .filter_map(|index| state.bid_history.get(&index).map(|bid| bid.clone()))
+        .take(limit)
+        .collect())
+}
+
+// view the bids the account placed in a page of auctions, oldest auction first
+#[receive(
+    contract = "auction",
+    name = "viewBidsBy",
+    parameter = "ViewBidsByParameter",
+    return_value = "Vec<(u64, BidRecord)>"
+)]
+fn view_bids_by<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<(u64, BidRecord)>> {
+    let param: ViewBidsByParameter = ctx.parameter_cursor().get()?;
+    let account = param.account;
+    let state = host.state().current();
+    let end = param
+        .skip
+        .saturating_add(u64::from(param.limit.min(MAX_PAGE_SIZE)))
+        .min(state.next_auction_id);
+    let mut bids = Vec::new();
+    for auction_id in param.skip..end {
+        let auction = match state.auctions.get(&auction_id) {
+            Some(auction) => auction,
+            None => continue,
+        };
+        let bidder_bids = auction
+            .bidder_bid_counts
+            .get(&account)
+            .map_or(0, |count| *count);
+        bids.extend((0..bidder_bids).filter_map(|n| {
+            let index = *auction.bids_by_bidder.get(&(account, n))?;
+            auction
+                .bid_history
+                .get(&index)
+                .map(|bid| (auction_id, bid.clone()))
+        }));
+    }
+    Ok(bids)
+}
+
+// view how much bidding the auction got
+#[receive(
+    contract = "auction",
+    name = "viewStats",
+    parameter = "u64",
+    return_value = "AuctionStats"
+)]
+fn view_stats<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<AuctionStats> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    Ok(AuctionStats {
+        bid_count: state.bid_history_count,
+        unique_bidders: state.unique_bidders,
+        total_volume: state.total_volume,
+    })
+}
+
+// view how every ended round of the auction went, oldest first. The round
+// still running isn't listed.
+#[receive(
+    contract = "auction",
+    name = "viewRounds",
+    parameter = "u64",
+    return_value = "Vec<RoundOutcome>"
+)]
+fn view_rounds<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<RoundOutcome>> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    Ok((1..=state.round)
+        .filter_map(|round| state.rounds.get(&round).map(|outcome| outcome.clone()))
+        .collect())
+}
+
+// view how long until the auction ends, from the current slot time
+#[receive(
+    contract = "auction",
+    name = "viewTimeRemaining",
+    parameter = "u64",
+    return_value = "TimeRemaining"
+)]
+fn view_time_remaining<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<TimeRemaining> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    Ok(state.time_remaining(ctx.metadata().slot_time()))
+}
+
+// view the smallest bid the auction accepts right now, none when it takes no
+// plain bids
+#[receive(
+    contract = "auction",
+    name = "viewMinimumNextBid",
+    parameter = "u64",
+    return_value = "Option<NextBid>"
+)]
+fn view_minimum_next_bid<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<Option<NextBid>> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    Ok(state.next_bid(ctx.metadata().slot_time())?)
+}
+
+// check whether a bid of the account for the amount would be accepted right
+// now, the verifier, credential and token gates aren't checked since a view
+// can't call other contracts
+#[receive(
+    contract = "auction",
+    name = "canBid",
+    parameter = "(u64, AccountAddress, Amount)",
+    return_value = "BidEligibility"
+)]
+fn can_bid<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<BidEligibility> {
+    let (auction_id, account, amount): (u64, AccountAddress, Amount) =
+        ctx.parameter_cursor().get()?;
+    let contract_state = host.state().current();
+    let state = contract_state
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    let slot_time = ctx.metadata().slot_time();
+    // same order as the checks in place_bid
+    let eligibility = if contract_state.paused {
+        BidEligibility::Paused
+    } else if state.auction_state != AuctionState::Continue {
+        BidEligibility::AuctionClosed
+    } else if !state.seller_can_bid && is_alias(&account, &state.seller) {
+        BidEligibility::SellerCannotBid
+    } else if state.blacklist.contains(&account) {
+        BidEligibility::Blacklisted
+    } else if state.whitelist_only && !state.whitelist.contains(&account) {
+        BidEligibility::NotWhitelisted
+    } else if slot_time < state.start {
+        BidEligibility::NotStarted
+    } else if slot_time > state.end {
+        BidEligibility::TooLate
+    } else {
+        match state.next_bid(slot_time)? {
+            None => BidEligibility::NoPlainBids,
+            Some(next_bid) if amount < next_bid.minimum => BidEligibility::TooLow(next_bid.minimum),
+            Some(_) => BidEligibility::Eligible,
+        }
+    };
+    Ok(eligibility)
+}
+
+// view the configuration of the auction, without the bids and the outcome
+#[receive(
+    contract = "auction",
+    name = "viewConfig",
+    parameter = "u64",
+    return_value = "AuctionConfig"
+)]
+fn view_config<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<AuctionConfig> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    Ok(AuctionConfig {
+        auction_type: state.auction_type,
+        item: state.item.get().clone(),
+        seller: state.seller,
+        start: state.start,
+        end: state.end,
+        round: state.round,
+        reserve: state.reserve,
+        min_bid: state.min_bid,
+        min_increment: state.min_increment,
+        buy_now: state.buy_now,
+        price_cap: state.price_cap,
+        extension_window: state.extension_window,
+        overtime_round: state.overtime_round,
+        settle_grace_period: state.settle_grace_period,
+        terms_hash: state.terms_hash,
+        aggregate_bids: state.aggregate_bids,
+        anonymous_bids: state.anonymous_bids,
+        keeper_bounty: state.keeper_bounty,
+        dispute_window: state.dispute_window,
+        arbitrator: state.arbitrator,
+        delivery_timeout: state.delivery_timeout,
+        payout_delay: state.payout_delay,
+        auto_relist: state.auto_relist,
+        quantity: state.quantity,
+        lots: (0..state.lot_count)
+            .filter_map(|lot_id| state.lots.get(&lot_id).map(|lot| lot.clone()))
+            .collect(),
+        fee_bps: state.fee_bps,
+        buyer_premium_bps: state.buyer_premium_bps,
+        royalty: state.royalty,
+        charity: state.charity,
+        payees: state.payees.clone(),
+        item_tokens: state.item_tokens.clone(),
+        payment_tokens: state.payment_tokens.clone(),
+        wccd: state.wccd,
+        verifier: state.verifier,
+        credential_registry: state.credential_registry,
+        token_gate: state.token_gate.clone(),
+        whitelist_only: state.whitelist_only,
+        uniform_price: state.uniform_price,
+        all_pay: state.all_pay,
+        fractional: state.fractional,
+        unwrap_proceeds: state.unwrap_proceeds,
+        seller_can_bid: state.seller_can_bid,
+    })
+}
+
+// standards the contract implements, reported by supports
+const SUPPORTS_STANDARDS: [StandardIdentifier<'static>; 2] = [
+    CIS0_STANDARD_IDENTIFIER,
+    StandardIdentifier::new_unchecked("CCD-AUCTION"),
+];
+
+// cis0, tell wallets and marketplaces which standards the contract implements
+#[receive(
+    contract = "auction",
+    name = "supports",
+    parameter = "SupportsQueryParams",
+    return_value = "SupportsQueryResponse"
+)]
+fn supports<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    _host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<SupportsQueryResponse> {
+    let params: SupportsQueryParams = ctx.parameter_cursor().get()?;
+    let response: Vec<SupportResult> = params
+        .queries
+        .iter()
+        .map(|id| {
+            if SUPPORTS_STANDARDS.contains(&id.as_standard_identifier()) {
+                SupportResult::Support
+            } else {
+                SupportResult::NoSupport
+            }
+        })
+        .collect();
+    Ok(SupportsQueryResponse::from(response))
+}
+
+// view the version of the contract code
+#[receive(contract = "auction", name = "version", return_value = "String")]
+fn version<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    _host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<String> {
+    Ok(env!("CARGO_PKG_VERSION").into())
+}
+
+// view highest bid
+#[receive(
+    contract = "auction",
+    name = "viewHighestBid",
+    parameter = "u64",
+    return_value = "Amount"
+)]
+fn view_highest_bid<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<Amount> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    Ok(state.highest_bid)
+}
+
+// view the live dutch auction price, none for english auctions
+#[receive(
+    contract = "auction",
+    name = "viewCurrentPrice",
+    parameter = "u64",
+    return_value = "Option<Amount>"
+)]
+fn view_current_price<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<Option<Amount>> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    match state.auction_type {
+        AuctionType::Dutch(dutch) => Ok(Some(
+            dutch.current_price(state.start, ctx.metadata().slot_time()),
+        )),
+        _ => Ok(None),
+    }
+}
+
+// settle the auction, records the winner and stops bidding. Nothing is paid
+// out here, so a failing transfer can't keep the auction open: the proceeds
+// are paid with claimProceeds and the highest bidder collects an escrowed
+// token with claimItem.
+
+#[receive(
+    contract = "auction",
+    name = "settle",
+    parameter = "u64",
+    error = "ContractError",
+    mutable,
+    enable_logger
+)]
+fn auction_settle<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), ContractError> {
+    ensure!(
+        !host.state().current().paused,
+        ContractError::ContractPaused
+    );
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        ContractError::UnknownAuction,
+        |host, state| settle_auction(ctx, host, state, logger, auction_id),
+    )
+}
+
+// what happened to one auction of a settleBatch
+#[derive(Debug, Serial, SchemaType, PartialEq, Eq)]
+enum BatchSettlement {
+    Settled,
+    Skipped(ContractError), // the auction can't be settled yet or anymore, e.g. it hasn't ended
+}
+
+// settle several auctions in one transaction, e.g. everything that ended
+// during the day. An auction that can't be settled is skipped and reported,
+// the others are still settled. Returns the outcome of each auction in order.
+#[receive(
+    contract = "auction",
+    name = "settleBatch",
+    parameter = "Vec<u64>",
+    return_value = "Vec<(u64, BatchSettlement)>",
+    error = "ContractError",
+    mutable,
+    enable_logger
+)]
+fn auction_settle_batch<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<Vec<(u64, BatchSettlement)>, ContractError> {
+    let state = host.state().current();
+    ensure!(!state.paused, ContractError::ContractPaused);
+    let finalizer = state.has_role(&ctx.sender(), Role::Finalizer);
+    let auction_ids: Vec<u64> = ctx.parameter_cursor().get()?;
+    let mut outcomes = Vec::with_capacity(auction_ids.len());
+    for auction_id in auction_ids {
+        // only the checks made before anything changes are skipped, a failure
+        // halfway through settling rejects the whole batch
+        let check = match host.state().current().auctions.get(&auction_id) {
+            Some(auction) => ensure_settleable(ctx, &auction, finalizer),
+            None => Err(ContractError::UnknownAuction),
+        };
+        if let Err(error) = check {
+            outcomes.push((auction_id, BatchSettlement::Skipped(error)));
+            continue;
+        }
+        with_auction(
+            host,
+            auction_id,
+            ContractError::UnknownAuction,
+            |host, state| settle_auction(ctx, host, state, logger, auction_id),
+        )?;
+        outcomes.push((auction_id, BatchSettlement::Settled));
+    }
+    Ok(outcomes)
+}
+
+// settle the auction, pay the keeper bounty and log the result
+fn settle_auction<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    logger: &mut impl HasLogger,
+    auction_id: u64,
+) -> Result<(), ContractError> {
+    let finalizer = host
+        .state()
+        .current()
+        .has_role(&ctx.sender(), Role::Finalizer);
+    // relisting settles the round too, so it waits for the same checks
+    ensure_settleable(ctx, state, finalizer)?;
+    if let Some(auto_relist) = state.auto_relist {
+        let reserve_met = state
+            .reserve
+            .is_none_or(|reserve| state.highest_bid >= reserve);
+        if state.relists_left > 0 && state.highest_bidder.is_some() && !reserve_met {
+            return relist_unmet_reserve(ctx, host, state, logger, auction_id, auto_relist);
+        }
+    }
+    finalize_auction(ctx, host, state, logger)?;
+    state.settled_at = Some(ctx.metadata().slot_time());
+    state.record_round(ctx.metadata().slot_time());
+    // a contract can't be paid, its bounty goes back to the seller
+    let keeper = match ctx.sender() {
+        Address::Account(account) => account,
+        Address::Contract(_) => state.seller,
+    };
+    pay_keeper_bounty(host, state, keeper);
+    log_finalized(host.state_mut().current_mut(), logger, auction_id, state)?;
+    Ok(())
+}
+
+// settle a round whose reserve wasn't met by reopening the auction for the
+// auto-relist period. The bid is refunded and the item stays escrowed.
+fn relist_unmet_reserve<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    logger: &mut impl HasLogger,
+    auction_id: u64,
+    auto_relist: AutoRelist,
+) -> Result<(), ContractError> {
+    let slot_time = ctx.metadata().slot_time();
+    let end = slot_time
+        .checked_add(auto_relist.period)
+        .ok_or(ContractError::Overflow)?;
+
+    state.auction_state = AuctionState::ReserveNotMet;
+    state.record_round(slot_time);
+    if let Some(bidder) = state.highest_bidder {
+        let owed = state.highest_bid + state.highest_premium;
+        let currency = state.highest_currency;
+        refund(ctx, host, state, bidder, owed, currency);
+        host.state_mut().current_mut().log_event(
+            logger,
+            AuctionEvent::RefundIssued {
+                account: bidder,
+                amount: owed,
+                reason: RefundReason::ReserveNotMet,
+            },
+        )?;
+    }
+    log_finalized(host.state_mut().current_mut(), logger, auction_id, state)?;
+    // whoever settled still earned the bounty
+    let keeper = match ctx.sender() {
+        Address::Account(account) => account,
+        Address::Contract(_) => state.seller,
+    };
+    pay_keeper_bounty(host, state, keeper);
+
+    let reserve = auto_relist.lowered_reserve.or(state.reserve);
+    state.reopen(slot_time, end, reserve);
+    state.relists_left -= 1;
+    let round = state.round;
+    host.state_mut().current_mut().log_event(
+        logger,
+        AuctionEvent::AuctionRelisted {
+            auction_id,
+            round,
+            end,
+        },
+    )?;
+    Ok(())
+}
+
+// check the sender can settle the auction now, changes nothing
+fn ensure_settleable<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    state: &Auction<S>,
+    finalizer: bool, // the sender holds the finalizer role
+) -> Result<(), ContractError> {
+    // ensure auction still continues
+    ensure_eq!(
+        state.auction_state,
+        AuctionState::Continue,
+        ContractError::AuctionAlreadyFinalized
+    );
+
+    let slot_time = ctx.metadata().slot_time();
+    // Ensure the auction has ended already
+    ensure!(
+        slot_time > state.end,
+        ContractError::AuctionStillActive { ends_at: state.end }
+    );
+
+    // a sealed-bid auction ends with its reveal phase
+    let ended_at = match state.auction_type {
+        AuctionType::SealedBid { reveal_end } => {
+            ensure!(
+                slot_time > reveal_end,
+                ContractError::AuctionStillActive {
+                    ends_at: reveal_end
+                }
+            );
+            reveal_end
+        }
+        _ => state.end,
+    };
+
+    ensure_grace_period_over(ctx, state, ended_at, finalizer)?;
+    ensure!(
+        !matches!(state.auction_type, AuctionType::Candle { .. }),
+        ContractError::CandleAuction
+    );
+    Ok(())
+}
+
+// the seller, the instance owner and finalizers get the first chance to settle
+fn ensure_grace_period_over<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    state: &Auction<S>,
+    ended_at: Timestamp,
+    finalizer: bool,
+) -> Result<(), ContractError> {
+    if let Some(open_at) = ended_at.checked_add(state.settle_grace_period) {
+        let sender = ctx.sender();
+        ensure!(
+            ctx.metadata().slot_time() > open_at
+                || finalizer
+                || sender.matches_account(&state.seller)
+                || sender.matches_account(&ctx.owner()),
+            ContractError::SellerGracePeriod { open_at }
+        );
+    }
+    Ok(())
+}
+
+// settle the auction depending on its type, once ensure_settleable passed
+fn finalize_auction<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), ContractError> {
+    if let AuctionType::SealedBid { .. } = state.auction_type {
+        return finalize_sealed(ctx, host, state);
+    }
+
+    if state.quantity > 1 {
+        return finalize_units(ctx, host, state);
+    }
+
+    if state.auction_type == AuctionType::Combinatorial {
+        return finalize_lots(ctx, host, state);
+    }
+
+    let reserve = state.reserve;
+    let all_pay = state.all_pay;
+    let highest_bid = state.highest_bid;
+    let highest_premium = state.highest_premium;
+    let highest_currency = state.highest_currency;
+    // an all-pay auction keeps every bid, not just the highest
+    let proceeds = if all_pay {
+        state.total_raised
+    } else {
+        highest_bid
+    };
+    if let Some(account_address) = state.highest_bidder {
+        if reserve.is_some_and(|reserve| highest_bid < reserve) {
+            // reserve not met, the seller takes an escrowed token back with
+            // reclaimItem
+            state.auction_state = AuctionState::ReserveNotMet;
+            if !all_pay {
+                // the bid goes back, all-pay bids are kept regardless
+                let owed = highest_bid + highest_premium;
+                refund(ctx, host, state, account_address, owed, highest_currency);
+                host.state_mut().current_mut().log_event(
+                    logger,
+                    AuctionEvent::RefundIssued {
+                        account: account_address,
+                        amount: owed,
+                        reason: RefundReason::ReserveNotMet,
+                    },
+                )?;
+                return Ok(());
+            }
+        } else {
+            // mark the auction end
+            state.auction_state = AuctionState::Sold(account_address);
+        }
+        // paid out with claimProceeds
+        state.unclaimed_proceeds = proceeds;
+        state.unclaimed_premium = highest_premium;
+    } else {
+        // nobody bid, the seller can take an escrowed token back
+        state.auction_state = AuctionState::NotSold;
+    }
+    Ok(())
+}
+
+// pay out the sale proceeds, the protocol fee goes to the fee receiver, the
+// royalty to the creator, the donation to the charity and the rest to the seller
+// or is split among the payees
+fn pay_proceeds<S: HasStateApi, E: From<TransferError> + From<LogError>>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    logger: &mut impl HasLogger,
+    proceeds: Amount,
+) -> Result<(), E> {
+    let fee = bps_of(proceeds, state.fee_bps);
+    let fee_receiver = host.state().current().fee_receiver;
+    let royalty = state.royalty;
+    let charity = state.charity;
+    let payees = state.payees.clone();
+    // paid in the currency of the winning bid
+    let currency = state.highest_currency;
+    if fee > Amount::zero() {
+        send_funds(ctx, host, state, &fee_receiver, fee, currency)?;
+    }
+
+    let mut remainder = proceeds - fee;
+    if let Some(royalty) = royalty {
+        let amount = bps_of(proceeds, royalty.bps);
+        if amount > Amount::zero() {
+            send_funds(ctx, host, state, &royalty.creator, amount, currency)?;
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::Royalty {
+                    creator: royalty.creator,
+                    amount,
+                },
+            )?;
+        }
+        remainder -= amount;
+    }
+    if let Some(charity) = charity {
+        let amount = bps_of(proceeds, charity.bps);
+        if amount > Amount::zero() {
+            send_funds(ctx, host, state, &charity.account, amount, currency)?;
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::Donated {
+                    charity: charity.account,
+                    amount,
+                },
+            )?;
+        }
+        remainder -= amount;
+    }
+
+    if payees.is_empty() {
+        let seller = state.seller;
+        pay_seller(ctx, host, state, &seller, remainder, currency)?;
+        return Ok(());
+    }
+    // the last payee also gets whatever rounding leaves over
+    let mut paid = Amount::zero();
+    for (index, (payee, share_bps)) in payees.iter().enumerate() {
+        let share = if index + 1 == payees.len() {
+            remainder - paid
+        } else {
+            bps_of(remainder, *share_bps)
+        };
+        if share > Amount::zero() {
+            pay_seller(ctx, host, state, payee, share, currency)?;
+        }
+        paid += share;
+    }
+    Ok(())
+}
+
+// pay out the proceeds recorded at settlement: the fee, royalty and donation,
+// the seller or payees, and the winner's premium. The payouts go to fixed
+// accounts so anyone can trigger them.
+#[receive(
+    contract = "auction",
+    name = "claimProceeds",
+    parameter = "u64",
+    mutable,
+    enable_logger
+)]
+fn auction_claim_proceeds<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), ProceedsClaimError> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        ProceedsClaimError::UnknownAuction,
+        |host, state| release_proceeds(ctx, host, state, logger),
+    )
+}
+
+// pay out the unclaimed proceeds and premium, unless the payout delay, a
+// dispute or the delivery holds them. The winner's confirmation ends the dispute
+// window and the wait for the delivery.
+fn release_proceeds<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), ProceedsClaimError> {
+    let proceeds = state.unclaimed_proceeds;
+    let premium = state.unclaimed_premium;
+    ensure!(
+        proceeds > Amount::zero() || premium > Amount::zero(),
+        ProceedsClaimError::NothingToClaim
+    );
+    ensure!(state.disputant.is_none(), ProceedsClaimError::Disputed);
+    let slot_time = ctx.metadata().slot_time();
+    // the payout delay holds even once the delivery is confirmed
+    if let Some(unlocks_at) = state.payout_unlocks_at() {
+        ensure!(
+            slot_time >= unlocks_at,
+            ProceedsClaimError::PayoutLocked { unlocks_at }
+        );
+    }
+    if !state.delivery_confirmed {
+        if let Some(closes_at) = state.dispute_closes_at() {
+            ensure!(
+                slot_time >= closes_at,
+                ProceedsClaimError::DisputeWindow { closes_at }
+            );
+        }
+        if let Some(release_at) = state.delivery_release_at() {
+            ensure!(
+                slot_time >= release_at,
+                ProceedsClaimError::DeliveryPending { release_at }
+            );
+        }
+    }
+    if proceeds > Amount::zero() {
+        pay_proceeds::<S, ProceedsClaimError>(ctx, host, state, logger, proceeds)?;
+    }
+    if premium > Amount::zero() {
+        let fee_receiver = host.state().current().fee_receiver;
+        let currency = state.highest_currency;
+        send_funds(ctx, host, state, &fee_receiver, premium, currency)?;
+    }
+    // only cleared once every payout went through, a failed one leaves the
+    // proceeds owed
+    state.unclaimed_proceeds = Amount::zero();
+    state.unclaimed_premium = Amount::zero();
+    Ok(())
+}
+
+// confirm the item arrived, which releases the proceeds to the seller right
+// away. Only the winner can do this, without it the proceeds are released
+// once the delivery timeout is over.
+#[receive(
+    contract = "auction",
+    name = "confirmDelivery",
+    parameter = "u64",
+    error = "ProceedsClaimError",
+    mutable,
+    enable_logger
+)]
+fn auction_confirm_delivery<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), ProceedsClaimError> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        ProceedsClaimError::UnknownAuction,
+        |host, state| {
+            let winner = match ctx.sender() {
+                Address::Account(account) if state.is_winner(account) => account,
+                _ => bail!(ProceedsClaimError::NotWinner),
+            };
+            state.delivery_confirmed = true;
+            release_proceeds(ctx, host, state, logger)?;
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::DeliveryConfirmed { auction_id, winner },
+            )?;
+            Ok(())
+        },
+    )
+}
+
+// dispute the sale while the proceeds are in escrow, only a winner can do this.
+// The proceeds stay frozen until the dispute is closed.
+#[receive(
+    contract = "auction",
+    name = "openDispute",
+    parameter = "u64",
+    error = "DisputeError",
+    mutable,
+    enable_logger
+)]
+fn auction_open_dispute<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), DisputeError> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        DisputeError::UnknownAuction,
+        |host, state| {
+            let winner = match ctx.sender() {
+                Address::Account(account) if state.is_winner(account) => account,
+                _ => bail!(DisputeError::NotWinner),
+            };
+            ensure!(state.disputant.is_none(), DisputeError::AlreadyDisputed);
+            let in_window = state
+                .dispute_closes_at()
+                .is_some_and(|closes_at| ctx.metadata().slot_time() < closes_at);
+            ensure!(
+                in_window && state.unclaimed_proceeds > Amount::zero(),
+                DisputeError::WindowClosed
+            );
+            state.disputant = Some(winner);
+            host.state_mut()
+                .current_mut()
+                .log_event(logger, AuctionEvent::DisputeOpened { auction_id, winner })?;
+            Ok(())
+        },
+    )
+}
+
+// drop the dispute, only the winner who opened it can do this
+#[receive(
+    contract = "auction",
+    name = "closeDispute",
+    parameter = "u64",
+    error = "DisputeError",
+    mutable,
+    enable_logger
+)]
+fn auction_close_dispute<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), DisputeError> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        DisputeError::UnknownAuction,
+        |host, state| {
+            let disputant = state.disputant.ok_or(DisputeError::NotDisputed)?;
+            ensure!(
+                ctx.sender().matches_account(&disputant),
+                DisputeError::NotWinner
+            );
+            state.disputant = None;
+            host.state_mut()
+                .current_mut()
+                .log_event(logger, AuctionEvent::DisputeClosed { auction_id })?;
+            Ok(())
+        },
+    )
+}
+
+// split the disputed proceeds, the winner gets back the given basis points of
+// the proceeds and premium and the rest is paid out to the seller as usual.
+// Only the auction's arbitrator can do this.
+#[receive(
+    contract = "auction",
+    name = "resolveDispute",
+    parameter = "(u64, u64)",
+    error = "DisputeError",
+    mutable,
+    enable_logger
+)]
+fn auction_resolve_dispute<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), DisputeError> {
+    let (auction_id, winner_bps): (u64, u64) = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        DisputeError::UnknownAuction,
+        |host, state| {
+            let arbitrator = match (state.arbitrator, ctx.sender()) {
+                (Some(arbitrator), sender) if sender.matches_account(&arbitrator) => arbitrator,
+                _ => bail!(DisputeError::NotArbitrator),
+            };
+            let winner = state.disputant.ok_or(DisputeError::NotDisputed)?;
+            ensure!(winner_bps <= 10_000, DisputeError::InvalidShare);
+
+            let proceeds = state.unclaimed_proceeds;
+            let premium = state.unclaimed_premium;
+            let winner_amount = bps_of(proceeds, winner_bps);
+            let winner_premium = bps_of(premium, winner_bps);
+            let seller_amount = proceeds - winner_amount;
+            state.disputant = None;
+            state.unclaimed_proceeds = Amount::zero();
+            state.unclaimed_premium = Amount::zero();
+
+            let currency = state.highest_currency;
+            // a winner that can't be paid withdraws its share later
+            let refunded = winner_amount + winner_premium;
+            if refunded > Amount::zero() {
+                refund(ctx, host, state, winner, refunded, currency);
+            }
+            if seller_amount > Amount::zero() {
+                pay_proceeds::<S, DisputeError>(ctx, host, state, logger, seller_amount)?;
+            }
+            let fee_receiver = host.state().current().fee_receiver;
+            if premium > winner_premium {
+                send_funds(
+                    ctx,
+                    host,
+                    state,
+                    &fee_receiver,
+                    premium - winner_premium,
+                    currency,
+                )?;
+            }
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::DisputeResolved {
+                    auction_id,
+                    arbitrator,
+                    winner,
+                    winner_bps,
+                    winner_amount: refunded,
+                    seller_amount,
+                },
+            )?;
+            Ok(())
+        },
+    )
+}
+
+// the winner collects the escrowed token once the auction is finalized
+#[receive(contract = "auction", name = "claimItem", parameter = "u64", mutable)]
+fn auction_claim_item<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), ItemClaimError> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        ItemClaimError::UnknownAuction,
+        |host, state| {
+            if state.fractional {
+                return claim_fraction(ctx, host, state);
+            }
+            let winner = match state.auction_state {
+                AuctionState::Sold(winner) => winner,
+                _ => bail!(ItemClaimError::NotWinner),
+            };
+            ensure!(
+                ctx.sender().matches_account(&winner),
+                ItemClaimError::NotWinner
+            );
+            send_item(ctx, host, state, winner)
+        },
+    )
+}
+
+// a winner of a fractional sale collects their share of the item token
+fn claim_fraction<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+) -> Result<(), ItemClaimError> {
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(ItemClaimError::NotWinner),
+        Address::Account(account_address) => account_address,
+    };
+    let share = state
+        .fraction_shares
+        .remove_and_get(&sender_address)
+        .ok_or(ItemClaimError::NotWinner)?;
+    let token = TokenItem {
+        amount: share,
+        ..state.item_tokens[0].clone()
+    };
+    transfer_token(ctx, host, token, sender_address)
+}
+
+// the seller takes the escrowed token back when the auction ended unsold
+#[receive(contract = "auction", name = "reclaimItem", parameter = "u64", mutable)]
+fn auction_reclaim_item<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), ItemClaimError> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        ItemClaimError::UnknownAuction,
+        |host, state| {
+            let seller = state.seller;
+            ensure!(
+                ctx.sender().matches_account(&seller),
+                ItemClaimError::OnlySeller
+            );
+            ensure!(
+                matches!(
+                    state.auction_state,
+                    AuctionState::NotSold | AuctionState::Cancelled | AuctionState::ReserveNotMet
+                ),
+                ItemClaimError::NotUnsold
+            );
+            send_item(ctx, host, state, seller)
+        },
+    )
+}
+
+// transfer the escrowed tokens out of the contract, pulled by the receiver so
+// a misbehaving token contract can't make finalize fail. An unsold bundle
+// returns whatever part of it was deposited.
+fn send_item<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    to: AccountAddress,
+) -> Result<(), ItemClaimError> {
+    let tokens: Vec<TokenItem> = state
+        .item_tokens
+        .iter()
+        .zip(state.tokens_deposited.iter())
+        .filter(|(_, deposited)| **deposited)
+        .map(|(token, _)| token.clone())
+        .collect();
+    ensure!(!tokens.is_empty(), ItemClaimError::NotEscrowed);
+    for token in tokens {
+        transfer_token(ctx, host, token, to)?;
+    }
+    state
+        .tokens_deposited
+        .iter_mut()
+        .for_each(|deposited| *deposited = false);
+    state.item_escrowed = false;
+    Ok(())
+}
+
+// transfer an amount of a token the contract holds
+fn transfer_token<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    token: TokenItem,
+    to: AccountAddress,
+) -> Result<(), ItemClaimError> {
+    let transfer = Transfer {
+        token_id: token.token_id,
+        amount: token.amount,
+        from: Address::Contract(ctx.self_address()),
+        to: Receiver::from_account(to),
+        data: AdditionalData::empty(),
+    };
+    host.invoke_contract(
+        &token.contract,
+        &TransferParams(vec![transfer]),
+        EntrypointName::new_unchecked("transfer"),
+        Amount::zero(),
+    )?;
+    Ok(())
+}
+
+// settle a sale made by a single bid, a dutch bid at the price or buy-now
+fn settle_instant_sale<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    proceeds: Amount,
+) {
+    state.settled_at = Some(ctx.metadata().slot_time());
+    state.record_round(ctx.metadata().slot_time());
+    // paid out with claimProceeds like any other sale, so a failing payout
+    // can't reject the winning bid
+    state.unclaimed_proceeds = proceeds;
+    state.unclaimed_premium = state.highest_premium;
+    // nobody settles a sale made by a bid, the bounty goes back
+    let seller = state.seller;
+    pay_keeper_bounty(host, state, seller);
+}
+
+// send an amount in the given currency, ccd or one of the payment tokens
+fn send_funds<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    to: &AccountAddress,
+    amount: Amount,
+    currency: u32,
+) -> Result<(), TransferError> {
+    let token = match state.payment_token(currency).cloned() {
+        Some(token) => token,
+        None => return host.invoke_transfer(to, amount),
+    };
+    let transfer = Transfer {
+        token_id: token.token_id,
+        amount: TokenAmountU64(amount.micro_ccd),
+        from: Address::Contract(ctx.self_address()),
+        to: Receiver::from_account(*to),
+        data: AdditionalData::empty(),
+    };
+    host.invoke_contract(
+        &token.contract,
+        &TransferParams(vec![transfer]),
+        EntrypointName::new_unchecked("transfer"),
+        Amount::zero(),
+    )
+    // callers only need to know the payment didn't go through
+    .map_err(|_| TransferError::MissingAccount)?;
+    Ok(())
+}
+
+// pay the seller's share, wccd is unwrapped to ccd first when configured
+fn pay_seller<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    to: &AccountAddress,
+    amount: Amount,
+    currency: u32,
+) -> Result<(), TransferError> {
+    let wccd = match state.wccd {
+        Some(wccd) if state.unwrap_proceeds => wccd,
+        _ => return send_funds(ctx, host, state, to, amount, currency),
+    };
+    let pays_wccd = state
+        .payment_token(currency)
+        .is_some_and(|token| token.contract == wccd);
+    if !pays_wccd {
+        return send_funds(ctx, host, state, to, amount, currency);
+    }
+    let params = UnwrapParams {
+        amount: TokenAmountU64(amount.micro_ccd),
+        owner: Address::Contract(ctx.self_address()),
+        receiver: Receiver::from_account(*to),
+        data: AdditionalData::empty(),
+    };
+    host.invoke_contract(
+        &wccd,
+        &params,
+        EntrypointName::new_unchecked("unwrap"),
+        Amount::zero(),
+    )
+    // callers only need to know the payment didn't go through
+    .map_err(|_| TransferError::MissingAccount)?;
+    Ok(())
+}
+
+// pay the keeper bounty out once, a failed transfer is added to the account's
+// deposit instead so it can't block the caller
+fn pay_keeper_bounty<S: HasStateApi>(
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    to: AccountAddress,
+) {
+    let bounty = state.keeper_bounty;
+    if bounty == Amount::zero() {
+        return;
+    }
+    state.keeper_bounty = Amount::zero();
+    if host.invoke_transfer(&to, bounty).is_err() {
+        let deposits = &mut host.state_mut().current_mut().deposits;
+        let balance = deposits.get(&to).map_or(Amount::zero(), |balance| *balance);
+        deposits.insert(to, balance + bounty);
+    }
+}
+
+// send a refund, if the transfer fails the amount is credited to pending
+// refunds so the account can withdraw it later instead of failing the call
+fn refund<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    account: AccountAddress,
+    amount: Amount,
+    currency: u32,
+) {
+    if send_funds(ctx, host, state, &account, amount, currency).is_err() {
+        state.credit_refund(account, amount, currency);
+    }
+}
+
+// settle a sealed-bid auction, the highest valid reveal wins and every other
+// deposit is refunded
+fn finalize_sealed<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+) -> Result<(), ContractError> {
+    let commitments: Vec<(AccountAddress, Commitment)> = state
+        .commitments
+        .iter()
+        .map(|(account, commitment)| (*account, commitment.clone()))
+        .collect();
+
+    let reserve = state.reserve;
+    // the earlier commit wins a tie
+    let winner = commitments
+        .iter()
+        .filter_map(|(account, commitment)| {
+            commitment
+                .revealed
+                .map(|bid| (*account, bid, commitment.order))
+        })
+        .max_by_key(|(_, bid, order)| (*bid, Reverse(*order)))
+        .map(|(account, bid, _)| (account, bid))
+        .filter(|(_, bid)| reserve.is_none_or(|reserve| *bid >= reserve));
+
+    match winner {
+        Some((account, bid)) => {
+            state.highest_bidder = Some(account);
+            state.highest_bid = bid;
+            state.auction_state = AuctionState::Sold(account);
+        }
+        None => state.auction_state = AuctionState::NotSold,
+    }
+
+    for (account, commitment) in commitments {
+        let owed = match winner {
+            Some((winner, bid)) if winner == account => commitment.deposit - bid,
+            _ => commitment.deposit,
+        };
+        if owed > Amount::zero() {
+            refund(ctx, host, state, account, owed, BASE_CURRENCY);
+        }
+    }
+
+    // blinded bids nobody revealed go back to whoever paid the deposit
+    let blinded: Vec<(HashSha2256, BlindedCommitment)> = state
+        .blinded_commitments
+        .iter()
+        .map(|(hash, blinded)| (*hash, blinded.clone()))
+        .collect();
+    for (hash, blinded) in blinded {
+        state.blinded_commitments.remove(&hash);
+        if blinded.deposit > Amount::zero() {
+            refund(
+                ctx,
+                host,
+                state,
+                blinded.depositor,
+                blinded.deposit,
+                BASE_CURRENCY,
+            );
+        }
+    }
+
+    if let Some((_, bid)) = winner {
+        state.unclaimed_proceeds = bid;
+    }
+    Ok(())
+}
+
+// settle a multi-unit auction, every bid in the ledger that meets the reserve
+// wins a unit and the rest is refunded. Winners pay their own bid, or with a
+// uniform price the lowest winning bid with the difference refunded.
+fn finalize_units<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+) -> Result<(), ContractError> {
+    let reserve = state.reserve;
+    let uniform_price = state.uniform_price;
+    let (winners, losers): (Vec<LedgerBid>, Vec<LedgerBid>) = state
+        .ledger
+        .iter()
+        .copied()
+        .partition(|entry| reserve.is_none_or(|reserve| entry.amount >= reserve));
+    state.auction_state = if winners.is_empty() {
+        AuctionState::NotSold
+    } else {
+        AuctionState::SoldUnits(winners.iter().map(|entry| entry.bidder).collect())
+    };
+
+    // in a fractional sale each winner gets a share of the supply in proportion
+    // to the bid, the last one also gets what rounding leaves over
+    if state.fractional && !winners.is_empty() {
+        let supply = state.item_tokens[0].amount.0;
+        let total: u128 = winners
+            .iter()
+            .map(|entry| u128::from(entry.amount.micro_ccd))
+            .sum();
+        let mut assigned = 0u64;
+        for (index, winner) in winners.iter().enumerate() {
+            let share = if index + 1 == winners.len() {
+                supply - assigned
+            } else {
+                (u128::from(supply) * u128::from(winner.amount.micro_ccd) / total) as u64
+            };
+            let owed = state
+                .fraction_shares
+                .get(&winner.bidder)
+                .map_or(0, |owed| owed.0);
+            state
+                .fraction_shares
+                .insert(winner.bidder, TokenAmountU64(owed + share));
+            assigned += share;
+        }
+    }
+
+    for loser in losers {
+        refund(ctx, host, state, loser.bidder, loser.amount, BASE_CURRENCY);
+    }
+
+    let clearing_price = winners.last().map(|lowest| lowest.amount);
+    let mut proceeds = Amount::zero();
+    for winner in winners.iter() {
+        let price = match clearing_price {
+            Some(clearing_price) if uniform_price => clearing_price,
+            _ => winner.amount,
+        };
+        if winner.amount > price {
+            refund(
+                ctx,
+                host,
+                state,
+                winner.bidder,
+                winner.amount - price,
+                BASE_CURRENCY,
+            );
+        }
+        proceeds += price;
+    }
+    state.unclaimed_proceeds = proceeds;
+    Ok(())
+}
+
+// pick the package bids with the highest total amount that don't share lots.
+// `revenue[mask]` is the best revenue from the lots not in `mask`, found by
+// either leaving its lowest free lot unsold or selling it with one of the bids
+// containing it. Returns the indices of the winning bids.
+fn best_allocation(bids: &[PackageBid], lot_count: u32) -> Vec<usize> {
+    let full = (1u32 << lot_count) - 1;
+    let lowest_free_lot = |mask: u32| 1u32 << (!mask & full).trailing_zeros();
+    let mut revenue = vec![0u64; full as usize + 1];
+    let mut choice: Vec<Option<usize>> = vec![None; full as usize + 1];
+    for mask in (0..full).rev() {
+        let lot = lowest_free_lot(mask);
+        let mut best = revenue[(mask | lot) as usize];
+        let mut best_choice = None;
+        for (index, bid) in bids.iter().enumerate() {
+            if bid.lots & lot != 0 && bid.lots & mask == 0 {
+                let total = bid
+                    .amount
+                    .micro_ccd
+                    .saturating_add(revenue[(mask | bid.lots) as usize]);
+                if total > best {
+                    best = total;
+                    best_choice = Some(index);
+                }
+            }
+        }
+        revenue[mask as usize] = best;
+        choice[mask as usize] = best_choice;
+    }
+
+    let mut winners = Vec::new();
+    let mut mask = 0u32;
+    while mask != full {
+        match choice[mask as usize] {
+            Some(index) => {
+                winners.push(index);
+                mask |= bids[index].lots;
+            }
+            None => mask |= lowest_free_lot(mask),
+        }
+    }
+    winners
+}
+
+// settle a combinatorial auction, the revenue maximizing set of package bids
+// wins and every other bid is refunded
+fn finalize_lots<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+) -> Result<(), ContractError> {
+    let mut indexed_bids: Vec<(u32, PackageBid)> = state
+        .package_bids
+        .iter()
+        .map(|(index, bid)| (*index, bid.clone()))
+        .collect();
+    // earlier bids win ties
+    indexed_bids.sort_by_key(|(index, _)| *index);
+    let bids: Vec<PackageBid> = indexed_bids.into_iter().map(|(_, bid)| bid).collect();
+
+    let mut winners = best_allocation(&bids, state.lot_count);
+    let revenue = winners
+        .iter()
+        .fold(Amount::zero(), |sum, index| sum + bids[*index].amount);
+    if state.reserve.is_some_and(|reserve| revenue < reserve) {
+        winners.clear();
+    }
+
+    state.auction_state = if winners.is_empty() {
+        AuctionState::NotSold
+    } else {
+        AuctionState::SoldLots(
+            winners
+                .iter()
+                .map(|index| PackageAward {
+                    bidder: bids[*index].bidder,
+                    lots: (0..MAX_LOTS)
+                        .filter(|lot| bids[*index].lots & (1 << lot) != 0)
+                        .collect(),
+                    price: bids[*index].amount,
+                })
+                .collect(),
+        )
+    };
+
+    let mut won = vec![false; bids.len()];
+    for index in &winners {
+        won[*index] = true;
+    }
+    let mut proceeds = Amount::zero();
+    for (index, bid) in bids.iter().enumerate() {
+        if won[index] {
+            proceeds += bid.amount;
+        } else {
+            refund(ctx, host, state, bid.bidder, bid.amount, BASE_CURRENCY);
+        }
+    }
+    state.unclaimed_proceeds = proceeds;
+    Ok(())
+}
+
+// bid on a package of lots in a combinatorial auction, the parameter is the
+// list of lot ids and the sent amount is the bid for all of them together
+#[receive(
+    contract = "auction",
+    name = "bidPackage",
+    parameter = "(u64, Vec<u32>)",
+    payable,
+    mutable,
+    enable_logger
+)]
+fn auction_bid_package<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+) -> Result<(), ContractError> {
+    ensure!(
+        !host.state().current().paused,
+        ContractError::ContractPaused
+    );
+    let mut cursor = ctx.parameter_cursor();
+    let auction_id: u64 = cursor.get()?;
+    with_auction(
+        host,
+        auction_id,
+        ContractError::UnknownAuction,
+        |host, state| {
+            ensure!(
+                state.auction_type == AuctionType::Combinatorial,
+                ContractError::NotCombinatorial
+            );
+            ensure_eq!(
+                state.auction_state,
+                AuctionState::Continue,
+                ContractError::AuctionFinalizedButBidded
+            );
+
+            let slot_time = ctx.metadata().slot_time();
+            ensure!(
+                slot_time >= state.start,
+                ContractError::AuctionNotStarted {
+                    starts_at: state.start
+                }
+            );
+            ensure!(
+                slot_time <= state.end,
+                ContractError::BidTooLate {
+                    ended_at: state.end
+                }
+            );
+
+            let sender_address = match ctx.sender() {
+                Address::Contract(_) => bail!(ContractError::OnlyAccount),
+                Address::Account(account_address) => account_address,
+            };
+            ensure_eligible(host, state, sender_address)?;
+            ensure!(
+                state.item_tokens.is_empty() || state.item_escrowed,
+                ContractError::ItemNotEscrowed
+            );
+
+            let lot_ids: Vec<u32> = cursor.get()?;
+            let mut lots = 0u32;
+            for lot_id in lot_ids {
+                ensure!(lot_id < state.lot_count, ContractError::UnknownLot);
+                lots |= 1 << lot_id;
+            }
+            ensure!(lots != 0, ContractError::UnknownLot);
+
+            let min_bid = state.min_bid;
+            ensure!(
+                amount > Amount::zero() && amount >= min_bid,
+                ContractError::BelowMinimum(min_bid)
+            );
+
+            // only the best bid on a package can win, so a package keeps one
+            // bid and a new one has to beat it
+            let mut packages = 0;
+            let mut standing = None;
+            for (index, bid) in state.package_bids.iter() {
+                packages += 1;
+                if bid.lots == lots {
+                    standing = Some((*index, bid.clone()));
+                }
+            }
+            match standing {
+                Some((index, bid)) => {
+                    let minimum_required = bid
+                        .amount
+                        .micro_ccd
+                        .checked_add(1)
+                        .map(Amount::from_micro_ccd)
+                        .ok_or(ContractError::Overflow)?;
+                    ensure!(
+                        amount >= minimum_required,
+                        ContractError::BidTooLow { minimum_required }
+                    );
+                    state.package_bids.remove(&index);
+                    state.credit_refund(bid.bidder, bid.amount, BASE_CURRENCY);
+                    host.state_mut().current_mut().log_event(
+                        logger,
+                        AuctionEvent::RefundIssued {
+                            account: bid.bidder,
+                            amount: bid.amount,
+                            reason: RefundReason::Outbid,
+                        },
+                    )?;
+                }
+                None => ensure!(packages < MAX_PACKAGE_BIDS, ContractError::TooManyPackages),
+            }
+
+            let index = state.package_bid_count;
+            state.package_bids.insert(
+                index,
+                PackageBid {
+                    bidder: sender_address,
+                    lots,
+                    amount,
+                },
+            );
+            state.package_bid_count += 1;
+            state.record_bid(sender_address, amount, slot_time, Vec::new());
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::PackageBidPlaced {
+                    auction_id,
+                    bidder: sender_address,
+                    lots,
+                    amount,
+                },
+            )?;
+            Ok(())
+        },
+    )
+}
+
+// finalize a candle auction by revealing the secret committed at init. The
+// secret and the time of finalizing decide the effective end within the
+// closing window, the highest bid placed before that wins and every other bid
+// is refunded.
+#[receive(
+    contract = "auction",
+    name = "finalizeCandle",
+    parameter = "(u64, Vec<u8>)",
+    mutable,
+    enable_logger,
+    crypto_primitives
+)]
+fn auction_finalize_candle<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), ContractError> {
+    ensure!(
+        !host.state().current().paused,
+        ContractError::ContractPaused
+    );
+    let mut cursor = ctx.parameter_cursor();
+    let auction_id: u64 = cursor.get()?;
+    with_auction(
+        host,
+        auction_id,
+        ContractError::UnknownAuction,
+        |host, state| {
+            ensure_eq!(
+                state.auction_state,
+                AuctionState::Continue,
+                ContractError::AuctionAlreadyFinalized
+            );
+            let (closing_window, seed_hash) = match state.auction_type {
+                AuctionType::Candle {
+                    closing_window,
+                    seed_hash,
+                } => (closing_window, seed_hash),
+                _ => bail!(ContractError::NotCandleAuction),
+            };
+            let slot_time = ctx.metadata().slot_time();
+            ensure!(
+                slot_time > state.end,
+                ContractError::AuctionStillActive { ends_at: state.end }
+            );
+            let finalizer = host
+                .state()
+                .current()
+                .has_role(&ctx.sender(), Role::Finalizer);
+            ensure_grace_period_over(ctx, state, state.end, finalizer)?;
+
+            let secret: Vec<u8> = cursor.get()?;
+            ensure_eq!(
+                crypto_primitives.hash_sha2_256(&secret),
+                seed_hash,
+                ContractError::InvalidSecret
+            );
+
+            // the seed hash is public, so the randomness is derived from the
+            // secret, mixed with the time of finalizing the seller couldn't know
+            // when committing to it
+            let random = crypto_primitives.hash_sha2_256(
+                &[
+                    secret.as_slice(),
+                    b"candle",
+                    &slot_time.timestamp_millis().to_le_bytes(),
+                ]
+                .concat(),
+            );
+            let mut random_bytes = [0u8; 8];
+            random_bytes.copy_from_slice(&random.0[..8]);
+            let offset = u64::from_le_bytes(random_bytes) % (closing_window.millis() + 1);
+            let window_start = state
+                .end
+                .timestamp_millis()
+                .saturating_sub(closing_window.millis());
+            let effective_end = Timestamp::from_timestamp_millis(window_start + offset);
+
+            let bids: Vec<CandleBid> = state.candle_bids.iter().map(|(_, bid)| *bid).collect();
+            // bids only go up, so the largest one before the effective end was the highest
+            let reserve = state.reserve;
+            let winner = bids
+                .iter()
+                .enumerate()
+                .filter(|(_, bid)| bid.timestamp <= effective_end)
+                .max_by_key(|(_, bid)| bid.amount)
+                .filter(|(_, bid)| reserve.is_none_or(|reserve| bid.amount >= reserve))
+                .map(|(index, bid)| (index, *bid));
+
+            match winner {
+                Some((_, bid)) => {
+                    state.highest_bidder = Some(bid.bidder);
+                    state.highest_bid = bid.amount;
+                    state.auction_state = AuctionState::Sold(bid.bidder);
+                }
+                None => {
+                    state.highest_bidder = None;
+                    state.highest_bid = Amount::zero();
+                    state.auction_state = AuctionState::NotSold;
+                }
+            }
+
+            for (index, bid) in bids.iter().enumerate() {
+                if winner.is_none_or(|(winning_index, _)| winning_index != index) {
+                    refund(ctx, host, state, bid.bidder, bid.amount, BASE_CURRENCY);
+                }
+            }
+
+            if let Some((_, bid)) = winner {
+                state.unclaimed_proceeds = bid.amount;
+            }
+            state.settled_at = Some(ctx.metadata().slot_time());
+            state.record_round(ctx.metadata().slot_time());
+            // a contract can't be paid, its bounty goes back to the seller
+            let keeper = match ctx.sender() {
+                Address::Account(account) => account,
+                Address::Contract(_) => state.seller,
+            };
+            pay_keeper_bounty(host, state, keeper);
+            log_finalized(host.state_mut().current_mut(), logger, auction_id, state)?;
+            Ok(())
+        },
+    )
+}
+
+// close a candle auction unsold when the seller didn't reveal the secret in
+// time, every bid is refunded and the seller can reclaim the item
+#[receive(
+    contract = "auction",
+    name = "expireCandle",
+    parameter = "u64",
+    error = "ContractError",
+    mutable,
+    enable_logger
+)]
+fn auction_expire_candle<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), ContractError> {
+    ensure!(
+        !host.state().current().paused,
+        ContractError::ContractPaused
+    );
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        ContractError::UnknownAuction,
+        |host, state| {
+            ensure_eq!(
+                state.auction_state,
+                AuctionState::Continue,
+                ContractError::AuctionAlreadyFinalized
+            );
+            ensure!(
+                matches!(state.auction_type, AuctionType::Candle { .. }),
+                ContractError::NotCandleAuction
+            );
+            let slot_time = ctx.metadata().slot_time();
+            let open_at = state
+                .end
+                .checked_add(CANDLE_REVEAL_TIMEOUT)
+                .ok_or(ContractError::Overflow)?;
+            ensure!(
+                slot_time > open_at,
+                ContractError::CandleRevealPending { open_at }
+            );
+
+            let bids: Vec<CandleBid> = state.candle_bids.iter().map(|(_, bid)| *bid).collect();
+            for bid in bids {
+                refund(ctx, host, state, bid.bidder, bid.amount, BASE_CURRENCY);
+            }
+            state.highest_bidder = None;
+            state.highest_bid = Amount::zero();
+            state.auction_state = AuctionState::NotSold;
+            state.settled_at = Some(slot_time);
+            state.record_round(slot_time);
+            // a contract can't be paid, its bounty goes back to the seller
+            let keeper = match ctx.sender() {
+                Address::Account(account) => account,
+                Address::Contract(_) => state.seller,
+            };
+            pay_keeper_bounty(host, state, keeper);
+            log_finalized(host.state_mut().current_mut(), logger, auction_id, state)?;
+            Ok(())
+        },
+    )
+}
+
+// log who won at which price, or that the auction ended unsold
+fn log_finalized<S: HasStateApi>(
+    contract_state: &mut State<S>,
+    logger: &mut impl HasLogger,
+    auction_id: u64,
+    state: &Auction<S>,
+) -> Result<(), LogError> {
+    let sales: Vec<(AccountAddress, Amount)> = match &state.auction_state {
+        AuctionState::Sold(winner) => vec![(*winner, state.highest_bid)],
+        AuctionState::SoldUnits(winners) => {
+            // the winners are the top of the ledger
+            let bids: Vec<LedgerBid> = state.ledger.iter().take(winners.len()).copied().collect();
+            let clearing_price = bids.last().map(|lowest| lowest.amount);
+            bids.iter()
+                .map(|entry| match clearing_price {
+                    Some(clearing_price) if state.uniform_price => (entry.bidder, clearing_price),
+                    _ => (entry.bidder, entry.amount),
+                })
+                .collect()
+        }
+        AuctionState::SoldLots(awards) => awards
+            .iter()
+            .map(|award| (award.bidder, award.price))
+            .collect(),
+        _ => Vec::new(),
+    };
+    if sales.is_empty() {
+        return contract_state.log_event(
+            logger,
+            AuctionEvent::AuctionFinalized {
+                auction_id,
+                winner: None,
+                price: Amount::zero(),
+            },
+        );
+    }
+    for (winner, price) in sales {
+        contract_state.log_event(
+            logger,
+            AuctionEvent::AuctionFinalized {
+                auction_id,
+                winner: Some(winner),
+                price,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+// commit a sealed bid, the sent amount is the deposit and has to cover the bid
+#[receive(
+    contract = "auction",
+    name = "commit",
+    parameter = "(u64, HashSha2256)",
+    payable,
+    mutable,
+    enable_logger
+)]
+fn auction_commit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+) -> Result<(), SealedBidError> {
+    ensure!(
+        !host.state().current().paused,
+        SealedBidError::ContractPaused
+    );
+    let mut cursor = ctx.parameter_cursor();
+    let auction_id: u64 = cursor.get()?;
+    with_auction(
+        host,
+        auction_id,
+        SealedBidError::UnknownAuction,
+        |host, state| {
+            ensure!(
+                matches!(state.auction_type, AuctionType::SealedBid { .. }),
+                SealedBidError::NotSealedAuction
+            );
+            ensure_eq!(
+                state.auction_state,
+                AuctionState::Continue,
+                SealedBidError::AuctionFinalized
+            );
+
+            let slot_time = ctx.metadata().slot_time();
+            ensure!(
+                state.start <= slot_time && slot_time <= state.end,
+                SealedBidError::CommitNotActive
+            );
+
+            let sender_address = match ctx.sender() {
+                Address::Contract(_) => bail!(SealedBidError::OnlyAccount),
+                Address::Account(account_address) => account_address,
+            };
+            ensure_sealed_bidder(host, state, sender_address)?;
+            ensure!(
+                state.item_tokens.is_empty() || state.item_escrowed,
+                SealedBidError::ItemNotEscrowed
+            );
+
+            let hash: HashSha2256 = cursor.get()?;
+            let commitment = Commitment {
+                hash,
+                deposit: amount,
+                revealed: None,
+                order: state.commit_count,
+            };
+            ensure!(
+                state
+                    .commitments
+                    .insert(sender_address, commitment)
+                    .is_none(),
+                SealedBidError::AlreadyCommitted
+            );
+            state.commit_count += 1;
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::BidCommitted {
+                    auction_id,
+                    depositor: sender_address,
+                    deposit: amount,
+                },
+            )?;
+            Ok(())
+        },
+    )
+}
+
+// check the account may take part in a sealed-bid auction
+fn ensure_sealed_bidder<S: HasStateApi>(
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &Auction<S>,
+    bidder: AccountAddress,
+) -> Result<(), SealedBidError> {
+    ensure!(
+        !state.blacklist.contains(&bidder),
+        SealedBidError::Blacklisted
+    );
+    ensure!(
+        !state.whitelist_only || state.whitelist.contains(&bidder),
+        SealedBidError::NotWhitelisted
+    );
+    ensure!(
+        state.seller_can_bid || !is_alias(&bidder, &state.seller),
+        SealedBidError::SellerCannotBid
+    );
+    if let Some(verifier) = state.verifier {
+        ensure!(
+            is_verified(host, &verifier, bidder),
+            SealedBidError::NotVerified
+        );
+    }
+    if let Some(registry) = state.credential_registry {
+        ensure!(
+            has_active_credential(host, &registry, bidder),
+            SealedBidError::NoActiveCredential
+        );
+    }
+    if let Some(gate) = state.token_gate.clone() {
+        ensure!(
+            holds_gate_token(host, gate, bidder),
+            SealedBidError::NotTokenHolder
+        );
+    }
+    Ok(())
+}
+
+// commit a blinded bid in an auction with anonymous bids. The hash covers the
+// bidder too, so the sending account can be any account funding the deposit
+// and the bidder stays unknown until the bid is revealed.
+#[receive(
+    contract = "auction",
+    name = "commitBlinded",
+    parameter = "(u64, HashSha2256)",
+    payable,
+    mutable,
+    enable_logger
+)]
+fn auction_commit_blinded<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+) -> Result<(), SealedBidError> {
+    ensure!(
+        !host.state().current().paused,
+        SealedBidError::ContractPaused
+    );
+    let (auction_id, hash): (u64, HashSha2256) = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        SealedBidError::UnknownAuction,
+        |host, state| {
+            ensure!(state.anonymous_bids, SealedBidError::NotAnonymous);
+            ensure_eq!(
+                state.auction_state,
+                AuctionState::Continue,
+                SealedBidError::AuctionFinalized
+            );
+            let slot_time = ctx.metadata().slot_time();
+            ensure!(
+                state.start <= slot_time && slot_time <= state.end,
+                SealedBidError::CommitNotActive
+            );
+            // unrevealed deposits are refunded to the depositor
+            let depositor = match ctx.sender() {
+                Address::Contract(_) => bail!(SealedBidError::OnlyAccount),
+                Address::Account(account_address) => account_address,
+            };
+            ensure!(
+                state.item_tokens.is_empty() || state.item_escrowed,
+                SealedBidError::ItemNotEscrowed
+            );
+            let commitment = BlindedCommitment {
+                depositor,
+                deposit: amount,
+                order: state.commit_count,
+            };
+            ensure!(
+                state.blinded_commitments.insert(hash, commitment).is_none(),
+                SealedBidError::AlreadyCommitted
+            );
+            state.commit_count += 1;
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::BidCommitted {
+                    auction_id,
+                    depositor,
+                    deposit: amount,
+                },
+            )?;
+            Ok(())
+        },
+    )
+}
+
+// reveal a blinded bid during the reveal window, anyone knowing the bid can
+// send it. The bidder is checked like any sealed bidder now that it's known,
+// and takes over the deposit.
+#[receive(
+    contract = "auction",
+    name = "revealBlinded",
+    parameter = "(u64, BlindedReveal)",
+    mutable,
+    enable_logger,
+    crypto_primitives
+)]
+fn auction_reveal_blinded<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), SealedBidError> {
+    ensure!(
+        !host.state().current().paused,
+        SealedBidError::ContractPaused
+    );
+    let (auction_id, param): (u64, BlindedReveal) = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        SealedBidError::UnknownAuction,
+        |host, state| {
+            let reveal_end = match state.auction_type {
+                AuctionType::SealedBid { reveal_end } => reveal_end,
+                _ => bail!(SealedBidError::NotSealedAuction),
+            };
+            ensure_eq!(
+                state.auction_state,
+                AuctionState::Continue,
+                SealedBidError::AuctionFinalized
+            );
+            let slot_time = ctx.metadata().slot_time();
+            ensure!(
+                state.end < slot_time && slot_time <= reveal_end,
+                SealedBidError::RevealNotActive
+            );
+
+            let hash = crypto_primitives.hash_sha2_256(&to_bytes(&(auction_id, &param)));
+            let blinded = state
+                .blinded_commitments
+                .get(&hash)
+                .map(|blinded| blinded.clone())
+                .ok_or(SealedBidError::NoCommitment)?;
+            ensure_sealed_bidder(host, state, param.bidder)?;
+            let min_bid = state.min_bid;
+            ensure!(
+                param.amount >= min_bid,
+                SealedBidError::BelowMinimum(min_bid)
+            );
+            ensure!(
+                param.amount <= blinded.deposit,
+                SealedBidError::DepositTooLow
+            );
+
+            let commitment = Commitment {
+                hash,
+                deposit: blinded.deposit,
+                revealed: Some(param.amount),
+                order: blinded.order,
+            };
+            ensure!(
+                state.commitments.insert(param.bidder, commitment).is_none(),
+                SealedBidError::AlreadyCommitted
+            );
+            state.blinded_commitments.remove(&hash);
+            // a revealed bid counts in the history and the stats like any other
+            state.record_bid(param.bidder, param.amount, slot_time, Vec::new());
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::BidRevealed {
+                    auction_id,
+                    bidder: param.bidder,
+                    amount: param.amount,
+                },
+            )?;
+            Ok(())
+        },
+    )
+}
+
+// reveal a sealed bid during the reveal window
+#[receive(
+    contract = "auction",
+    name = "reveal",
+    parameter = "(u64, RevealParameter)",
+    mutable,
+    enable_logger,
+    crypto_primitives
+)]
+fn auction_reveal<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> Result<(), SealedBidError> {
+    ensure!(
+        !host.state().current().paused,
+        SealedBidError::ContractPaused
+    );
+    let mut cursor = ctx.parameter_cursor();
+    let auction_id: u64 = cursor.get()?;
+    with_auction(
+        host,
+        auction_id,
+        SealedBidError::UnknownAuction,
+        |host, state| {
+            let reveal_end = match state.auction_type {
+                AuctionType::SealedBid { reveal_end } => reveal_end,
+                _ => bail!(SealedBidError::NotSealedAuction),
+            };
+            ensure_eq!(
+                state.auction_state,
+                AuctionState::Continue,
+                SealedBidError::AuctionFinalized
+            );
+
+            let slot_time = ctx.metadata().slot_time();
+            ensure!(
+                state.end < slot_time && slot_time <= reveal_end,
+                SealedBidError::RevealNotActive
+            );
+
+            let sender_address = match ctx.sender() {
+                Address::Contract(_) => bail!(SealedBidError::OnlyAccount),
+                Address::Account(account_address) => account_address,
+            };
+
+            let param: RevealParameter = cursor.get()?;
+            let min_bid = state.min_bid;
+            ensure!(
+                param.amount >= min_bid,
+                SealedBidError::BelowMinimum(min_bid)
+            );
+
+            {
+                let mut commitment = state
+                    .commitments
+                    .get_mut(&sender_address)
+                    .ok_or(SealedBidError::NoCommitment)?;
+                ensure!(
+                    commitment.revealed.is_none(),
+                    SealedBidError::AlreadyRevealed
+                );
+                // the hash binds the bid to the auction and the bidder
+                ensure_eq!(
+                    crypto_primitives.hash_sha2_256(&to_bytes(&(
+                        auction_id,
+                        sender_address,
+                        &param
+                    ))),
+                    commitment.hash,
+                    SealedBidError::InvalidReveal
+                );
+                ensure!(
+                    param.amount <= commitment.deposit,
+                    SealedBidError::DepositTooLow
+                );
+                commitment.revealed = Some(param.amount);
+            }
+            // a revealed bid counts in the history and the stats like any other
+            state.record_bid(sender_address, param.amount, slot_time, Vec::new());
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::BidRevealed {
+                    auction_id,
+                    bidder: sender_address,
+                    amount: param.amount,
+                },
+            )?;
+            Ok(())
+        },
+    )
+}
+
+// cis2 receive hook, called by a token contract when tokens are transferred to
+// this contract. Receiving the payment token places a bid, otherwise it's the
+// seller depositing the auctioned token and bidding stays closed until then.
+// The transfer data holds the id of the auction.
+#[receive(
+    contract = "auction",
+    name = "onReceivingCIS2",
+    parameter = "OnReceivingCis2Params<ContractTokenId, ContractTokenAmount>",
+    mutable,
+    enable_logger
+)]
+fn auction_on_receiving_cis2<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), EscrowError> {
+    let params: OnReceivingCis2Params<ContractTokenId, ContractTokenAmount> =
+        ctx.parameter_cursor().get()?;
+
+    // the sender names the auction in the additional data
+    let auction_id: u64 = from_bytes(params.data.as_ref())?;
+    with_auction(
+        host,
+        auction_id,
+        EscrowError::UnknownAuction,
+        |host, state| {
+            let currency = state.payment_tokens.iter().position(|token| {
+                ctx.sender() == Address::Contract(token.contract)
+                    && params.token_id == token.token_id
+            });
+            if let Some(currency) = currency {
+                let bidder = match params.from {
+                    Address::Contract(_) => bail!(ContractError::OnlyAccount.into()),
+                    Address::Account(account_address) => account_address,
+                };
+                let amount = Amount::from_micro_ccd(params.amount.0);
+                place_bid(
+                    ctx,
+                    host,
+                    state,
+                    bidder,
+                    amount,
+                    Amount::zero(),
+                    currency as u32,
+                    Vec::new(),
+                    logger,
+                )?;
+                return Ok(());
+            }
+
+            ensure!(!state.item_tokens.is_empty(), EscrowError::NoTokenItem);
+            // only the token contract itself can report a transfer
+            let index = state
+                .item_tokens
+                .iter()
+                .position(|token| {
+                    ctx.sender() == Address::Contract(token.contract)
+                        && params.token_id == token.token_id
+                        && params.amount == token.amount
+                })
+                .ok_or(EscrowError::UnknownToken)?;
+            ensure!(
+                params.from.matches_account(&state.seller),
+                EscrowError::NotSeller
+            );
+            ensure!(!state.tokens_deposited[index], EscrowError::AlreadyEscrowed);
+            state.tokens_deposited[index] = true;
+            state.item_escrowed = state.tokens_deposited.iter().all(|deposited| *deposited);
+            Ok(())
+        },
+    )
+}
+
+// cancel an auction before it ends, only the seller can do this. Every bid the
+// contract holds for it is refunded.
+#[receive(
+    contract = "auction",
+    name = "cancelAuction",
+    parameter = "u64",
+    mutable,
+    enable_logger
+)]
+fn auction_cancel<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), CancelError> {
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        CancelError::UnknownAuction,
+        |host, state| {
+            ensure!(
+                ctx.sender().matches_account(&state.seller),
+                CancelError::OnlySeller
+            );
+            ensure_eq!(
+                state.auction_state,
+                AuctionState::Continue,
+                CancelError::AuctionFinalized
+            );
+            ensure!(
+                ctx.metadata().slot_time() <= state.end,
+                CancelError::AuctionEnded
+            );
+            ensure!(
+                !state.all_pay || state.highest_bidder.is_none(),
+                CancelError::AllPayBids
+            );
+
+            state.auction_state = AuctionState::Cancelled;
+            state.record_round(ctx.metadata().slot_time());
+            refund_bids(ctx, host, state);
+            // nobody settles a cancelled auction, the bounty goes back
+            let seller = state.seller;
+            pay_keeper_bounty(host, state, seller);
+            host.state_mut()
+                .current_mut()
+                .log_event(logger, AuctionEvent::AuctionCancelled { auction_id })?;
+            Ok(())
+        },
+    )
+}
+
+// relist input, the auction runs again from now until `end`
+#[derive(Serialize, SchemaType)]
+struct RelistParameter {
+    auction_id: u64,
+    end: Timestamp,
+    reserve: Option<Amount>, // replaces the old reserve
+}
+
+// list an auction that ended unsold again, only the seller can do this. The
+// item stays escrowed and every other setting is kept, the ccd sent is the
+// keeper bounty of the new round.
+#[receive(
+    contract = "auction",
+    name = "relist",
+    parameter = "RelistParameter",
+    error = "RelistError",
+    payable,
+    mutable,
+    enable_logger
+)]
+fn auction_relist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    amount: Amount,
+    logger: &mut impl HasLogger,
+) -> Result<(), RelistError> {
+    let param: RelistParameter = ctx.parameter_cursor().get()?;
+    let auction_id = param.auction_id;
+    let (min_duration, max_duration) = {
+        let state = host.state().current();
+        (state.min_duration, state.max_duration)
+    };
+    with_auction(
+        host,
+        auction_id,
+        RelistError::UnknownAuction,
+        |host, state| {
+            ensure!(
+                ctx.sender().matches_account(&state.seller),
+                RelistError::OnlySeller
+            );
+            ensure_eq!(
+                state.auction_state,
+                AuctionState::NotSold,
+                RelistError::NotUnsold
+            );
+            let slot_time = ctx.metadata().slot_time();
+            let duration = param
+                .end
+                .duration_since(slot_time)
+                .filter(|duration| *duration > Duration::from_millis(0))
+                .ok_or(RelistError::EndInPast)?;
+            ensure!(duration >= min_duration, RelistError::DurationTooShort);
+            ensure!(duration <= max_duration, RelistError::DurationTooLong);
+            // a sealed-bid auction keeps the length of its reveal phase
+            state.auction_type = match state.auction_type {
+                AuctionType::SealedBid { reveal_end } => {
+                    let reveal = reveal_end
+                        .duration_since(state.end)
+                        .unwrap_or(Duration::from_millis(0));
+                    AuctionType::SealedBid {
+                        reveal_end: param
+                            .end
+                            .checked_add(reveal)
+                            .ok_or(RelistError::DurationTooLong)?,
+                    }
+                }
+                AuctionType::Candle { .. } => bail!(RelistError::CandleAuction),
+                auction_type => auction_type,
+            };
+
+            state.reopen(slot_time, param.end, param.reserve);
+            state.keeper_bounty = amount;
+            let round = state.round;
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::AuctionRelisted {
+                    auction_id,
+                    round,
+                    end: param.end,
+                },
+            )?;
+            Ok(())
+        },
+    )
+}
+
+// give back every bid held for the auction, wherever the auction type keeps them
+fn refund_bids<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+) {
+    let bids = state.held_bids();
+    let currency = state.highest_currency;
+    for (account, amount) in bids {
+        refund(ctx, host, state, account, amount, currency);
+    }
+}
+
+// send the sender everything it is owed from being outbid
+#[receive(contract = "auction", name = "withdraw", parameter = "u64", mutable)]
+fn auction_withdraw<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), WithdrawError> {
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(WithdrawError::OnlyAccount),
+        Address::Account(account_address) => account_address,
+    };
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        WithdrawError::UnknownAuction,
+        |host, state| claim_pending_refund(ctx, host, state, sender_address),
+    )
+}
+
+// send the sender any refund that was deferred or failed, the same ledger
+// withdraw pays out from
+#[receive(contract = "auction", name = "claimRefund", parameter = "u64", mutable)]
+fn auction_claim_refund<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), WithdrawError> {
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(WithdrawError::OnlyAccount),
+        Address::Account(account_address) => account_address,
+    };
+    let auction_id: u64 = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        WithdrawError::UnknownAuction,
+        |host, state| claim_pending_refund(ctx, host, state, sender_address),
+    )
+}
+
+// view how much an account can claim
+#[receive(
+    contract = "auction",
+    name = "viewPendingRefund",
+    parameter = "(u64, AccountAddress, u32)",
+    return_value = "Amount"
+)]
+fn view_pending_refund<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<Amount> {
+    // the auction, the account and the currency, 0 unless bids are made in
+    // several tokens
+    let (auction_id, account, currency): (u64, AccountAddress, u32) =
+        ctx.parameter_cursor().get()?;
+    let state = host
+        .state()
+        .current()
+        .auctions
+        .get(&auction_id)
+        .ok_or_else(Reject::default)?;
+    Ok(state
+        .pending_refunds
+        .get(&(account, currency))
+        .map_or(Amount::zero(), |owed| *owed))
+}
+
+// pay out and clear the account's pending refunds in every currency
+fn claim_pending_refund<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    state: &mut Auction<S>,
+    sender_address: AccountAddress,
+) -> Result<(), WithdrawError> {
+    let currencies = state.payment_tokens.len().max(1) as u32;
+    let mut withdrawn = false;
+    for currency in 0..currencies {
+        let owed = state
+            .pending_refunds
+            .remove_and_get(&(sender_address, currency));
+        if let Some(owed) = owed {
+            send_funds(ctx, host, state, &sender_address, owed, currency)?;
+            withdrawn = true;
+        }
+    }
+    ensure!(withdrawn, WithdrawError::NothingToWithdraw);
+    Ok(())
+}
+
+// nominate the next admin, the role only moves once the nominee accepts so a
+// wrong address can't lock everyone out. Only the admin can do this.
+#[receive(
+    contract = "auction",
+    name = "nominateAdmin",
+    parameter = "Address",
+    mutable
+)]
+fn nominate_admin<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), AdminError> {
+    ensure!(
+        ctx.sender() == host.state().current().admin,
+        AdminError::OnlyAdmin
+    );
+    let nominee: Address = ctx.parameter_cursor().get()?;
+    host.state_mut().current_mut().pending_admin = Some(nominee);
+    Ok(())
+}
+
+// take over the admin role, only the nominee can do this
+#[receive(contract = "auction", name = "acceptAdmin", mutable)]
+fn accept_admin<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), AdminError> {
+    let state = host.state_mut().current_mut();
+    ensure_eq!(
+        state.pending_admin,
+        Some(ctx.sender()),
+        AdminError::NotNominated
+    );
+    state.admin = ctx.sender();
+    state.pending_admin = None;
+    Ok(())
+}
+
+// upgrade input, the migration is called on the upgraded instance right after
+#[derive(Serialize, SchemaType)]
+struct UpgradeParameter {
+    module: ModuleReference,
+    migrate: Option<(OwnedEntrypointName, OwnedParameter)>,
+}
+
+// replace the code of the instance, keeping its state and balance, only the
+// admin can do this. low level so the old state isn't written back over what
+// the migration stored
+#[receive(
+    contract = "auction",
+    name = "upgrade",
+    parameter = "UpgradeParameter",
+    low_level
+)]
+fn upgrade<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<S, StateApiType = S>,
+) -> Result<(), AdminError> {
+    let state: VersionedState<S> = host.state().read_root()?;
+    ensure!(ctx.sender() == state.admin(), AdminError::OnlyAdmin);
+    let param: UpgradeParameter = ctx.parameter_cursor().get()?;
+    host.upgrade(param.module)
+        .map_err(|_| AdminError::UpgradeFailed)?;
+    if let Some((entrypoint, parameter)) = param.migrate {
+        host.invoke_contract_raw(
+            &ctx.self_address(),
+            parameter.as_parameter(),
+            entrypoint.as_entrypoint_name(),
+            Amount::zero(),
+        )
+        .map_err(|_| AdminError::MigrationFailed)?;
+    }
+    Ok(())
+}
+
+// store the state in the current layout, called by upgrade once the new code
+// is in place. low level since the stored layout may not be the current one
+#[receive(contract = "auction", name = "migrate", low_level)]
+fn migrate<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<S, StateApiType = S>,
+) -> Result<(), MigrateError> {
+    let old_state: VersionedState<S> = host.state().read_root()?;
+    let sender = ctx.sender();
+    ensure!(
+        sender == Address::Contract(ctx.self_address()) || sender == old_state.admin(),
+        MigrateError::Unauthorized
+    );
+    let balance = host.self_balance();
+    let (state_api, state_builder) = host.state_and_builder();
+    migrate_root(state_api, state_builder, old_state, balance)
+}
+
+// convert the stored state and write it back as the new root
+fn migrate_root<S: HasStateApi>(
+    state_api: &mut S,
+    state_builder: &mut StateBuilder<S>,
+    old_state: VersionedState<S>,
+    balance: Amount,
+) -> Result<(), MigrateError> {
+    let owed = old_state.ccd_owed();
+    let state = old_state.migrate(state_builder);
+    // every bid and refund has to survive the migration, and still be backed
+    // by the balance
+    ensure_eq!(state.ccd_owed(), owed, MigrateError::FundsLost);
+    ensure!(owed <= balance, MigrateError::Underfunded);
+    state_api.write_root(&VersionedState::V2(state));
+    Ok(())
+}
+
+// grant a role to an address, only the admin can do this
+#[receive(
+    contract = "auction",
+    name = "grantRole",
+    parameter = "RoleParameter",
+    mutable
+)]
+fn grant_role<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), AdminError> {
+    ensure!(
+        ctx.sender() == host.state().current().admin,
+        AdminError::OnlyAdmin
+    );
+    let param: RoleParameter = ctx.parameter_cursor().get()?;
+    let (versioned, state_builder) = host.state_and_builder();
+    let state = versioned.current_mut();
+    let mut roles = state
+        .roles
+        .remove_and_get(&param.address)
+        .unwrap_or_else(|| state_builder.new_set());
+    roles.insert(param.role);
+    state.roles.insert(param.address, roles);
+    Ok(())
+}
+
+// revoke a role from an address, only the admin can do this
+#[receive(
+    contract = "auction",
+    name = "revokeRole",
+    parameter = "RoleParameter",
+    mutable
+)]
+fn revoke_role<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), AdminError> {
+    ensure!(
+        ctx.sender() == host.state().current().admin,
+        AdminError::OnlyAdmin
+    );
+    let param: RoleParameter = ctx.parameter_cursor().get()?;
+    if let Some(mut roles) = host.state_mut().current_mut().roles.get_mut(&param.address) {
+        roles.remove(&param.role);
+    }
+    Ok(())
+}
+
+// stop bidding and finalizing in every auction, needs the pauser role
+#[receive(contract = "auction", name = "pause", mutable)]
+fn pause<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), AdminError> {
+    ensure!(
+        host.state().current().has_role(&ctx.sender(), Role::Pauser),
+        AdminError::MissingRole
+    );
+    host.state_mut().current_mut().paused = true;
+    Ok(())
+}
+
+// resume bidding and finalizing, needs the pauser role
+#[receive(contract = "auction", name = "unpause", mutable)]
+fn unpause<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), AdminError> {
+    ensure!(
+        host.state().current().has_role(&ctx.sender(), Role::Pauser),
+        AdminError::MissingRole
+    );
+    host.state_mut().current_mut().paused = false;
+    Ok(())
+}
+
+// set the protocol fee of auctions created from now on, auctions already
+// created keep theirs. Needs the fee manager role.
+#[receive(contract = "auction", name = "setFee", parameter = "u64", mutable)]
+fn set_fee<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), AdminError> {
+    ensure!(
+        host.state()
+            .current()
+            .has_role(&ctx.sender(), Role::FeeManager),
+        AdminError::MissingRole
+    );
+    let fee_bps: u64 = ctx.parameter_cursor().get()?;
+    ensure!(fee_bps <= MAX_FEE_BPS, AdminError::FeeTooHigh);
+    host.state_mut().current_mut().fee_bps = fee_bps;
+    Ok(())
+}
+
+// rotate the account collecting the protocol fee of every auction, needs the
+// fee manager role
+#[receive(
+    contract = "auction",
+    name = "setFeeReceiver",
+    parameter = "AccountAddress",
+    mutable
+)]
+fn set_fee_receiver<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), AdminError> {
+    ensure!(
+        host.state()
+            .current()
+            .has_role(&ctx.sender(), Role::FeeManager),
+        AdminError::MissingRole
+    );
+    let fee_receiver: AccountAddress = ctx.parameter_cursor().get()?;
+    host.state_mut().current_mut().fee_receiver = fee_receiver;
+    Ok(())
+}
+
+// set the shortest and longest time new auctions can run, auctions already
+// created keep their end. Only the admin can do this.
+#[receive(
+    contract = "auction",
+    name = "setDurationLimits",
+    parameter = "(Duration, Duration)",
+    mutable
+)]
+fn set_duration_limits<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), AdminError> {
+    ensure!(
+        ctx.sender() == host.state().current().admin,
+        AdminError::OnlyAdmin
+    );
+    let (min_duration, max_duration): (Duration, Duration) = ctx.parameter_cursor().get()?;
+    ensure!(min_duration <= max_duration, AdminError::InvalidLimits);
+    let state = host.state_mut().current_mut();
+    state.min_duration = min_duration;
+    state.max_duration = max_duration;
+    Ok(())
+}
+
+// declare an emergency withdrawal of the amount to the account, replacing any
+// earlier one. It can only be executed `EMERGENCY_WITHDRAW_DELAY` later, the
+// event gives users time to withdraw first. Only the admin can do this.
+#[receive(
+    contract = "auction",
+    name = "declareEmergencyWithdraw",
+    parameter = "(AccountAddress, Amount)",
+    mutable,
+    enable_logger
+)]
+fn declare_emergency_withdraw<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), AdminError> {
+    ensure!(
+        ctx.sender() == host.state().current().admin,
+        AdminError::OnlyAdmin
+    );
+    let (to, amount): (AccountAddress, Amount) = ctx.parameter_cursor().get()?;
+    let executable_at = ctx
+        .metadata()
+        .slot_time()
+        .checked_add(EMERGENCY_WITHDRAW_DELAY)
+        .ok_or(AdminError::Overflow)?;
+    let withdrawal = EmergencyWithdrawal {
+        to,
+        amount,
+        executable_at,
+    };
+    let state = host.state_mut().current_mut();
+    state.emergency_withdrawal = Some(withdrawal);
+    state.log_event(logger, AuctionEvent::EmergencyWithdrawDeclared(withdrawal))?;
+    Ok(())
+}
+
+// drop the declared emergency withdrawal, only the admin can do this
+#[receive(
+    contract = "auction",
+    name = "cancelEmergencyWithdraw",
+    mutable,
+    enable_logger
+)]
+fn cancel_emergency_withdraw<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), AdminError> {
+    ensure!(
+        ctx.sender() == host.state().current().admin,
+        AdminError::OnlyAdmin
+    );
+    let state = host.state_mut().current_mut();
+    ensure!(
+        state.emergency_withdrawal.take().is_some(),
+        AdminError::NoEmergencyWithdrawal
+    );
+    state.log_event(logger, AuctionEvent::EmergencyWithdrawCancelled)?;
+    Ok(())
+}
+
+// pay out the declared emergency withdrawal once its delay is over, whatever
+// the contract owes. Only the admin can do this.
+#[receive(
+    contract = "auction",
+    name = "emergencyWithdraw",
+    mutable,
+    enable_logger
+)]
+fn emergency_withdraw<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), AdminError> {
+    ensure!(
+        ctx.sender() == host.state().current().admin,
+        AdminError::OnlyAdmin
+    );
+    let withdrawal = host
+        .state()
+        .current()
+        .emergency_withdrawal
+        .ok_or(AdminError::NoEmergencyWithdrawal)?;
+    ensure!(
+        ctx.metadata().slot_time() >= withdrawal.executable_at,
+        AdminError::EmergencyTimelock
+    );
+    host.state_mut().current_mut().emergency_withdrawal = None;
+    host.invoke_transfer(&withdrawal.to, withdrawal.amount)
+        .map_err(|_| AdminError::TransferFailed)?;
+    host.state_mut().current_mut().log_event(
+        logger,
+        AuctionEvent::EmergencyWithdrawn {
+            to: withdrawal.to,
+            amount: withdrawal.amount,
+        },
+    )?;
+    Ok(())
+}
+
+// view the protocol fee new auctions take and who receives it, an auction's
+// own fee is in its viewConfig
+#[receive(
+    contract = "auction",
+    name = "viewFeeConfig",
+    return_value = "FeeConfig"
+)]
+fn view_fee_config<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> ReceiveResult<FeeConfig> {
+    let state = host.state().current();
+    Ok(FeeConfig {
+        fee_bps: state.fee_bps,
+        fee_receiver: state.fee_receiver,
+    })
+}
+
+// add an account to the blacklist, needs the lister role
+#[receive(
+    contract = "auction",
+    name = "addToBlacklist",
+    parameter = "(u64, AccountAddress)",
+    mutable,
+    enable_logger
+)]
+fn add_to_blacklist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), AdminError> {
+    ensure!(
+        host.state().current().has_role(&ctx.sender(), Role::Lister),
+        AdminError::MissingRole
+    );
+    let (auction_id, account): (u64, AccountAddress) = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        AdminError::UnknownAuction,
+        |host, state| {
+            state.blacklist.insert(account);
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::Blacklisted {
+                    auction_id,
+                    account,
+                },
+            )?;
+            Ok(())
+        },
+    )
+}
+
+// remove an account from the blacklist, needs the lister role
+#[receive(
+    contract = "auction",
+    name = "removeFromBlacklist",
+    parameter = "(u64, AccountAddress)",
+    mutable,
+    enable_logger
+)]
+fn remove_from_blacklist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), AdminError> {
+    ensure!(
+        host.state().current().has_role(&ctx.sender(), Role::Lister),
+        AdminError::MissingRole
+    );
+    let (auction_id, account): (u64, AccountAddress) = ctx.parameter_cursor().get()?;
+    with_auction(
+        host,
+        auction_id,
+        AdminError::UnknownAuction,
+        |host, state| {
+            state.blacklist.remove(&account);
+            host.state_mut().current_mut().log_event(
+                logger,
+                AuctionEvent::Unblacklisted {
+                    auction_id,
+                    account,
+                },
+            )?;
+            Ok(())
+        },
+    )
+}
+
+// add an account to the whitelist, needs the lister role
+#[receive(
+    contract = "auction",
+    name = "addToWhitelist",
+    parameter = "(u64, AccountAddress)",
+    mutable
+)]
+fn add_to_whitelist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), AdminError> {
+    ensure!(
+        host.state().current().has_role(&ctx.sender(), Role::Lister),
+        AdminError::MissingRole
+    );
+    let (auction_id, account): (u64, AccountAddress) = ctx.parameter_cursor().get()?;
+    with_auction(host, auction_id, AdminError::UnknownAuction, |_, state| {
+        state.whitelist.insert(account);
+        Ok(())
+    })
+}
+
+// remove an account from the whitelist, needs the lister role
+#[receive(
+    contract = "auction",
+    name = "removeFromWhitelist",
+    parameter = "(u64, AccountAddress)",
+    mutable
+)]
+fn remove_from_whitelist<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VersionedState<S>, StateApiType = S>,
+) -> Result<(), AdminError> {
+    ensure!(
+        host.state().current().has_role(&ctx.sender(), Role::Lister),
+        AdminError::MissingRole
+    );
+    let (auction_id, account): (u64, AccountAddress) = ctx.parameter_cursor().get()?;
+    with_auction(host, auction_id, AdminError::UnknownAuction, |_, state| {
+        state.whitelist.remove(&account);
+        Ok(())
+    })
+}
+
+#[concordium_cfg_test]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use test_infrastructure::*;
+
+    // a counter for generating new accounts
+    static ADDRESS_COUNTER: AtomicU8 = AtomicU8::new(0);
+    const AUCTION_END: u64 = 1;
+    // the auction created at init, named by the default receive parameter
+    const AUCTION_ID: u64 = 0;
+    static AUCTION_ID_BYTES: [u8; 8] = AUCTION_ID.to_le_bytes();
+    const ITEM: &str = "Starry night by Van Gogh";
+
+    fn item() -> ItemMetadata {
+        ItemMetadata {
+            name: ITEM.into(),
+            description: "Oil on canvas, 1889".into(),
+            url: "https://example.com/starry-night.json".into(),
+            hash: Some(HashSha2256([7; 32])),
+        }
+    }
+    const FEE_RECEIVER: AccountAddress = AccountAddress([255; 32]);
+    // deploys the contract, so it owns the instance and sells the first auction
+    const SELLER: AccountAddress = AccountAddress([254; 32]);
+    const TOKEN_CONTRACT: ContractAddress = ContractAddress {
+        index: 1,
+        subindex: 0,
+    };
+    const PAYMENT_CONTRACT: ContractAddress = ContractAddress {
+        index: 2,
+        subindex: 0,
+    };
+    // the auction instance itself
+    const SELF_ADDRESS: ContractAddress = ContractAddress {
+        index: 10,
+        subindex: 0,
+    };
+
+    fn expect_error<E, T>(expr: Result<T, E>, err: E, msg: &str)
+    where
+        E: Eq + Debug,
+        T: Debug,
+    {
+        let actual = expr.expect_err_report(msg);
+        claim_eq!(actual, err)
+    }
+
+    fn item_and_param() -> InitParameter {
+        InitParameter {
+            auction_type: AuctionType::English,
+            item: item(),
+            start: Timestamp::from_timestamp_millis(0),
+            end: Timestamp::from_timestamp_millis(AUCTION_END),
+            whitelist_only: false,
+            reserve: None,
+            min_bid: Amount::zero(),
+            min_increment: BidIncrement::Absolute(Amount::zero()),
+            buy_now: None,
+            price_cap: None,
+            extension_window: Duration::from_millis(0),
+            overtime_round: Duration::from_millis(0),
+            settle_grace_period: Duration::from_millis(0),
+            terms_hash: None,
+            aggregate_bids: false,
+            anonymous_bids: false,
+            dispute_window: Duration::from_millis(0),
+            arbitrator: None,
+            delivery_timeout: Duration::from_millis(0),
+            payout_delay: Duration::from_millis(0),
+            auto_relist: None,
+            quantity: 1,
+            uniform_price: false,
+            all_pay: false,
+            lots: Vec::new(),
+            payees: Vec::new(),
+            royalty: None,
+            buyer_premium_bps: 0,
+            charity: None,
+            item_tokens: Vec::new(),
+            payment_tokens: Vec::new(),
+            wccd: None,
+            unwrap_proceeds: false,
+            fractional: false,
+            seller: None,
+            seller_can_bid: false,
+            verifier: None,
+            credential_registry: None,
+            token_gate: None,
+        }
+    }
+
+    fn create_parameter_bytes(parameter: &InitParameter) -> Vec<u8> {
+        to_bytes(parameter)
+    }
+
+    fn parametrized_init_ctx(parameter_bytes: &[u8]) -> TestInitContext<'_> {
+        let mut ctx = TestInitContext::empty();
+        ctx.set_init_origin(SELLER);
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(0));
+        ctx.set_parameter(parameter_bytes);
+        ctx
+    }
+
+    fn new_account() -> AccountAddress {
+        let account = AccountAddress([ADDRESS_COUNTER.load(Ordering::SeqCst); 32]);
+        ADDRESS_COUNTER.fetch_add(1, Ordering::SeqCst);
+        account
+    }
+
+    fn new_account_ctx<'a>() -> (AccountAddress, TestReceiveContext<'a>) {
+        let account = new_account();
+        let ctx = new_ctx(account, account, AUCTION_END);
+        (account, ctx)
+    }
+
+    fn new_ctx<'a>(
+        owner: AccountAddress,
+        sender: AccountAddress,
+        slot_time: u64,
+    ) -> TestReceiveContext<'a> {
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_sender(Address::Account(sender));
+        ctx.set_owner(owner);
+        ctx.set_self_address(SELF_ADDRESS);
+        ctx.set_metadata_slot_time(Timestamp::from_timestamp_millis(slot_time));
+        ctx.set_parameter(&AUCTION_ID_BYTES);
+        ctx
+    }
+
+    fn auction(
+        host: &TestHost<VersionedState<TestStateApi>>,
+    ) -> StateRef<'_, Auction<TestStateApi>> {
+        host.state()
+            .current()
+            .auctions
+            .get(&AUCTION_ID)
+            .expect_report("Auction should exist")
+    }
+
+    fn bid(
+        host: &mut TestHost<VersionedState<TestStateApi>>,
+        ctx: &TestContext<TestReceiveOnlyData>,
+        amount: Amount,
+        current_contract_balance: Amount,
+    ) {
+        //set balance
+        // initial + bid
+        host.set_self_balance(amount + current_contract_balance);
+
+        let mut logger = TestLogger::init();
+        auction_bid(ctx, host, amount, &mut logger).expect_report("Bidding should pass");
+    }
+
+    #[concordium_test]
+    fn test_init() {
+        let parameter_bytes = create_parameter_bytes(&item_and_param());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let mut state_builder = TestStateBuilder::new();
+        let state_result = auction_init(
+            &ctx,
+            &mut state_builder,
+            Amount::zero(),
+            &mut TestLogger::init(),
+        );
+        state_result.expect_report("Contract initialize error");
+    }
+
+    #[concordium_test]
+    fn test_view_item() {
+        let host = initialized_host();
+        let ctx = new_ctx(new_account(), new_account(), AUCTION_END);
+        claim_eq!(
+            view_item(&ctx, &host).expect_report("View should pass"),
+            item()
+        );
+    }
+
+    #[concordium_test]
+    fn test_unknown_auction() {
+        let mut host = initialized_host();
+        let parameter_bytes = to_bytes(&(AUCTION_ID + 1));
+        let (_, mut ctx) = new_account_ctx();
+        ctx.set_parameter(&parameter_bytes);
+        expect_error(
+            auction_bid(
+                &ctx,
+                &mut host,
+                Amount::from_ccd(1),
+                &mut TestLogger::init(),
+            ),
+            ContractError::UnknownAuction,
+            "Bidding on an unknown auction should fail",
+        );
+        claim!(
+            view(&ctx, &host).is_err(),
+            "Viewing an unknown auction should fail"
+        );
+    }
+
+    #[concordium_test]
+    fn test_create_auction() {
+        let mut host = initialized_host();
+        let seller = new_account();
+        let parameter_bytes = create_parameter_bytes(&item_and_param());
+        let mut ctx = new_ctx(new_account(), seller, 0);
+        ctx.set_parameter(&parameter_bytes);
+        let auction_id = create_auction(&ctx, &mut host, Amount::zero(), &mut TestLogger::init())
+            .expect_report("Creating should pass");
+        claim_eq!(auction_id, AUCTION_ID + 1);
+        claim_eq!(
+            host.state()
+                .current()
+                .auctions
+                .get(&auction_id)
+                .map(|created| created.seller),
+            Some(seller)
+        );
+
+        // the bid only goes to the auction named in the parameter
+        let id_bytes = to_bytes(&auction_id);
+        let mut ctx = new_ctx(seller, new_account(), AUCTION_END);
+        ctx.set_parameter(&id_bytes);
+        bid(&mut host, &ctx, Amount::from_ccd(5), Amount::zero());
+        claim_eq!(
+            host.state()
+                .current()
+                .auctions
+                .get(&auction_id)
+                .map(|created| created.highest_bid),
+            Some(Amount::from_ccd(5))
+        );
+        claim_eq!(auction(&host).highest_bid, Amount::zero());
+    }
+
+    #[concordium_test]
+    fn test_seller_is_paid() {
+        let seller = new_account();
+        let mut host = host_with_param(&InitParameter {
+            seller: Some(seller),
+            ..item_and_param()
+        });
+        let ctx = new_ctx(SELLER, new_account(), AUCTION_END);
+        bid(&mut host, &ctx, Amount::from_ccd(10), Amount::zero());
+
+        auction_settle(
+            &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        // the seller is paid, not the owner of the instance
+        claim_eq!(host.get_transfers(), [(seller, Amount::from_ccd(10))]);
+    }
+
+    #[concordium_test]
+    fn test_cancel_auction() {
+        let mut host = initialized_host();
+        let bidder = new_account();
+        let ten = Amount::from_ccd(10);
+        bid(&mut host, &new_ctx(SELLER, bidder, 0), ten, Amount::zero());
+
+        let mut logger = TestLogger::init();
+        expect_error(
+            auction_cancel(&new_ctx(SELLER, bidder, 0), &mut host, &mut logger),
+            CancelError::OnlySeller,
+            "Only the seller can cancel",
+        );
+        auction_cancel(&new_ctx(SELLER, SELLER, 0), &mut host, &mut logger)
+            .expect_report("Cancelling should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::Cancelled);
+        claim_eq!(host.get_transfers(), [(bidder, ten)]);
+        claim_eq!(
+            logger.logs,
+            [event(
+                2,
+                AuctionEvent::AuctionCancelled {
+                    auction_id: AUCTION_ID
+                }
+            )]
+        );
+
+        host.set_self_balance(ten);
+        expect_error(
+            auction_bid(&new_ctx(SELLER, bidder, 0), &mut host, ten, &mut logger),
+            ContractError::AuctionFinalizedButBidded,
+            "Bidding on a cancelled auction should fail",
+        );
+    }
+
+    #[concordium_test]
+    fn test_view_auctions() {
+        let mut host = initialized_host();
+        let parameter_bytes = create_parameter_bytes(&item_and_param());
+        let mut ctx = new_ctx(SELLER, new_account(), 0);
+        ctx.set_parameter(&parameter_bytes);
+        for _ in 0..2 {
+            create_auction(&ctx, &mut host, Amount::zero(), &mut TestLogger::init())
+                .expect_report("Creating should pass");
+        }
+
+        let page_bytes = to_bytes(&ViewAuctionsParameter { skip: 1, limit: 5 });
+        ctx.set_parameter(&page_bytes);
+        let page = view_auctions(&ctx, &host).expect_report("View should pass");
+        claim_eq!(
+            page.iter()
+                .map(|summary| summary.auction_id)
+                .collect::<Vec<_>>(),
+            [1, 2]
+        );
+        claim_eq!(page[0].item, item());
+    }
+
+    #[concordium_test]
+    fn test_view_auctions_by_seller() {
+        let mut host = initialized_host();
+        let seller = new_account();
+        let parameter_bytes = create_parameter_bytes(&item_and_param());
+        let mut ctx = new_ctx(SELLER, seller, 0);
+        ctx.set_parameter(&parameter_bytes);
+        for _ in 0..2 {
+            create_auction(&ctx, &mut host, Amount::zero(), &mut TestLogger::init())
+                .expect_report("Creating should pass");
+        }
+
+        let seller_bytes = to_bytes(&seller);
+        ctx.set_parameter(&seller_bytes);
+        claim_eq!(
+            view_auctions_by_seller(&ctx, &host).expect_report("View should pass"),
+            [1, 2]
+        );
+        let seller_bytes = to_bytes(&SELLER);
+        ctx.set_parameter(&seller_bytes);
+        claim_eq!(
+            view_auctions_by_seller(&ctx, &host).expect_report("View should pass"),
+            [AUCTION_ID]
+        );
+    }
+
+    #[concordium_test]
+    fn test_view_bid_history() {
+        let mut host = initialized_host();
+        let (bidder1, bidder2) = (new_account(), new_account());
+        let (ten, twenty) = (Amount::from_ccd(10), Amount::from_ccd(20));
+        bid(&mut host, &new_ctx(SELLER, bidder1, 0), ten, Amount::zero());
+        bid(&mut host, &new_ctx(SELLER, bidder2, 1), twenty, ten);
+
+        let page_bytes = to_bytes(&ViewBidHistoryParameter {
+            auction_id: AUCTION_ID,
+            skip: 0,
+            limit: 5,
+        });
+        let mut ctx = new_ctx(SELLER, SELLER, 2);
+        ctx.set_parameter(&page_bytes);
+        // the outbid bid is still listed
+        claim_eq!(
+            view_bid_history(&ctx, &host).expect_report("View should pass"),
+            [
+                BidRecord {
+                    bidder: bidder1,
+                    amount: ten,
+                    timestamp: Timestamp::from_timestamp_millis(0),
+                    memo: Vec::new(),
+                },
+                BidRecord {
+                    bidder: bidder2,
+                    amount: twenty,
+                    timestamp: Timestamp::from_timestamp_millis(1),
+                    memo: Vec::new(),
+                }
+            ]
+        );
+    }
+
+    #[concordium_test]
+    fn test_bid_history_pages() {
+        let mut host = initialized_host();
+        let mut balance = Amount::zero();
+        for ccd in 1..=4 {
+            let amount = Amount::from_ccd(ccd);
+            bid(
+                &mut host,
+                &new_ctx(SELLER, new_account(), 0),
+                amount,
+                balance,
+            );
+            balance += amount;
+        }
+
+        let page = |host: &TestHost<VersionedState<TestStateApi>>, skip: u32, limit: u32| {
+            let page_bytes = to_bytes(&ViewBidHistoryParameter {
+                auction_id: AUCTION_ID,
+                skip,
+                limit,
+            });
+            let mut ctx = new_ctx(SELLER, SELLER, 0);
+            ctx.set_parameter(&page_bytes);
+            view_bid_history(&ctx, host)
+                .expect_report("View should pass")
+                .iter()
+                .map(|bid| bid.amount)
+                .collect::<Vec<_>>()
+        };
+        // pages follow the order the bids were placed in
+        claim_eq!(
+            page(&host, 0, 2),
+            [Amount::from_ccd(1), Amount::from_ccd(2)]
+        );
+        claim_eq!(
+            page(&host, 2, 2),
+            [Amount::from_ccd(3), Amount::from_ccd(4)]
+        );
+        claim!(page(&host, 4, 2).is_empty());
+        // a limit over the page size is capped
+        claim_eq!(page(&host, 0, MAX_PAGE_SIZE + 1).len(), 4);
+    }
+
+    #[concordium_test]
+    fn test_view_bids_by() {
+        let mut host = initialized_host();
+        let (bidder, other) = (new_account(), new_account());
+        let (ten, twenty) = (Amount::from_ccd(10), Amount::from_ccd(20));
+        bid(&mut host, &new_ctx(SELLER, bidder, 0), ten, Amount::zero());
+        bid(&mut host, &new_ctx(SELLER, other, 0), twenty, ten);
+
+        let parameter_bytes = create_parameter_bytes(&item_and_param());
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&parameter_bytes);
+        let auction_id = create_auction(&ctx, &mut host, Amount::zero(), &mut TestLogger::init())
+            .expect_report("Creating should pass");
+        let auction_id_bytes = to_bytes(&auction_id);
+        let mut ctx = new_ctx(SELLER, bidder, 1);
+        ctx.set_parameter(&auction_id_bytes);
+        host.set_self_balance(ten + twenty + ten);
+        auction_bid(&ctx, &mut host, ten, &mut TestLogger::init())
+            .expect_report("Bidding should pass");
+
+        let view = |skip: u64, limit: u32| {
+            let parameter_bytes = to_bytes(&ViewBidsByParameter {
+                account: bidder,
+                skip,
+                limit,
+            });
+            let mut ctx = new_ctx(SELLER, SELLER, 2);
+            ctx.set_parameter(&parameter_bytes);
+            view_bids_by(&ctx, &host).expect_report("View should pass")
+        };
+        claim_eq!(
+            view(0, MAX_PAGE_SIZE),
+            [
+                (
+                    AUCTION_ID,
+                    BidRecord {
+                        bidder,
+                        amount: ten,
+                        timestamp: Timestamp::from_timestamp_millis(0),
+                        memo: Vec::new(),
+                    }
+                ),
+                (
+                    auction_id,
+                    BidRecord {
+                        bidder,
+                        amount: ten,
+                        timestamp: Timestamp::from_timestamp_millis(1),
+                        memo: Vec::new(),
+                    }
+                )
+            ]
+        );
+        // a page only searches its own auctions
+        claim_eq!(view(0, 1).len(), 1);
+        claim_eq!(view(1, 1)[0].0, auction_id);
+        claim_eq!(view(2, MAX_PAGE_SIZE), []);
+    }
+
+    #[concordium_test]
+    fn test_view_stats() {
+        let mut host = initialized_host();
+        let (bidder1, bidder2) = (new_account(), new_account());
+        let (ten, twenty, thirty) = (
+            Amount::from_ccd(10),
+            Amount::from_ccd(20),
+            Amount::from_ccd(30),
+        );
+        bid(&mut host, &new_ctx(SELLER, bidder1, 0), ten, Amount::zero());
+        bid(&mut host, &new_ctx(SELLER, bidder2, 0), twenty, ten);
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder1, AUCTION_END),
+            thirty,
+            twenty,
+        );
+
+        let ctx = new_ctx(SELLER, SELLER, AUCTION_END + 1);
+        claim_eq!(
+            view_stats(&ctx, &host).expect_report("View should pass"),
+            AuctionStats {
+                bid_count: 3,
+                unique_bidders: 2,
+                total_volume: ten + twenty + thirty,
+            }
+        );
+    }
+
+    #[concordium_test]
+    fn test_view_state() {
+        let host = initialized_host();
+        let view_state = view(&new_ctx(SELLER, SELLER, AUCTION_END - 1), &host)
+            .expect_report("View should pass");
+        claim_eq!(view_state.version, VIEW_STATE_VERSION);
+        claim_eq!(view_state.time_remaining, TimeRemaining::Remaining(1));
+    }
+
+    #[concordium_test]
+    fn test_view_time_remaining() {
+        let host = initialized_host();
+        claim_eq!(
+            view_time_remaining(&new_ctx(SELLER, SELLER, AUCTION_END - 1), &host)
+                .expect_report("View should pass"),
+            TimeRemaining::Remaining(1)
+        );
+        claim_eq!(
+            view_time_remaining(&new_ctx(SELLER, SELLER, AUCTION_END), &host)
+                .expect_report("View should pass"),
+            TimeRemaining::Remaining(0)
+        );
+        claim_eq!(
+            view_time_remaining(&new_ctx(SELLER, SELLER, AUCTION_END + 5), &host)
+                .expect_report("View should pass"),
+            TimeRemaining::Overtime(5)
+        );
+    }
+
+    #[concordium_test]
+    fn test_view_minimum_next_bid() {
+        let mut host = host_with_param(&InitParameter {
+            min_bid: Amount::from_ccd(5),
+            min_increment: BidIncrement::Absolute(Amount::from_ccd(2)),
+            reserve: Some(Amount::from_ccd(10)),
+            buy_now: Some(Amount::from_ccd(11)),
+            ..item_and_param()
+        });
+        let ctx = new_ctx(SELLER, SELLER, 0);
+        claim_eq!(
+            view_minimum_next_bid(&ctx, &host).expect_report("View should pass"),
+            Some(NextBid {
+                minimum: Amount::from_ccd(5),
+                reserve_met: false,
+            })
+        );
+
+        bid(
+            &mut host,
+            &new_ctx(SELLER, new_account(), 0),
+            Amount::from_ccd(8),
+            Amount::zero(),
+        );
+        claim_eq!(
+            view_minimum_next_bid(&ctx, &host).expect_report("View should pass"),
+            Some(NextBid {
+                minimum: Amount::from_ccd(10),
+                reserve_met: true,
+            })
+        );
+
+        // the buy-now price undercuts the increment
+        bid(
+            &mut host,
+            &new_ctx(SELLER, new_account(), 0),
+            Amount::from_ccd(10),
+            Amount::from_ccd(8),
+        );
+        claim_eq!(
+            view_minimum_next_bid(&ctx, &host).expect_report("View should pass"),
+            Some(NextBid {
+                minimum: Amount::from_ccd(11),
+                reserve_met: true,
+            })
+        );
+
+        // nothing beats the largest amount
+        host.state_mut()
+            .current_mut()
+            .auctions
+            .get_mut(&AUCTION_ID)
+            .expect_report("The auction should exist")
+            .highest_bid = Amount::from_micro_ccd(u64::MAX);
+        claim_eq!(
+            auction(&host).next_bid(Timestamp::from_timestamp_millis(0)),
+            Err(ContractError::Overflow)
+        );
+    }
+
+    #[concordium_test]
+    fn test_can_bid() {
+        let mut host = host_with_param(&InitParameter {
+            min_bid: Amount::from_ccd(5),
+            ..item_and_param()
+        });
+        let bidder = new_account();
+        let can_bid_with =
+            |host: &TestHost<VersionedState<TestStateApi>>, amount: Amount, time: u64| {
+                let parameter_bytes = to_bytes(&(AUCTION_ID, bidder, amount));
+                let mut ctx = new_ctx(SELLER, SELLER, time);
+                ctx.set_parameter(&parameter_bytes);
+                can_bid(&ctx, host).expect_report("View should pass")
+            };
+
+        claim_eq!(
+            can_bid_with(&host, Amount::from_ccd(5), 0),
+            BidEligibility::Eligible
+        );
+        claim_eq!(
+            can_bid_with(&host, Amount::from_ccd(4), 0),
+            BidEligibility::TooLow(Amount::from_ccd(5))
+        );
+        claim_eq!(
+            can_bid_with(&host, Amount::from_ccd(5), AUCTION_END + 1),
+            BidEligibility::TooLate
+        );
+        host.state_mut().current_mut().paused = true;
+        claim_eq!(
+            can_bid_with(&host, Amount::from_ccd(5), 0),
+            BidEligibility::Paused
+        );
+    }
+
+    #[concordium_test]
+    fn test_view_config() {
+        let royalty = Royalty {
+            creator: new_account(),
+            bps: 500,
+        };
+        let host = host_with_param(&InitParameter {
+            reserve: Some(Amount::from_ccd(10)),
+            royalty: Some(royalty),
+            ..item_and_param()
+        });
+        let config =
+            view_config(&new_ctx(SELLER, SELLER, 0), &host).expect_report("View should pass");
+        claim_eq!(config.item, item());
+        claim_eq!(config.seller, SELLER);
+        claim_eq!(config.reserve, Some(Amount::from_ccd(10)));
+        claim_eq!(config.royalty, Some(royalty));
+    }
+
+    #[concordium_test]
+    fn test_ccd_owed() {
+        let mut host = initialized_host();
+        let (ten, twenty) = (Amount::from_ccd(10), Amount::from_ccd(20));
+        bid(
+            &mut host,
+            &new_ctx(SELLER, new_account(), 0),
+            ten,
+            Amount::zero(),
+        );
+        bid(&mut host, &new_ctx(SELLER, new_account(), 0), twenty, ten);
+        // the outbid bid waiting to be withdrawn and the highest bid
+        claim_eq!(host.state().current().ccd_owed(), ten + twenty);
+
+        auction_settle(
+            &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        // the winning bid stays owed until the proceeds are claimed
+        claim_eq!(host.state().current().ccd_owed(), ten + twenty);
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(host.state().current().ccd_owed(), ten);
+    }
+
+    #[concordium_test]
+    fn test_supports() {
+        let host = initialized_host();
+        let query_bytes = to_bytes(&SupportsQueryParams {
+            queries: vec![
+                StandardIdentifierOwned::new_unchecked("CIS-0".into()),
+                StandardIdentifierOwned::new_unchecked("CCD-AUCTION".into()),
+                StandardIdentifierOwned::new_unchecked("CIS-2".into()),
+            ],
+        });
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&query_bytes);
+        let response = supports(&ctx, &host).expect_report("Query should pass");
+        claim_eq!(
+            to_bytes(&response),
+            to_bytes(&SupportsQueryResponse::from(vec![
+                SupportResult::Support,
+                SupportResult::Support,
+                SupportResult::NoSupport
+            ]))
+        );
+    }
+
+    #[concordium_test]
+    fn test_permit_bid() {
+        let mut host = initialized_host();
+        let signer = new_account();
+        let sponsor = new_account();
+        let amount = Amount::from_ccd(10);
+
+        let key_bytes = to_bytes(&PublicKeyEd25519([1; 32]));
+        let mut ctx = new_ctx(SELLER, signer, 0);
+        ctx.set_parameter(&key_bytes);
+        set_permit_key(&ctx, &mut host).expect_report("Setting the key should pass");
+
+        let crypto_primitives = TestCryptoPrimitives::new();
+        crypto_primitives.setup_verify_ed25519_signature_mock(|_, _, _| true);
+        let permit_bytes = to_bytes(&PermitParam {
+            signature: SignatureEd25519([2; 64]),
+            signer,
+            message: PermitMessage {
+                contract_address: SELF_ADDRESS,
+                nonce: 0,
+                timestamp: Timestamp::from_timestamp_millis(AUCTION_END),
+                entry_point: OwnedEntrypointName::new_unchecked("bid".into()),
+                payload: to_bytes(&PermitBidPayload {
+                    auction_id: AUCTION_ID,
+                    amount,
+                }),
+            },
+        });
+        let mut ctx = new_ctx(SELLER, sponsor, 0);
+        ctx.set_parameter(&permit_bytes);
+        host.set_self_balance(amount);
+        permit(
+            &ctx,
+            &mut host,
+            amount,
+            &mut TestLogger::init(),
+            &crypto_primitives,
+        )
+        .expect_report("Permit should pass");
+        // the signer bid, not the sponsor
+        claim_eq!(auction(&host).highest_bidder, Some(signer));
+
+        // the same permit can't be used twice
+        expect_error(
+            permit(
+                &ctx,
+                &mut host,
+                amount,
+                &mut TestLogger::init(),
+                &crypto_primitives,
+            ),
+            PermitError::WrongNonce,
+            "Replaying the permit should fail",
+        );
+        let account_bytes = to_bytes(&signer);
+        ctx.set_parameter(&account_bytes);
+        claim_eq!(view_nonce(&ctx, &host).expect_report("View should pass"), 1);
+    }
+
+    #[concordium_test]
+    fn test_settle_bids() {
+        let mut host = initialized_host();
+        let (signer1, signer2) = (new_account(), new_account());
+        let (ten, twenty) = (Amount::from_ccd(10), Amount::from_ccd(20));
+        for (signer, amount) in [(signer1, ten), (signer2, twenty)] {
+            let account_bytes = to_bytes(&signer);
+            let mut ctx = new_ctx(SELLER, new_account(), 0);
+            ctx.set_parameter(&account_bytes);
+            auction_deposit(&ctx, &mut host, amount).expect_report("Depositing should pass");
+            let key_bytes = to_bytes(&PublicKeyEd25519([1; 32]));
+            let mut ctx = new_ctx(SELLER, signer, 0);
+            ctx.set_parameter(&key_bytes);
+            set_permit_key(&ctx, &mut host).expect_report("Setting the key should pass");
+        }
+
+        let order = |signer, amount| PermitParam {
+            signature: SignatureEd25519([2; 64]),
+            signer,
+            message: PermitMessage {
+                contract_address: SELF_ADDRESS,
+                nonce: 0,
+                timestamp: Timestamp::from_timestamp_millis(AUCTION_END),
+                entry_point: OwnedEntrypointName::new_unchecked("bid".into()),
+                payload: to_bytes(&PermitBidPayload {
+                    auction_id: AUCTION_ID,
+                    amount,
+                }),
+            },
+        };
+        let crypto_primitives = TestCryptoPrimitives::new();
+        crypto_primitives.setup_verify_ed25519_signature_mock(|_, _, _| true);
+        let orders_bytes = to_bytes(&vec![order(signer1, ten), order(signer2, twenty)]);
+        host.set_self_balance(ten + twenty);
+
+        let mut ctx = new_ctx(SELLER, new_account(), 0);
+        ctx.set_parameter(&orders_bytes);
+        expect_error(
+            settle_bids(&ctx, &mut host, &mut TestLogger::init(), &crypto_primitives),
+            PermitError::MissingRole,
+            "Settling without the role should fail",
+        );
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&orders_bytes);
+        let mut logger = TestLogger::init();
+        settle_bids(&ctx, &mut host, &mut logger, &crypto_primitives)
+            .expect_report("Settling should pass");
+        claim_eq!(auction(&host).highest_bidder, Some(signer2));
+        // the outbid signer can withdraw the bid, the deposits are used up
+        claim_eq!(pending_refund(&host, signer1), ten);
+        claim_eq!(host.state().current().ccd_owed(), ten + twenty);
+    }
+
+    #[concordium_test]
+    fn test_supports_permit() {
+        let host = initialized_host();
+        let query_bytes = to_bytes(&SupportsPermitQueryParams {
+            queries: vec![
+                OwnedEntrypointName::new_unchecked("bid".into()),
+                OwnedEntrypointName::new_unchecked("claimRefund".into()),
+                OwnedEntrypointName::new_unchecked("settle".into()),
+            ],
+        });
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&query_bytes);
+        let response = supports_permit(&ctx, &host).expect_report("Query should pass");
+        claim_eq!(
+            to_bytes(&response),
+            to_bytes(&SupportsQueryResponse::from(vec![
+                SupportResult::Support,
+                SupportResult::Support,
+                SupportResult::NoSupport
+            ]))
+        );
+    }
+
+    #[concordium_test]
+    fn test_settle_then_claim_proceeds() {
+        let seller = new_account();
+        let mut host = host_with_param(&InitParameter {
+            seller: Some(seller),
+            ..item_and_param()
+        });
+        let bidder = new_account();
+        let ten = Amount::from_ccd(10);
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, AUCTION_END),
+            ten,
+            Amount::zero(),
+        );
+        host.make_account_missing(seller);
+
+        // settling doesn't pay anyone, a missing seller can't block it
+        auction_settle(
+            &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder));
+        claim!(host.get_transfers().is_empty());
+
+        let ctx = new_ctx(SELLER, new_account(), AUCTION_END + 1);
+        expect_error(
+            auction_claim_proceeds(&ctx, &mut host, &mut TestLogger::init()),
+            ProceedsClaimError::TransferFailed,
+            "Paying a missing seller should fail",
+        );
+        // the proceeds stay owed until the payout goes through
+        claim_eq!(auction(&host).unclaimed_proceeds, ten);
+
+        // with the seller around the claim goes through once
+        let mut host = host_with_param(&InitParameter {
+            seller: Some(seller),
+            ..item_and_param()
+        });
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, AUCTION_END),
+            ten,
+            Amount::zero(),
+        );
+        auction_settle(
+            &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(host.get_transfers(), [(seller, ten)]);
+        expect_error(
+            auction_claim_proceeds(&ctx, &mut host, &mut TestLogger::init()),
+            ProceedsClaimError::NothingToClaim,
+            "Claiming twice should fail",
+        );
+    }
+
+    #[concordium_test]
+    fn test_dispute_window() {
+        let mut host = host_with_param(&InitParameter {
+            dispute_window: Duration::from_millis(100),
+            arbitrator: Some(new_account()),
+            ..item_and_param()
+        });
+        let bidder = new_account();
+        let ten = Amount::from_ccd(10);
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, AUCTION_END),
+            ten,
+            Amount::zero(),
+        );
+        let settled_at = AUCTION_END + 1;
+        auction_settle(
+            &new_ctx(SELLER, SELLER, settled_at),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        let closes_at = settled_at + 100;
+        let claim = |host: &mut TestHost<VersionedState<TestStateApi>>, slot_time| {
+            auction_claim_proceeds(
+                &new_ctx(SELLER, SELLER, slot_time),
+                host,
+                &mut TestLogger::init(),
+            )
+        };
+        expect_error(
+            claim(&mut host, settled_at),
+            ProceedsClaimError::DisputeWindow {
+                closes_at: Timestamp::from_timestamp_millis(closes_at),
+            },
+            "The proceeds stay in escrow during the dispute window",
+        );
+
+        expect_error(
+            auction_open_dispute(
+                &new_ctx(SELLER, SELLER, settled_at),
+                &mut host,
+                &mut TestLogger::init(),
+            ),
+            DisputeError::NotWinner,
+            "Only the winner can dispute",
+        );
+        let mut logger = TestLogger::init();
+        auction_open_dispute(&new_ctx(SELLER, bidder, settled_at), &mut host, &mut logger)
+            .expect_report("Disputing should pass");
+        claim_eq!(
+            logger.logs,
+            [event(
+                3,
+                AuctionEvent::DisputeOpened {
+                    auction_id: AUCTION_ID,
+                    winner: bidder
+                }
+            )]
+        );
+        // the dispute outlives the window
+        expect_error(
+            claim(&mut host, closes_at),
+            ProceedsClaimError::Disputed,
+            "Disputed proceeds are frozen",
+        );
+
+        auction_close_dispute(
+            &new_ctx(SELLER, bidder, closes_at),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Closing the dispute should pass");
+        expect_error(
+            auction_open_dispute(
+                &new_ctx(SELLER, bidder, closes_at),
+                &mut host,
+                &mut TestLogger::init(),
+            ),
+            DisputeError::WindowClosed,
+            "Disputes can only be opened during the window",
+        );
+        claim(&mut host, closes_at).expect_report("Claiming should pass");
+        claim_eq!(host.get_transfers(), [(SELLER, ten)]);
+    }
+
+    #[concordium_test]
+    fn test_payout_delay() {
+        let mut host = host_with_param(&InitParameter {
+            payout_delay: Duration::from_millis(100),
+            ..item_and_param()
+        });
+        let ten = Amount::from_ccd(10);
+        bid(
+            &mut host,
+            &new_ctx(SELLER, new_account(), AUCTION_END),
+            ten,
+            Amount::zero(),
+        );
+        auction_settle(
+            &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        let unlocks_at = AUCTION_END + 1 + 100;
+        expect_error(
+            auction_claim_proceeds(
+                &new_ctx(SELLER, SELLER, unlocks_at - 1),
+                &mut host,
+                &mut TestLogger::init(),
+            ),
+            ProceedsClaimError::PayoutLocked {
+                unlocks_at: Timestamp::from_timestamp_millis(unlocks_at),
+            },
+            "The proceeds are locked until the delay is over",
+        );
+        auction_claim_proceeds(
+            &new_ctx(SELLER, SELLER, unlocks_at),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Claiming after the delay should pass");
+        claim_eq!(host.get_transfers(), [(SELLER, ten)]);
+    }
+
+    #[concordium_test]
+    fn test_confirm_delivery() {
+        let settled_host = |bidder| {
+            let mut host = host_with_param(&InitParameter {
+                delivery_timeout: Duration::from_millis(100),
+                ..item_and_param()
+            });
+            bid(
+                &mut host,
+                &new_ctx(SELLER, bidder, AUCTION_END),
+                Amount::from_ccd(10),
+                Amount::zero(),
+            );
+            auction_settle(
+                &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+                &mut host,
+                &mut TestLogger::init(),
+            )
+            .expect_report("Settling should pass");
+            host
+        };
+        let release_at = AUCTION_END + 1 + 100;
+        let bidder = new_account();
+        let mut host = settled_host(bidder);
+        expect_error(
+            auction_claim_proceeds(
+                &new_ctx(SELLER, SELLER, release_at - 1),
+                &mut host,
+                &mut TestLogger::init(),
+            ),
+            ProceedsClaimError::DeliveryPending {
+                release_at: Timestamp::from_timestamp_millis(release_at),
+            },
+            "The proceeds wait for the delivery",
+        );
+        expect_error(
+            auction_confirm_delivery(
+                &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+                &mut host,
+                &mut TestLogger::init(),
+            ),
+            ProceedsClaimError::NotWinner,
+            "Only the winner can confirm the delivery",
+        );
+        auction_confirm_delivery(
+            &new_ctx(SELLER, bidder, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Confirming should pass");
+        claim_eq!(host.get_transfers(), [(SELLER, Amount::from_ccd(10))]);
+
+        // without a confirmation the proceeds are released after the timeout
+        let mut host = settled_host(bidder);
+        auction_claim_proceeds(
+            &new_ctx(SELLER, SELLER, release_at),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Claiming after the timeout should pass");
+        claim_eq!(host.get_transfers(), [(SELLER, Amount::from_ccd(10))]);
+    }
+
+    #[concordium_test]
+    fn test_resolve_dispute() {
+        let arbitrator = new_account();
+        let mut host = host_with_param(&InitParameter {
+            dispute_window: Duration::from_millis(100),
+            arbitrator: Some(arbitrator),
+            ..item_and_param()
+        });
+        let bidder = new_account();
+        let ten = Amount::from_ccd(10);
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, AUCTION_END),
+            ten,
+            Amount::zero(),
+        );
+        auction_settle(
+            &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        auction_open_dispute(
+            &new_ctx(SELLER, bidder, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Disputing should pass");
+
+        // the winner gets a quarter back
+        let parameter_bytes = to_bytes(&(AUCTION_ID, 2500u64));
+        let mut ctx = new_ctx(SELLER, SELLER, AUCTION_END + 1);
+        ctx.set_parameter(&parameter_bytes);
+        expect_error(
+            auction_resolve_dispute(&ctx, &mut host, &mut TestLogger::init()),
+            DisputeError::NotArbitrator,
+            "Only the arbitrator can resolve a dispute",
+        );
+        ctx.set_sender(Address::Account(arbitrator));
+        let mut logger = TestLogger::init();
+        auction_resolve_dispute(&ctx, &mut host, &mut logger)
+            .expect_report("Resolving should pass");
+        let (winner_amount, seller_amount) = (
+            Amount::from_micro_ccd(2_500_000),
+            Amount::from_micro_ccd(7_500_000),
+        );
+        claim_eq!(
+            host.get_transfers(),
+            [(bidder, winner_amount), (SELLER, seller_amount)]
+        );
+        claim_eq!(
+            logger.logs,
+            [event(
+                4,
+                AuctionEvent::DisputeResolved {
+                    auction_id: AUCTION_ID,
+                    arbitrator,
+                    winner: bidder,
+                    winner_bps: 2500,
+                    winner_amount,
+                    seller_amount,
+                }
+            )]
+        );
+        expect_error(
+            auction_resolve_dispute(&ctx, &mut host, &mut TestLogger::init()),
+            DisputeError::NotDisputed,
+            "A dispute is resolved once",
+        );
+    }
+
+    #[concordium_test]
+    fn test_timing_errors_carry_times() {
+        let mut host = initialized_host();
+        let amount = Amount::from_ccd(1);
+        host.set_self_balance(amount);
+        // the rejection tells the bidder when the auction ended
+        expect_error(
+            auction_bid(
+                &new_ctx(SELLER, new_account(), AUCTION_END + 1),
+                &mut host,
+                amount,
+                &mut TestLogger::init(),
+            ),
+            ContractError::BidTooLate {
+                ended_at: Timestamp::from_timestamp_millis(AUCTION_END),
+            },
+            "Bidding after the end should fail",
+        );
+        expect_error(
+            auction_settle(
+                &new_ctx(SELLER, SELLER, AUCTION_END),
+                &mut host,
+                &mut TestLogger::init(),
+            ),
+            ContractError::AuctionStillActive {
+                ends_at: Timestamp::from_timestamp_millis(AUCTION_END),
+            },
+            "Settling before the end should fail",
+        );
+    }
+
+    #[concordium_test]
+    fn test_create_auction_validation() {
+        let mut host = initialized_host();
+        let create = |param: InitParameter,
+                      slot_time: u64,
+                      host: &mut TestHost<VersionedState<TestStateApi>>| {
+            let parameter_bytes = create_parameter_bytes(&param);
+            let mut ctx = new_ctx(SELLER, SELLER, slot_time);
+            ctx.set_parameter(&parameter_bytes);
+            create_auction(&ctx, host, Amount::zero(), &mut TestLogger::init())
+        };
+        expect_error(
+            create(item_and_param(), AUCTION_END, &mut host),
+            InitError::EndInPast,
+            "Ending at the current time should fail",
+        );
+        expect_error(
+            create(
+                InitParameter {
+                    min_increment: BidIncrement::BasisPoints(10_001),
+                    ..item_and_param()
+                },
+                0,
+                &mut host,
+            ),
+            InitError::InvalidIncrement,
+            "An increment above 100% should fail",
+        );
+        expect_error(
+            create(
+                InitParameter {
+                    reserve: Some(Amount::from_ccd(10)),
+                    buy_now: Some(Amount::from_ccd(5)),
+                    ..item_and_param()
+                },
+                0,
+                &mut host,
+            ),
+            InitError::BuyNowTooLow,
+            "A buy-now price below the reserve should fail",
+        );
+        expect_error(
+            create(
+                InitParameter {
+                    dispute_window: Duration::from_millis(100),
+                    ..item_and_param()
+                },
+                0,
+                &mut host,
+            ),
+            InitError::MissingArbitrator,
+            "A dispute window without an arbitrator should fail",
+        );
+
+        // the admin sets the duration limits for new auctions
+        let limits_bytes = to_bytes(&(Duration::from_millis(10), Duration::from_millis(100)));
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&limits_bytes);
+        set_duration_limits(&ctx, &mut host).expect_report("Setting limits should pass");
+        expect_error(
+            create(item_and_param(), 0, &mut host),
+            InitError::DurationTooShort,
+            "Running shorter than the minimum should fail",
+        );
+        let long = InitParameter {
+            end: Timestamp::from_timestamp_millis(1_000),
+            ..item_and_param()
+        };
+        expect_error(
+            create(long, 0, &mut host),
+            InitError::DurationTooLong,
+            "Running longer than the maximum should fail",
+        );
+        let fits = InitParameter {
+            end: Timestamp::from_timestamp_millis(50),
+            ..item_and_param()
+        };
+        create(fits, 0, &mut host).expect_report("Creating within the limits should pass");
+    }
+
+    #[concordium_test]
+    fn test_item_validation() {
+        let mut host = initialized_host();
+        let cases = [
+            (
+                ItemMetadata {
+                    name: " ".into(),
+                    ..item()
+                },
+                InitError::EmptyItemName,
+            ),
+            (
+                ItemMetadata {
+                    description: "x".repeat(MAX_ITEM_DESCRIPTION_LEN + 1),
+                    ..item()
+                },
+                InitError::ItemTooLarge,
+            ),
+            (
+                ItemMetadata {
+                    url: "example.com/starry night".into(),
+                    ..item()
+                },
+                InitError::InvalidItemUrl,
+            ),
+            (
+                ItemMetadata {
+                    url: String::new(),
+                    ..item()
+                },
+                InitError::InvalidItemUrl,
+            ),
+        ];
+        for (item, error) in cases {
+            let parameter_bytes = create_parameter_bytes(&InitParameter {
+                item,
+                ..item_and_param()
+            });
+            let mut ctx = new_ctx(SELLER, SELLER, 0);
+            ctx.set_parameter(&parameter_bytes);
+            expect_error(
+                create_auction(&ctx, &mut host, Amount::zero(), &mut TestLogger::init()),
+                error,
+                "Invalid item metadata should fail",
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_settle_grace_period() {
+        let param = InitParameter {
+            settle_grace_period: Duration::from_millis(10),
+            ..item_and_param()
+        };
+        let mut host = host_with_param(&param);
+        let keeper = new_account();
+        expect_error(
+            auction_settle(
+                &new_ctx(SELLER, keeper, AUCTION_END + 10),
+                &mut host,
+                &mut TestLogger::init(),
+            ),
+            ContractError::SellerGracePeriod {
+                open_at: Timestamp::from_timestamp_millis(AUCTION_END + 10),
+            },
+            "Settling during the grace period should fail for others",
+        );
+        auction_settle(
+            &new_ctx(SELLER, keeper, AUCTION_END + 11),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Anyone can settle after the grace period");
+
+        // the seller doesn't have to wait
+        let mut host = host_with_param(&param);
+        auction_settle(
+            &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("The seller can settle during the grace period");
+    }
+
+    #[concordium_test]
+    fn test_keeper_bounty() {
+        let mut host = initialized_host();
+        let bounty = Amount::from_ccd(1);
+        let parameter_bytes = create_parameter_bytes(&item_and_param());
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&parameter_bytes);
+        let auction_id = create_auction(&ctx, &mut host, bounty, &mut TestLogger::init())
+            .expect_report("Creating should pass");
+        host.set_self_balance(bounty);
+        claim_eq!(host.state().current().ccd_owed(), bounty);
+
+        // whoever settles the ended auction collects the bounty, once
+        let keeper = new_account();
+        let auction_id_bytes = to_bytes(&auction_id);
+        let mut ctx = new_ctx(SELLER, keeper, AUCTION_END + 1);
+        ctx.set_parameter(&auction_id_bytes);
+        auction_settle(&ctx, &mut host, &mut TestLogger::init())
+            .expect_report("Settling should pass");
+        claim_eq!(host.get_transfers(), [(keeper, bounty)]);
+        claim_eq!(host.state().current().ccd_owed(), Amount::zero());
+
+        // finalizing a candle auction earns the bounty the same way
+        let secret = vec![42u8; 16];
+        let seed_hash = HashSha2256([9; 32]);
+        let parameter_bytes = create_parameter_bytes(&InitParameter {
+            auction_type: AuctionType::Candle {
+                closing_window: Duration::from_millis(0),
+                seed_hash,
+            },
+            ..item_and_param()
+        });
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&parameter_bytes);
+        let auction_id = create_auction(&ctx, &mut host, bounty, &mut TestLogger::init())
+            .expect_report("Creating should pass");
+        host.set_self_balance(bounty);
+        let crypto_primitives = TestCryptoPrimitives::new();
+        crypto_primitives.setup_hash_sha2_256_mock(move |_| seed_hash);
+        let secret_bytes = to_bytes(&(auction_id, secret));
+        let mut ctx = new_ctx(SELLER, keeper, AUCTION_END + 1);
+        ctx.set_parameter(&secret_bytes);
+        auction_finalize_candle(&ctx, &mut host, &mut TestLogger::init(), &crypto_primitives)
+            .expect_report("Finalizing should pass");
+        claim_eq!(host.get_transfers(), [(keeper, bounty), (keeper, bounty)]);
+        claim_eq!(host.state().current().ccd_owed(), Amount::zero());
+    }
+
+    #[concordium_test]
+    fn test_keeper_bounty_returned_on_buy_now() {
+        let mut host = initialized_host();
+        let bounty = Amount::from_ccd(1);
+        let buy_now = Amount::from_ccd(50);
+        let parameter_bytes = create_parameter_bytes(&InitParameter {
+            buy_now: Some(buy_now),
+            ..item_and_param()
+        });
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&parameter_bytes);
+        let auction_id = create_auction(&ctx, &mut host, bounty, &mut TestLogger::init())
+            .expect_report("Creating should pass");
+
+        // the sale settles itself, so the seller gets the bounty back
+        host.set_self_balance(bounty + buy_now);
+        let bid_bytes = to_bytes(&auction_id);
+        let mut ctx = new_ctx(SELLER, new_account(), 0);
+        ctx.set_parameter(&bid_bytes);
+        auction_bid(&ctx, &mut host, buy_now, &mut TestLogger::init())
+            .expect_report("Bidding should pass");
+        claim_eq!(host.get_transfers(), [(SELLER, bounty)]);
+        claim_eq!(host.state().current().ccd_owed(), buy_now);
+    }
+
+    #[concordium_test]
+    fn test_settle_batch() {
+        let mut host = initialized_host();
+        let bidder = new_account();
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, AUCTION_END),
+            Amount::from_ccd(10),
+            Amount::zero(),
+        );
+        let later_end = Timestamp::from_timestamp_millis(100);
+        let parameter_bytes = create_parameter_bytes(&InitParameter {
+            end: later_end,
+            ..item_and_param()
+        });
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&parameter_bytes);
+        let running = create_auction(&ctx, &mut host, Amount::zero(), &mut TestLogger::init())
+            .expect_report("Creating should pass");
+
+        let batch_bytes = to_bytes(&vec![AUCTION_ID, running, 7]);
+        let mut ctx = new_ctx(SELLER, new_account(), AUCTION_END + 1);
+        ctx.set_parameter(&batch_bytes);
+        let outcomes = auction_settle_batch(&ctx, &mut host, &mut TestLogger::init())
+            .expect_report("Settling the batch should pass");
+        // the running and the unknown auction don't stop the ended one
+        claim_eq!(
+            outcomes,
+            [
+                (AUCTION_ID, BatchSettlement::Settled),
+                (
+                    running,
+                    BatchSettlement::Skipped(ContractError::AuctionStillActive {
+                        ends_at: later_end
+                    })
+                ),
+                (7, BatchSettlement::Skipped(ContractError::UnknownAuction)),
+            ]
+        );
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder));
+    }
+
+    #[concordium_test]
+    fn test_top_up() {
+        let mut host = initialized_host();
+        let (bidder1, bidder2) = (new_account(), new_account());
+        let (ten, twenty) = (Amount::from_ccd(10), Amount::from_ccd(20));
+        bid(&mut host, &new_ctx(SELLER, bidder1, 0), ten, Amount::zero());
+        bid(&mut host, &new_ctx(SELLER, bidder2, 0), twenty, ten);
+
+        // the outbid 10 plus 15 more beat the 20
+        let five = Amount::from_ccd(5);
+        let ctx = new_ctx(SELLER, bidder1, 0);
+        auction_top_up(&ctx, &mut host, ten + five, &mut TestLogger::init())
+            .expect_report("Topping up should pass");
+        claim_eq!(auction(&host).highest_bidder, Some(bidder1));
+        claim_eq!(auction(&host).highest_bid, twenty + five);
+        claim_eq!(pending_refund(&host, bidder1), Amount::zero());
+
+        // the winning bidder raises its own bid without being refunded
+        auction_top_up(&ctx, &mut host, five, &mut TestLogger::init())
+            .expect_report("Raising the winning bid should pass");
+        claim_eq!(auction(&host).highest_bid, twenty + ten);
+        claim_eq!(pending_refund(&host, bidder1), Amount::zero());
+        let parameter_bytes = to_bytes(&(AUCTION_ID, bidder1));
+        let mut view_ctx = new_ctx(SELLER, bidder1, 0);
+        view_ctx.set_parameter(&parameter_bytes);
+        claim_eq!(
+            view_standing_bid(&view_ctx, &host).expect_report("View should pass"),
+            twenty + ten
+        );
+
+        expect_error(
+            auction_top_up(
+                &new_ctx(SELLER, new_account(), 0),
+                &mut host,
+                five,
+                &mut TestLogger::init(),
+            ),
+            ContractError::NothingToTopUp,
+            "Topping up without a bid should fail",
+        );
+    }
+
+    #[concordium_test]
+    fn test_aggregate_bids() {
+        let mut host = host_with_param(&InitParameter {
+            aggregate_bids: true,
+            ..item_and_param()
+        });
+        let (bidder1, bidder2) = (new_account(), new_account());
+        let (ten, twenty) = (Amount::from_ccd(10), Amount::from_ccd(20));
+        bid(&mut host, &new_ctx(SELLER, bidder1, 0), ten, Amount::zero());
+        bid(&mut host, &new_ctx(SELLER, bidder2, 0), twenty, ten);
+        // 10 more on top of the outbid 10 doesn't beat 20
+        host.set_self_balance(ten + twenty + ten);
+        expect_error(
+            auction_bid(
+                &new_ctx(SELLER, bidder1, 0),
+                &mut host,
+                ten,
+                &mut TestLogger::init(),
+            ),
+            ContractError::BidTooLow {
+                minimum_required: twenty + Amount::from_micro_ccd(1),
+            },
+            "The sum has to beat the highest bid",
+        );
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder1, 0),
+            Amount::from_ccd(11),
+            ten + twenty,
+        );
+        claim_eq!(auction(&host).highest_bidder, Some(bidder1));
+        claim_eq!(auction(&host).highest_bid, Amount::from_ccd(21));
+        claim_eq!(pending_refund(&host, bidder1), Amount::zero());
+    }
+
+    #[concordium_test]
+    fn test_bid_with_beneficiary() {
+        let mut host = initialized_host();
+        let (user, other) = (new_account(), new_account());
+        let ten = Amount::from_ccd(10);
+        let parameter_bytes = to_bytes(&BidParams {
+            auction_id: AUCTION_ID,
+            beneficiary: Some(user),
+            memo: Vec::new(),
+            terms: None,
+        });
+        // a custodian contract bids for its user, once the user approved it
+        let mut ctx = new_ctx(SELLER, new_account(), 0);
+        ctx.set_sender(Address::Contract(TOKEN_CONTRACT));
+        ctx.set_parameter(&parameter_bytes);
+        host.set_self_balance(ten);
+        expect_error(
+            auction_bid(&ctx, &mut host, ten, &mut TestLogger::init()),
+            ContractError::NotOperator,
+            "Bidding for an account that didn't approve the sender should fail",
+        );
+        let update_bytes = to_bytes(&UpdateOperatorParams(vec![UpdateOperator {
+            update: OperatorUpdate::Add,
+            operator: Address::Contract(TOKEN_CONTRACT),
+        }]));
+        let mut user_ctx = new_ctx(SELLER, user, 0);
+        user_ctx.set_parameter(&update_bytes);
+        update_operator(&user_ctx, &mut host).expect_report("Adding should pass");
+        bid(&mut host, &ctx, ten, Amount::zero());
+        claim_eq!(auction(&host).highest_bidder, Some(user));
+
+        // a blacklisted account can't bid through an account that approved it
+        let excluded = new_account();
+        let blacklist_bytes = to_bytes(&(AUCTION_ID, excluded));
+        let mut seller_ctx = new_ctx(SELLER, SELLER, 0);
+        seller_ctx.set_parameter(&blacklist_bytes);
+        add_to_blacklist(&seller_ctx, &mut host, &mut TestLogger::init())
+            .expect_report("Blacklisting should pass");
+        let update_bytes = to_bytes(&UpdateOperatorParams(vec![UpdateOperator {
+            update: OperatorUpdate::Add,
+            operator: Address::Account(excluded),
+        }]));
+        user_ctx.set_parameter(&update_bytes);
+        update_operator(&user_ctx, &mut host).expect_report("Adding should pass");
+        let mut excluded_ctx = new_ctx(SELLER, excluded, 0);
+        excluded_ctx.set_parameter(&parameter_bytes);
+        let twenty = Amount::from_ccd(20);
+        host.set_self_balance(ten + twenty);
+        expect_error(
+            auction_bid(&excluded_ctx, &mut host, twenty, &mut TestLogger::init()),
+            ContractError::Blacklisted,
+            "A blacklisted sender should be rejected",
+        );
+
+        // the refund goes to the user, not the custodian
+        bid(
+            &mut host,
+            &new_ctx(SELLER, other, 0),
+            Amount::from_ccd(20),
+            ten,
+        );
+        claim_eq!(pending_refund(&host, user), ten);
+    }
+
+    #[concordium_test]
+    fn test_bid_memo() {
+        let mut host = initialized_host();
+        let bidder = new_account();
+        let ten = Amount::from_ccd(10);
+        let memo = b"order-1234".to_vec();
+        let parameter_bytes = to_bytes(&BidParams {
+            auction_id: AUCTION_ID,
+            beneficiary: None,
+            memo: memo.clone(),
+            terms: None,
+        });
+        let mut ctx = new_ctx(SELLER, bidder, 0);
+        ctx.set_parameter(&parameter_bytes);
+        host.set_self_balance(ten);
+        let mut logger = TestLogger::init();
+        auction_bid(&ctx, &mut host, ten, &mut logger).expect_report("Bidding should pass");
+        claim_eq!(
+            logger.logs,
+            [event(
+                1,
+                AuctionEvent::Bid(BidEvent {
+                    bidder,
+                    amount: ten,
+                    new_end: Timestamp::from_timestamp_millis(AUCTION_END),
+                    timestamp: Timestamp::from_timestamp_millis(0),
+                    memo: memo.clone(),
+                })
+            )]
+        );
+        claim_eq!(
+            auction(&host)
+                .bid_history
+                .get(&0)
+                .map(|bid| bid.memo.clone()),
+            Some(memo)
+        );
+
+        let parameter_bytes = to_bytes(&BidParams {
+            auction_id: AUCTION_ID,
+            beneficiary: None,
+            memo: vec![0; MAX_BID_MEMO_LEN + 1],
+            terms: None,
+        });
+        ctx.set_parameter(&parameter_bytes);
+        let twenty = Amount::from_ccd(20);
+        host.set_self_balance(ten + twenty);
+        expect_error(
+            auction_bid(&ctx, &mut host, twenty, &mut logger),
+            ContractError::MemoTooLong,
+            "A memo above the limit should be rejected",
+        );
+    }
+
+    #[concordium_test]
+    fn test_terms_of_sale() {
+        let terms = HashSha2256([3; 32]);
+        let mut host = host_with_param(&InitParameter {
+            terms_hash: Some(terms),
+            ..item_and_param()
+        });
+        let bidder = new_account();
+        let ten = Amount::from_ccd(10);
+        let bid_bytes = |terms| {
+            to_bytes(&BidParams {
+                auction_id: AUCTION_ID,
+                beneficiary: None,
+                memo: Vec::new(),
+                terms,
+            })
+        };
+        host.set_self_balance(ten);
+        for (terms, msg) in [
+            (None, "A bid without the terms should be rejected"),
+            (
+                Some(HashSha2256([4; 32])),
+                "A bid with other terms should be rejected",
+            ),
+        ] {
+            let parameter_bytes = bid_bytes(terms);
+            let mut ctx = new_ctx(SELLER, bidder, 0);
+            ctx.set_parameter(&parameter_bytes);
+            expect_error(
+                auction_bid(&ctx, &mut host, ten, &mut TestLogger::init()),
+                ContractError::TermsNotAccepted,
+                msg,
+            );
+        }
+
+        let parameter_bytes = bid_bytes(Some(terms));
+        let mut ctx = new_ctx(SELLER, bidder, 0);
+        ctx.set_parameter(&parameter_bytes);
+        bid(&mut host, &ctx, ten, Amount::zero());
+        claim_eq!(auction(&host).highest_bidder, Some(bidder));
+    }
+
+    #[concordium_test]
+    fn test_emergency_withdraw() {
+        let mut host = initialized_host();
+        let recipient = new_account();
+        let amount = Amount::from_ccd(10);
+        host.set_self_balance(amount);
+        let parameter_bytes = to_bytes(&(recipient, amount));
+        let mut ctx = new_ctx(SELLER, new_account(), 0);
+        ctx.set_parameter(&parameter_bytes);
+        expect_error(
+            declare_emergency_withdraw(&ctx, &mut host, &mut TestLogger::init()),
+            AdminError::OnlyAdmin,
+            "Only the admin can declare an emergency withdrawal",
+        );
+
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&parameter_bytes);
+        let mut logger = TestLogger::init();
+        declare_emergency_withdraw(&ctx, &mut host, &mut logger)
+            .expect_report("Declaring should pass");
+        let executable_at = EMERGENCY_WITHDRAW_DELAY.millis();
+        claim_eq!(
+            logger.logs,
+            [event(
+                1,
+                AuctionEvent::EmergencyWithdrawDeclared(EmergencyWithdrawal {
+                    to: recipient,
+                    amount,
+                    executable_at: Timestamp::from_timestamp_millis(executable_at),
+                })
+            )]
+        );
+
+        // users get the whole delay to react
+        expect_error(
+            emergency_withdraw(
+                &new_ctx(SELLER, SELLER, executable_at - 1),
+                &mut host,
+                &mut TestLogger::init(),
+            ),
+            AdminError::EmergencyTimelock,
+            "The withdrawal can't run before the delay is over",
+        );
+        let mut logger = TestLogger::init();
+        emergency_withdraw(
+            &new_ctx(SELLER, SELLER, executable_at),
+            &mut host,
+            &mut logger,
+        )
+        .expect_report("Withdrawing should pass");
+        claim_eq!(host.get_transfers(), [(recipient, amount)]);
+        claim_eq!(
+            logger.logs,
+            [event(
+                2,
+                AuctionEvent::EmergencyWithdrawn {
+                    to: recipient,
+                    amount
+                }
+            )]
+        );
+        expect_error(
+            emergency_withdraw(
+                &new_ctx(SELLER, SELLER, executable_at),
+                &mut host,
+                &mut TestLogger::init(),
+            ),
+            AdminError::NoEmergencyWithdrawal,
+            "A withdrawal runs only once",
+        );
+    }
+
+    #[concordium_test]
+    fn test_relist() {
+        let mut host = initialized_host();
+        auction_settle(
+            &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::NotSold);
+
+        let end = Timestamp::from_timestamp_millis(100);
+        let parameter_bytes = to_bytes(&RelistParameter {
+            auction_id: AUCTION_ID,
+            end,
+            reserve: Some(Amount::from_ccd(5)),
+        });
+        let mut ctx = new_ctx(SELLER, new_account(), AUCTION_END + 1);
+        ctx.set_parameter(&parameter_bytes);
+        expect_error(
+            auction_relist(&ctx, &mut host, Amount::zero(), &mut TestLogger::init()),
+            RelistError::OnlySeller,
+            "Only the seller can relist",
+        );
+        ctx.set_sender(Address::Account(SELLER));
+        let mut logger = TestLogger::init();
+        auction_relist(&ctx, &mut host, Amount::zero(), &mut logger)
+            .expect_report("Relisting should pass");
+        claim_eq!(
+            logger.logs,
+            [event(
+                2,
+                AuctionEvent::AuctionRelisted {
+                    auction_id: AUCTION_ID,
+                    round: 2,
+                    end
+                }
+            )]
+        );
+        claim_eq!(auction(&host).auction_state, AuctionState::Continue);
+        claim_eq!(auction(&host).reserve, Some(Amount::from_ccd(5)));
+        expect_error(
+            auction_relist(&ctx, &mut host, Amount::zero(), &mut TestLogger::init()),
+            RelistError::NotUnsold,
+            "Only an unsold auction can be relisted",
+        );
+
+        // the new round takes bids until the new end
+        let bidder = new_account();
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, 50),
+            Amount::from_ccd(10),
+            Amount::zero(),
+        );
+        claim_eq!(auction(&host).highest_bidder, Some(bidder));
+    }
+
+    #[concordium_test]
+    fn test_view_rounds() {
+        let mut host = initialized_host();
+        let settle = |host: &mut TestHost<VersionedState<TestStateApi>>, slot_time| {
+            auction_settle(
+                &new_ctx(SELLER, SELLER, slot_time),
+                host,
+                &mut TestLogger::init(),
+            )
+            .expect_report("Settling should pass");
+        };
+        settle(&mut host, AUCTION_END + 1);
+
+        let end = Timestamp::from_timestamp_millis(100);
+        let parameter_bytes = to_bytes(&RelistParameter {
+            auction_id: AUCTION_ID,
+            end,
+            reserve: None,
+        });
+        let mut ctx = new_ctx(SELLER, SELLER, AUCTION_END + 1);
+        ctx.set_parameter(&parameter_bytes);
+        auction_relist(&ctx, &mut host, Amount::zero(), &mut TestLogger::init())
+            .expect_report("Relisting should pass");
+        let bidder = new_account();
+        let ten = Amount::from_ccd(10);
+        bid(&mut host, &new_ctx(SELLER, bidder, 50), ten, Amount::zero());
+        settle(&mut host, 101);
+
+        claim_eq!(
+            view_rounds(&new_ctx(SELLER, SELLER, 101), &host).expect_report("View should pass"),
+            [
+                RoundOutcome {
+                    round: 1,
+                    start: Timestamp::from_timestamp_millis(0),
+                    end: Timestamp::from_timestamp_millis(AUCTION_END),
+                    closed_at: Timestamp::from_timestamp_millis(AUCTION_END + 1),
+                    outcome: AuctionState::NotSold,
+                    highest_bid: Amount::zero(),
+                },
+                RoundOutcome {
+                    round: 2,
+                    start: Timestamp::from_timestamp_millis(AUCTION_END + 1),
+                    end,
+                    closed_at: Timestamp::from_timestamp_millis(101),
+                    outcome: AuctionState::Sold(bidder),
+                    highest_bid: ten,
+                }
+            ]
+        );
+    }
+
+    #[concordium_test]
+    fn test_auto_relist() {
+        let mut host = host_with_param(&InitParameter {
+            reserve: Some(Amount::from_ccd(20)),
+            auto_relist: Some(AutoRelist {
+                period: Duration::from_millis(100),
+                lowered_reserve: Some(Amount::from_ccd(15)),
+                max_retries: 1,
+            }),
+            settle_grace_period: Duration::from_millis(10),
+            ..item_and_param()
+        });
+        let bidder = new_account();
+        let ten = Amount::from_ccd(10);
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, AUCTION_END),
+            ten,
+            Amount::zero(),
+        );
+        // relisting waits for the end and the grace period like settling
+        expect_error(
+            auction_settle(
+                &new_ctx(SELLER, new_account(), AUCTION_END),
+                &mut host,
+                &mut TestLogger::init(),
+            ),
+            ContractError::AuctionStillActive {
+                ends_at: Timestamp::from_timestamp_millis(AUCTION_END),
+            },
+            "Relisting a running auction should fail",
+        );
+        expect_error(
+            auction_settle(
+                &new_ctx(SELLER, new_account(), AUCTION_END + 1),
+                &mut host,
+                &mut TestLogger::init(),
+            ),
+            ContractError::SellerGracePeriod {
+                open_at: Timestamp::from_timestamp_millis(AUCTION_END + 10),
+            },
+            "Only the seller can relist during the grace period",
+        );
+        claim_eq!(auction(&host).round, 1);
+        auction_settle(
+            &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        // the auction reopens with the lower reserve and the bid goes back
+        claim_eq!(auction(&host).auction_state, AuctionState::Continue);
+        claim_eq!(auction(&host).round, 2);
+        claim_eq!(auction(&host).reserve, Some(Amount::from_ccd(15)));
+        claim_eq!(
+            auction(&host).end,
+            Timestamp::from_timestamp_millis(AUCTION_END + 1 + 100)
+        );
+        claim_eq!(host.get_transfers(), [(bidder, ten)]);
+
+        // without retries left the reserve ends the auction as usual
+        bid(&mut host, &new_ctx(SELLER, bidder, 50), ten, Amount::zero());
+        auction_settle(
+            &new_ctx(SELLER, SELLER, 200),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::ReserveNotMet);
+    }
+
+    #[concordium_test]
+    fn test_pause() {
+        let mut host = initialized_host();
+        let bidder = new_account();
+        expect_error(
+            pause(&new_ctx(SELLER, bidder, 0), &mut host),
+            AdminError::MissingRole,
+            "Only the admin can pause",
+        );
+        pause(&new_ctx(SELLER, SELLER, 0), &mut host).expect_report("Pausing should pass");
+
+        let amount = Amount::from_ccd(10);
+        host.set_self_balance(amount);
+        let mut logger = TestLogger::init();
+        expect_error(
+            auction_bid(&new_ctx(SELLER, bidder, 0), &mut host, amount, &mut logger),
+            ContractError::ContractPaused,
+            "Bidding while paused should fail",
+        );
+        let commit_bytes = to_bytes(&(AUCTION_ID, HashSha2256([7; 32])));
+        let mut commit_ctx = new_ctx(SELLER, bidder, 0);
+        commit_ctx.set_parameter(&commit_bytes);
+        expect_error(
+            auction_commit(&commit_ctx, &mut host, amount, &mut TestLogger::init()),
+            SealedBidError::ContractPaused,
+            "Committing while paused should fail",
+        );
+        let lots_bytes = to_bytes(&(AUCTION_ID, vec![0u32]));
+        let mut package_ctx = new_ctx(SELLER, bidder, 0);
+        package_ctx.set_parameter(&lots_bytes);
+        expect_error(
+            auction_bid_package(&package_ctx, &mut host, amount, &mut TestLogger::init()),
+            ContractError::ContractPaused,
+            "Bidding on a package while paused should fail",
+        );
+        expect_error(
+            auction_settle(
+                &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+                &mut host,
+                &mut logger,
+            ),
+            ContractError::ContractPaused,
+            "Finalizing while paused should fail",
+        );
+
+        unpause(&new_ctx(SELLER, SELLER, 0), &mut host).expect_report("Unpausing should pass");
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, 0),
+            amount,
+            Amount::zero(),
+        );
+    }
+
+    #[concordium_test]
+    fn test_admin_handover() {
+        let mut host = initialized_host();
+        let admin = new_account();
+        let parameter_bytes = to_bytes(&Address::Account(admin));
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&parameter_bytes);
+        nominate_admin(&ctx, &mut host).expect_report("Nominating should pass");
+        claim_eq!(host.state().current().admin, Address::Account(SELLER));
+
+        expect_error(
+            accept_admin(&new_ctx(SELLER, new_account(), 0), &mut host),
+            AdminError::NotNominated,
+            "Only the nominee can accept",
+        );
+        accept_admin(&new_ctx(SELLER, admin, 0), &mut host).expect_report("Accepting should pass");
+
+        // the owner of the instance lost its rights with the role
+        expect_error(
+            pause(&new_ctx(SELLER, SELLER, 0), &mut host),
+            AdminError::MissingRole,
+            "Only the admin can pause",
+        );
+        pause(&new_ctx(SELLER, admin, 0), &mut host).expect_report("Pausing should pass");
+    }
+
+    #[concordium_test]
+    fn test_roles() {
+        let mut host = host_with_param(&InitParameter {
+            settle_grace_period: Duration::from_millis(10),
+            ..item_and_param()
+        });
+        let operator = new_account();
+        let grant = |host: &mut TestHost<VersionedState<TestStateApi>>, role| {
+            let parameter_bytes = to_bytes(&RoleParameter {
+                address: Address::Account(operator),
+                role,
+            });
+            let mut ctx = new_ctx(SELLER, SELLER, 0);
+            ctx.set_parameter(&parameter_bytes);
+            grant_role(&ctx, host).expect_report("Granting should pass");
+        };
+        grant(&mut host, Role::Pauser);
+        grant(&mut host, Role::Finalizer);
+        pause(&new_ctx(SELLER, operator, 0), &mut host).expect_report("Pausing should pass");
+
+        // the pause holds for finalizers too
+        let settle_ctx = new_ctx(SELLER, operator, AUCTION_END + 1);
+        expect_error(
+            auction_settle(&settle_ctx, &mut host, &mut TestLogger::init()),
+            ContractError::ContractPaused,
+            "Finalizing while paused should fail",
+        );
+        unpause(&new_ctx(SELLER, operator, 0), &mut host).expect_report("Unpausing should pass");
+
+        // a finalizer settles during the seller's grace period
+        auction_settle(&settle_ctx, &mut host, &mut TestLogger::init())
+            .expect_report("Finalizing should pass");
+
+        let parameter_bytes = to_bytes(&RoleParameter {
+            address: Address::Account(operator),
+            role: Role::Pauser,
+        });
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&parameter_bytes);
+        revoke_role(&ctx, &mut host).expect_report("Revoking should pass");
+        expect_error(
+            unpause(&new_ctx(SELLER, operator, 0), &mut host),
+            AdminError::MissingRole,
+            "Unpausing without the role should fail",
+        );
+    }
+
+    #[concordium_test]
+    fn test_bid_for_client() {
+        let mut host = initialized_host();
+        let client = new_account();
+        let custodian = Address::Contract(ContractAddress {
+            index: 3,
+            subindex: 0,
+        });
+        let ten = Amount::from_ccd(10);
+        let bid_bytes = to_bytes(&(AUCTION_ID, client));
+        let mut ctx = new_ctx(SELLER, client, 0);
+        ctx.set_sender(custodian);
+        ctx.set_parameter(&bid_bytes);
+        host.set_self_balance(ten);
+        expect_error(
+            auction_bid_for(&ctx, &mut host, ten, &mut TestLogger::init()),
+            ContractError::NotOperator,
+            "Bidding without approval should fail",
+        );
+
+        let update_bytes = to_bytes(&UpdateOperatorParams(vec![UpdateOperator {
+            update: OperatorUpdate::Add,
+            operator: custodian,
+        }]));
+        let mut client_ctx = new_ctx(SELLER, client, 0);
+        client_ctx.set_parameter(&update_bytes);
+        update_operator(&client_ctx, &mut host).expect_report("Adding should pass");
+        auction_bid_for(&ctx, &mut host, ten, &mut TestLogger::init())
+            .expect_report("Bidding for the client should pass");
+        claim_eq!(auction(&host).highest_bidder, Some(client));
+
+        // the refund of the outbid bid goes to the client, not the custodian
+        bid(
+            &mut host,
+            &new_ctx(SELLER, new_account(), 0),
+            ten + ten,
+            ten,
+        );
+        claim_eq!(pending_refund(&host, client), ten);
+    }
+
+    #[concordium_test]
+    fn test_seller_cannot_bid() {
+        let mut host = initialized_host();
+        // an alias differs from the address only in the last 3 bytes
+        let mut alias = SELLER;
+        alias.0[31] = 0;
+        let amount = Amount::from_ccd(10);
+        host.set_self_balance(amount);
+        expect_error(
+            auction_bid(
+                &new_ctx(SELLER, alias, 0),
+                &mut host,
+                amount,
+                &mut TestLogger::init(),
+            ),
+            ContractError::SellerCannotBid,
+            "The seller's alias can't bid",
+        );
+
+        let mut host = host_with_param(&InitParameter {
+            seller_can_bid: true,
+            ..item_and_param()
+        });
+        bid(
+            &mut host,
+            &new_ctx(SELLER, SELLER, 0),
+            amount,
+            Amount::zero(),
+        );
+    }
+
+    #[concordium_test]
+    fn test_verified_bidders() {
+        let verifier = ContractAddress {
+            index: 4,
+            subindex: 0,
+        };
+        let mut host = host_with_param(&InitParameter {
+            verifier: Some(verifier),
+            ..item_and_param()
+        });
+        let is_verified = OwnedEntrypointName::new_unchecked("isVerified".into());
+        host.setup_mock_entrypoint(verifier, is_verified.clone(), MockFn::returning_ok(false));
+        let bidder = new_account();
+        let amount = Amount::from_ccd(10);
+        host.set_self_balance(amount);
+        expect_error(
+            auction_bid(
+                &new_ctx(SELLER, bidder, 0),
+                &mut host,
+                amount,
+                &mut TestLogger::init(),
+            ),
+            ContractError::NotVerified,
+            "Unverified bidders can't bid",
+        );
+
+        host.setup_mock_entrypoint(verifier, is_verified, MockFn::returning_ok(true));
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, 0),
+            amount,
+            Amount::zero(),
+        );
+    }
+
+    #[concordium_test]
+    fn test_credential_gated_bids() {
+        let registry = ContractAddress {
+            index: 5,
+            subindex: 0,
+        };
+        let mut host = host_with_param(&InitParameter {
+            credential_registry: Some(registry),
+            ..item_and_param()
+        });
+        host.setup_mock_entrypoint(
+            registry,
+            OwnedEntrypointName::new_unchecked("credentialStatus".into()),
+            MockFn::returning_ok(CredentialStatus::Active),
+        );
+        let bidder = new_account();
+        let amount = Amount::from_ccd(10);
+        host.set_self_balance(amount);
+        expect_error(
+            auction_bid(
+                &new_ctx(SELLER, bidder, 0),
+                &mut host,
+                amount,
+                &mut TestLogger::init(),
+            ),
+            ContractError::NoActiveCredential,
+            "Bidding without a credential should fail",
+        );
+
+        let crypto_primitives = TestCryptoPrimitives::new();
+        crypto_primitives.setup_verify_ed25519_signature_mock(|_, _, _| true);
+        let parameter_bytes = to_bytes(&PresentCredentialParameter {
+            holder_id: PublicKeyEd25519([1; 32]),
+            signature: SignatureEd25519([2; 64]),
+        });
+        let mut ctx = new_ctx(SELLER, bidder, 0);
+        ctx.set_parameter(&parameter_bytes);
+        present_credential(&ctx, &mut host, &crypto_primitives)
+            .expect_report("Presenting should pass");
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, 0),
+            amount,
+            Amount::zero(),
+        );
+    }
+
+    #[concordium_test]
+    fn test_token_gated_bids() {
+        let gate_contract = ContractAddress {
+            index: 6,
+            subindex: 0,
+        };
+        let mut host = host_with_param(&InitParameter {
+            token_gate: Some(TokenGate {
+                contract: gate_contract,
+                token_id: TokenIdVec(vec![9]),
+                min_balance: TokenAmountU64(2),
+            }),
+            ..item_and_param()
+        });
+        let balance_of = OwnedEntrypointName::new_unchecked("balanceOf".into());
+        // the response isn't Clone, so it's built on every call
+        let balances = |balance| {
+            MockFn::new_v1(move |_, _, _, _| {
+                Ok((false, BalanceOfQueryResponse(vec![TokenAmountU64(balance)])))
+            })
+        };
+        host.setup_mock_entrypoint(gate_contract, balance_of.clone(), balances(1));
+        let bidder = new_account();
+        let amount = Amount::from_ccd(10);
+        host.set_self_balance(amount);
+        expect_error(
+            auction_bid(
+                &new_ctx(SELLER, bidder, 0),
+                &mut host,
+                amount,
+                &mut TestLogger::init(),
+            ),
+            ContractError::NotTokenHolder,
+            "Bidding with too few tokens should fail",
+        );
+
+        host.setup_mock_entrypoint(gate_contract, balance_of, balances(2));
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, 0),
+            amount,
+            Amount::zero(),
+        );
+    }
+
+    #[concordium_test]
+    fn test_bid_event() {
+        let mut host = initialized_host();
+        let bidder = new_account();
+        let amount = Amount::from_ccd(10);
+        host.set_self_balance(amount);
+        let mut logger = TestLogger::init();
+        auction_bid(&new_ctx(SELLER, bidder, 0), &mut host, amount, &mut logger)
+            .expect_report("Bidding should pass");
+        claim_eq!(
+            logger.logs,
+            [event(
+                1,
+                AuctionEvent::Bid(BidEvent {
+                    bidder,
+                    amount,
+                    new_end: Timestamp::from_timestamp_millis(AUCTION_END),
+                    timestamp: Timestamp::from_timestamp_millis(0),
+                    memo: Vec::new(),
+                })
+            )]
+        );
+    }
+
+    #[concordium_test]
+    fn test_lifecycle_events() {
+        let parameter_bytes = create_parameter_bytes(&item_and_param());
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let mut state_builder = TestStateBuilder::new();
+        let mut logger = TestLogger::init();
+        let state = auction_init(&ctx, &mut state_builder, Amount::zero(), &mut logger)
+            .expect_report("Contract initialize error");
+        claim_eq!(
+            logger.logs,
+            [event(
+                0,
+                AuctionEvent::AuctionCreated {
+                    auction_id: AUCTION_ID,
+                    seller: SELLER,
+                }
+            )]
+        );
+
+        let mut host = TestHost::new(state, state_builder);
+        let bidder = new_account();
+        let ctx = new_ctx(SELLER, bidder, AUCTION_END);
+        bid(&mut host, &ctx, Amount::from_ccd(10), Amount::zero());
+        let mut logger = TestLogger::init();
+        auction_settle(
+            &new_ctx(SELLER, SELLER, AUCTION_END + 1),
+            &mut host,
+            &mut logger,
+        )
+        .expect_report("Finalizing should pass");
+        claim_eq!(
+            logger.logs,
+            [event(
+                2,
+                AuctionEvent::AuctionFinalized {
+                    auction_id: AUCTION_ID,
+                    winner: Some(bidder),
+                    price: Amount::from_ccd(10),
+                }
+            )]
+        );
+    }
+
+    #[concordium_test]
+    fn test_event_sequence() {
+        let mut host = initialized_host();
+        let bidder = new_account();
+        let amount = Amount::from_ccd(10);
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder, 0),
+            amount,
+            Amount::zero(),
+        );
+        // init logged seq 0, the first bid seq 1
+        claim_eq!(host.state().current().next_event_seq, 2);
+
+        host.set_self_balance(amount + amount + amount);
+        let mut logger = TestLogger::init();
+        auction_bid(
+            &new_ctx(SELLER, new_account(), 0),
+            &mut host,
+            amount + amount,
+            &mut logger,
+        )
+        .expect_report("Bidding should pass");
+        let seqs: Vec<u64> = logger
+            .logs
+            .iter()
+            .map(|log| {
+                from_bytes::<SequencedEvent>(log)
+                    .expect_report("Event should parse")
+                    .seq
+            })
+            .collect();
+        claim_eq!(seqs, [2, 3]);
+        claim_eq!(host.state().current().next_event_seq, 4);
+    }
+
+    // the bytes logged for the event at the given sequence number
+    fn event(seq: u64, event: AuctionEvent) -> Vec<u8> {
+        to_bytes(&SequencedEvent { seq, event })
+    }
+
+    // pay out the proceeds recorded when the auction was settled
+    fn claim_proceeds(host: &mut TestHost<VersionedState<TestStateApi>>, logger: &mut TestLogger) {
+        auction_claim_proceeds(
+            &new_ctx(SELLER, new_account(), AUCTION_END + 1),
+            host,
+            logger,
+        )
+        .expect_report("Claiming the proceeds should pass");
+    }
+
+    fn pending_refund(
+        host: &TestHost<VersionedState<TestStateApi>>,
+        account: AccountAddress,
+    ) -> Amount {
+        pending_refund_in(host, account, BASE_CURRENCY)
+    }
+
+    fn pending_refund_in(
+        host: &TestHost<VersionedState<TestStateApi>>,
+        account: AccountAddress,
+        currency: u32,
+    ) -> Amount {
+        auction(host)
+            .pending_refunds
+            .get(&(account, currency))
+            .map_or(Amount::zero(), |owed| *owed)
+    }
+
+    fn initialized_host() -> TestHost<VersionedState<TestStateApi>> {
+        host_with_param(&item_and_param())
+    }
+
+    fn host_with_param(parameter: &InitParameter) -> TestHost<VersionedState<TestStateApi>> {
+        let parameter_bytes = create_parameter_bytes(parameter);
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let mut state_builder = TestStateBuilder::new();
+        let state = auction_init(
+            &ctx,
+            &mut state_builder,
+            Amount::zero(),
+            &mut TestLogger::init(),
+        )
+        .expect_report("Contract initialize error");
+        TestHost::new(state, state_builder)
+    }
+
+    // set the contract's fee and fee receiver as the admin
+    fn set_fees(host: &mut TestHost<VersionedState<TestStateApi>>, fee_bps: u64) {
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        let fee_bytes = to_bytes(&fee_bps);
+        ctx.set_parameter(&fee_bytes);
+        set_fee(&ctx, host).expect_report("Setting the fee should pass");
+        let receiver_bytes = to_bytes(&FEE_RECEIVER);
+        ctx.set_parameter(&receiver_bytes);
+        set_fee_receiver(&ctx, host).expect_report("Setting the fee receiver should pass");
+    }
+
+    #[concordium_test]
+    fn test_plain_transfer_does_not_affect_highest_bid() {
+        let mut host = initialized_host();
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        let (ten, twenty) = (Amount::from_ccd(10), Amount::from_ccd(20));
+        // someone sent 100 CCD to the contract outside of bidding
+        let stray = Amount::from_ccd(100);
+        bid(&mut host, &new_ctx(owner, bidder1, AUCTION_END), ten, stray);
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder2, AUCTION_END),
+            twenty,
+            stray + ten,
+        );
+        claim_eq!(auction(&host).highest_bid, twenty);
+        claim_eq!(pending_refund(&host, bidder1), ten);
+
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(host.get_transfers(), [(owner, twenty)]);
+    }
+
+    #[concordium_test]
+    fn test_protocol_fee() {
+        let mut host = initialized_host();
+        set_fees(&mut host, 250);
+        let seller = new_account();
+        let bidder = new_account();
+        let parameter_bytes = create_parameter_bytes(&item_and_param());
+        let mut ctx = new_ctx(seller, seller, 0);
+        ctx.set_parameter(&parameter_bytes);
+        let auction_id = create_auction(&ctx, &mut host, Amount::zero(), &mut TestLogger::init())
+            .expect_report("Creating should pass");
+        // the auction created before the fee was set keeps its zero fee
+        claim_eq!(auction(&host).fee_bps, 0);
+
+        let id_bytes = to_bytes(&auction_id);
+        let mut ctx = new_ctx(seller, bidder, AUCTION_END);
+        ctx.set_parameter(&id_bytes);
+        bid(&mut host, &ctx, Amount::from_ccd(100), Amount::zero());
+        let mut ctx = new_ctx(seller, seller, AUCTION_END + 1);
+        ctx.set_parameter(&id_bytes);
+        auction_settle(&ctx, &mut host, &mut TestLogger::init())
+            .expect_report("Finalizing should pass");
+        auction_claim_proceeds(&ctx, &mut host, &mut TestLogger::init())
+            .expect_report("Claiming the proceeds should pass");
+        claim_eq!(
+            host.get_transfers(),
+            [
+                (FEE_RECEIVER, Amount::from_micro_ccd(2_500_000)),
+                (seller, Amount::from_micro_ccd(97_500_000))
+            ]
+        );
+    }
+
+    #[concordium_test]
+    fn test_fee_above_cap_rejected() {
+        let mut host = initialized_host();
+        let fee_bytes = to_bytes(&(MAX_FEE_BPS + 1));
+        let mut ctx = new_ctx(SELLER, new_account(), 0);
+        ctx.set_parameter(&fee_bytes);
+        expect_error(
+            set_fee(&ctx, &mut host),
+            AdminError::MissingRole,
+            "Only the fee manager can set the fee",
+        );
+
+        let mut ctx = new_ctx(SELLER, SELLER, 0);
+        ctx.set_parameter(&fee_bytes);
+        expect_error(
+            set_fee(&ctx, &mut host),
+            AdminError::FeeTooHigh,
+            "Fee above the cap should fail",
+        );
+    }
+
+    #[concordium_test]
+    fn test_payment_split() {
+        let gallery = new_account();
+        let artist = new_account();
+        let mut host = host_with_param(&InitParameter {
+            payees: vec![(gallery, 3000), (artist, 7000)],
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder = new_account();
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder, AUCTION_END),
+            Amount::from_ccd(100),
+            Amount::zero(),
+        );
+
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(
+            host.get_transfers(),
+            [
+                (gallery, Amount::from_ccd(30)),
+                (artist, Amount::from_ccd(70))
+            ]
+        );
+    }
+
+    #[concordium_test]
+    fn test_creator_royalty() {
+        let creator = new_account();
+        let royalty = Royalty { creator, bps: 1000 };
+        let mut host = host_with_param(&InitParameter {
+            royalty: Some(royalty),
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder = new_account();
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder, AUCTION_END),
+            Amount::from_ccd(100),
+            Amount::zero(),
+        );
+
+        let mut logger = TestLogger::init();
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut logger,
+        )
+        .expect_report("Finalizing should pass");
+        claim_proceeds(&mut host, &mut logger);
+        claim_eq!(
+            host.get_transfers(),
+            [
+                (creator, Amount::from_ccd(10)),
+                (owner, Amount::from_ccd(90))
+            ]
+        );
+        claim_eq!(
+            logger.logs,
+            [
+                event(
+                    2,
+                    AuctionEvent::AuctionFinalized {
+                        auction_id: AUCTION_ID,
+                        winner: Some(bidder),
+                        price: Amount::from_ccd(100),
+                    }
+                ),
+                event(
+                    3,
+                    AuctionEvent::Royalty {
+                        creator,
+                        amount: Amount::from_ccd(10),
+                    }
+                )
+            ]
+        );
+    }
+
+    fn token_item() -> TokenItem {
+        TokenItem {
+            contract: TOKEN_CONTRACT,
+            token_id: TokenIdVec(vec![1]),
+            amount: TokenAmountU64(1),
+        }
+    }
+
+    // a token contract reporting a transfer of `amount` tokens from `from`
+    fn receive_tokens(
+        host: &mut TestHost<VersionedState<TestStateApi>>,
+        owner: AccountAddress,
+        contract: ContractAddress,
+        token_id: ContractTokenId,
+        amount: u64,
+        from: AccountAddress,
+    ) -> Result<(), EscrowError> {
+        let parameter_bytes = to_bytes(&OnReceivingCis2Params {
+            token_id,
+            amount: TokenAmountU64(amount),
+            from: Address::Account(from),
+            data: AdditionalData::from(to_bytes(&AUCTION_ID)),
+        });
+        let mut ctx = new_ctx(owner, owner, 0);
+        ctx.set_sender(Address::Contract(contract));
+        ctx.set_parameter(&parameter_bytes);
+        auction_on_receiving_cis2(&ctx, host, &mut TestLogger::init())
+    }
+
+    // the seller, or someone else, depositing an auctioned token
+    fn deposit(
+        host: &mut TestHost<VersionedState<TestStateApi>>,
+        owner: AccountAddress,
+        from: AccountAddress,
+        token: &TokenItem,
+    ) -> Result<(), EscrowError> {
+        receive_tokens(
+            host,
+            owner,
+            token.contract,
+            token.token_id.clone(),
+            token.amount.0,
+            from,
+        )
+    }
+
+    #[concordium_test]
+    fn test_token_escrow() {
+        let mut host = host_with_param(&InitParameter {
+            item_tokens: vec![token_item()],
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder = new_account();
+        let ctx = new_ctx(owner, bidder, AUCTION_END);
+        expect_error(
+            auction_bid(
+                &ctx,
+                &mut host,
+                Amount::from_ccd(10),
+                &mut TestLogger::init(),
+            ),
+            ContractError::ItemNotEscrowed,
+            "Bidding before the deposit should fail",
+        );
+
+        expect_error(
+            deposit(&mut host, owner, bidder, &token_item()),
+            EscrowError::NotSeller,
+            "Only the seller can deposit the token",
+        );
+        deposit(&mut host, owner, owner, &token_item()).expect_report("Depositing should pass");
+        claim!(auction(&host).item_escrowed);
+        bid(&mut host, &ctx, Amount::from_ccd(10), Amount::zero());
+    }
+
+    #[concordium_test]
+    fn test_token_bundle_escrow() {
+        let second = TokenItem {
+            token_id: TokenIdVec(vec![2]),
+            amount: TokenAmountU64(5),
+            ..token_item()
+        };
+        let mut host = host_with_param(&InitParameter {
+            item_tokens: vec![token_item(), second.clone()],
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        deposit(&mut host, owner, owner, &token_item()).expect_report("Depositing should pass");
+        // the bundle isn't complete yet
+        claim!(!auction(&host).item_escrowed);
+        expect_error(
+            deposit(&mut host, owner, owner, &token_item()),
+            EscrowError::AlreadyEscrowed,
+            "Depositing the same token twice should fail",
+        );
+        deposit(&mut host, owner, owner, &second).expect_report("Depositing should pass");
+        claim!(auction(&host).item_escrowed);
+    }
+
+    #[concordium_test]
+    fn test_token_bids() {
+        let mut host = host_with_param(&InitParameter {
+            payment_tokens: vec![PaymentToken {
+                contract: PAYMENT_CONTRACT,
+                token_id: TokenIdVec(Vec::new()),
+                min_bid: Amount::zero(),
+            }],
+            ..item_and_param()
+        });
+        host.setup_mock_entrypoint(
+            PAYMENT_CONTRACT,
+            OwnedEntrypointName::new_unchecked("transfer".into()),
+            MockFn::returning_ok(()),
+        );
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        expect_error(
+            auction_bid(
+                &new_ctx(owner, bidder1, AUCTION_END),
+                &mut host,
+                Amount::from_ccd(10),
+                &mut TestLogger::init(),
+            ),
+            ContractError::TokenAuction,
+            "Bidding ccd should fail",
+        );
+
+        let token_id = TokenIdVec(Vec::new());
+        receive_tokens(
+            &mut host,
+            owner,
+            PAYMENT_CONTRACT,
+            token_id.clone(),
+            100,
+            bidder1,
+        )
+        .expect_report("Bidding tokens should pass");
+        expect_error(
+            receive_tokens(
+                &mut host,
+                owner,
+                PAYMENT_CONTRACT,
+                token_id.clone(),
+                50,
+                bidder2,
+            ),
+            EscrowError::Bid(ContractError::BidTooLow {
+                minimum_required: Amount::from_micro_ccd(101),
+            }),
+            "A lower token bid should fail",
+        );
+        receive_tokens(&mut host, owner, PAYMENT_CONTRACT, token_id, 200, bidder2)
+            .expect_report("Bidding tokens should pass");
+        claim_eq!(pending_refund(&host, bidder1), Amount::from_micro_ccd(100));
+
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder2));
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        // the owner was paid in tokens, not ccd
+        claim!(host.get_transfers().is_empty());
+    }
+
+    #[concordium_test]
+    fn test_several_payment_tokens() {
+        let stable = |id: u8, min_bid: u64| PaymentToken {
+            contract: PAYMENT_CONTRACT,
+            token_id: TokenIdVec(vec![id]),
+            min_bid: Amount::from_micro_ccd(min_bid),
+        };
+        let mut host = host_with_param(&InitParameter {
+            payment_tokens: vec![stable(1, 100), stable(2, 500)],
+            ..item_and_param()
+        });
+        host.setup_mock_entrypoint(
+            PAYMENT_CONTRACT,
+            OwnedEntrypointName::new_unchecked("transfer".into()),
+            MockFn::returning_ok(()),
+        );
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        // each token has its own minimum
+        expect_error(
+            receive_tokens(
+                &mut host,
+                owner,
+                PAYMENT_CONTRACT,
+                TokenIdVec(vec![2]),
+                200,
+                bidder1,
+            ),
+            EscrowError::Bid(ContractError::BelowMinimum(Amount::from_micro_ccd(500))),
+            "A bid below the token's minimum should fail",
+        );
+        receive_tokens(
+            &mut host,
+            owner,
+            PAYMENT_CONTRACT,
+            TokenIdVec(vec![1]),
+            200,
+            bidder1,
+        )
+        .expect_report("Bidding should pass");
+        receive_tokens(
+            &mut host,
+            owner,
+            PAYMENT_CONTRACT,
+            TokenIdVec(vec![2]),
+            300,
+            bidder2,
+        )
+        .expect_report("Bidding should pass");
+        claim_eq!(auction(&host).highest_currency, 1);
+        // the outbid bid is owed back in the token it was made in
+        claim_eq!(
+            pending_refund_in(&host, bidder1, 0),
+            Amount::from_micro_ccd(200)
+        );
+        claim_eq!(pending_refund_in(&host, bidder1, 1), Amount::zero());
+    }
+
+    #[concordium_test]
+    fn test_wccd_unwrapped_for_seller() {
+        let mut host = host_with_param(&InitParameter {
+            wccd: Some(PAYMENT_CONTRACT),
+            unwrap_proceeds: true,
+            ..item_and_param()
+        });
+        // only unwrap is mocked, paying the seller in wccd would fail
+        host.setup_mock_entrypoint(
+            PAYMENT_CONTRACT,
+            OwnedEntrypointName::new_unchecked("unwrap".into()),
+            MockFn::returning_ok(()),
+        );
+        let owner = SELLER;
+        let bidder = new_account();
+        receive_tokens(
+            &mut host,
+            owner,
+            PAYMENT_CONTRACT,
+            TokenIdVec(Vec::new()),
+            1000,
+            bidder,
+        )
+        .expect_report("Bidding wccd should pass");
+
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder));
+        claim_proceeds(&mut host, &mut TestLogger::init());
+    }
+
+    #[concordium_test]
+    fn test_claim_item() {
+        let mut host = host_with_param(&InitParameter {
+            item_tokens: vec![token_item()],
+            ..item_and_param()
+        });
+        host.setup_mock_entrypoint(
+            TOKEN_CONTRACT,
+            OwnedEntrypointName::new_unchecked("transfer".into()),
+            MockFn::returning_ok(()),
+        );
+        let owner = SELLER;
+        let bidder = new_account();
+        deposit(&mut host, owner, owner, &token_item()).expect_report("Depositing should pass");
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder, AUCTION_END),
+            Amount::from_ccd(10),
+            Amount::zero(),
+        );
+
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        // settling leaves the token for the winner to pull
+        claim!(auction(&host).item_escrowed);
+
+        expect_error(
+            auction_claim_item(&new_ctx(owner, owner, AUCTION_END + 1), &mut host),
+            ItemClaimError::NotWinner,
+            "Only the winner can claim the token",
+        );
+        expect_error(
+            auction_reclaim_item(&new_ctx(owner, owner, AUCTION_END + 1), &mut host),
+            ItemClaimError::NotUnsold,
+            "The seller can't reclaim a sold token",
+        );
+        let ctx = new_ctx(owner, bidder, AUCTION_END + 1);
+        auction_claim_item(&ctx, &mut host).expect_report("Claiming should pass");
+        claim!(!auction(&host).item_escrowed);
+        expect_error(
+            auction_claim_item(&ctx, &mut host),
+            ItemClaimError::NotEscrowed,
+            "Claiming twice should fail",
+        );
+    }
+
+    #[concordium_test]
+    fn test_charity_donation() {
+        let charity = Charity {
+            account: new_account(),
+            bps: 500,
+        };
+        let mut host = host_with_param(&InitParameter {
+            charity: Some(charity),
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder = new_account();
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder, AUCTION_END),
+            Amount::from_ccd(100),
+            Amount::zero(),
+        );
+
+        let mut logger = TestLogger::init();
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut logger,
+        )
+        .expect_report("Finalizing should pass");
+        claim_proceeds(&mut host, &mut logger);
+        claim_eq!(
+            host.get_transfers(),
+            [
+                (charity.account, Amount::from_ccd(5)),
+                (owner, Amount::from_ccd(95))
+            ]
+        );
+        claim_eq!(
+            logger.logs,
+            [
+                event(
+                    2,
+                    AuctionEvent::AuctionFinalized {
+                        auction_id: AUCTION_ID,
+                        winner: Some(bidder),
+                        price: Amount::from_ccd(100),
+                    }
+                ),
+                event(
+                    3,
+                    AuctionEvent::Donated {
+                        charity: charity.account,
+                        amount: Amount::from_ccd(5),
+                    }
+                )
+            ]
+        );
+    }
+
+    #[concordium_test]
+    fn test_buyer_premium() {
+        let mut host = host_with_param(&InitParameter {
+            buyer_premium_bps: 1000,
+            ..item_and_param()
+        });
+        set_fees(&mut host, 0);
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        // 11 CCD is a 10 CCD bid plus the 10% premium
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder1, AUCTION_END),
+            Amount::from_ccd(11),
+            Amount::zero(),
+        );
+        claim_eq!(auction(&host).highest_bid, Amount::from_ccd(10));
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder2, AUCTION_END),
+            Amount::from_ccd(22),
+            Amount::from_ccd(11),
+        );
+        // the outbid bidder gets the premium back along with the bid
+        claim_eq!(pending_refund(&host, bidder1), Amount::from_ccd(11));
+
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(
+            host.get_transfers(),
+            [
+                (owner, Amount::from_ccd(20)),
+                (FEE_RECEIVER, Amount::from_ccd(2))
+            ]
+        );
+    }
+
+    #[concordium_test]
+    fn test_payment_split_must_sum_to_whole() {
+        let parameter_bytes = create_parameter_bytes(&InitParameter {
+            payees: vec![(new_account(), 3000), (new_account(), 6000)],
+            ..item_and_param()
+        });
+        let ctx = parametrized_init_ctx(&parameter_bytes);
+        let mut state_builder = TestStateBuilder::new();
+        claim!(
+            auction_init(
+                &ctx,
+                &mut state_builder,
+                Amount::zero(),
+                &mut TestLogger::init()
+            )
+            .is_err(),
+            "Shares not adding to 100% should fail"
+        );
+    }
+
+    #[concordium_test]
+    fn test_set_fee_receiver() {
+        let mut host = initialized_host();
+        let owner = SELLER;
+        let new_receiver = new_account();
+        let parameter_bytes = to_bytes(&new_receiver);
+
+        let mut ctx = new_ctx(owner, new_receiver, AUCTION_END);
+        ctx.set_parameter(&parameter_bytes);
+        expect_error(
+            set_fee_receiver(&ctx, &mut host),
+            AdminError::MissingRole,
+            "Only the admin can set the fee receiver",
+        );
+
+        let mut ctx = new_ctx(owner, owner, AUCTION_END);
+        ctx.set_parameter(&parameter_bytes);
+        set_fee_receiver(&ctx, &mut host).expect_report("Setting the fee receiver should pass");
+        claim_eq!(
+            view_fee_config(&ctx, &host).expect_report("View should pass"),
+            FeeConfig {
+                fee_bps: 0,
+                fee_receiver: new_receiver,
+            }
+        );
+
+        // the view follows the contract wide fee as well
+        set_fees(&mut host, 250);
+        claim_eq!(
+            view_fee_config(&ctx, &host).expect_report("View should pass"),
+            FeeConfig {
+                fee_bps: 250,
+                fee_receiver: FEE_RECEIVER,
+            }
+        );
+    }
+
+    #[concordium_test]
+    fn test_outbid_bidder_withdraws() {
+        let mut host = initialized_host();
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        let (ten, twenty) = (Amount::from_ccd(10), Amount::from_ccd(20));
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder1, AUCTION_END),
+            ten,
+            Amount::zero(),
+        );
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder1, AUCTION_END),
+            twenty,
+            ten,
+        );
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder2, AUCTION_END),
+            Amount::from_ccd(30),
+            ten + twenty,
+        );
+        // bidding does no transfers, both of bidder1's bids are owed
+        claim!(host.get_transfers().is_empty());
+
+        let ctx = new_ctx(owner, bidder1, AUCTION_END);
+        auction_withdraw(&ctx, &mut host).expect_report("Withdrawing should pass");
+        claim_eq!(host.get_transfers(), [(bidder1, ten + twenty)]);
+        expect_error(
+            auction_withdraw(&ctx, &mut host),
+            WithdrawError::NothingToWithdraw,
+            "Withdrawing twice should fail",
+        );
+    }
+
+    #[concordium_test]
+    fn test_blacklisted_bidder_rejected() {
+        let mut host = initialized_host();
+        let owner = SELLER;
+        let bidder = new_account();
+        let parameter_bytes = to_bytes(&(AUCTION_ID, bidder));
+
+        let mut ctx = new_ctx(owner, owner, AUCTION_END);
+        ctx.set_parameter(&parameter_bytes);
+        let mut logger = TestLogger::init();
+        add_to_blacklist(&ctx, &mut host, &mut logger).expect_report("Blacklisting should pass");
+
+        let bid_ctx = new_ctx(owner, bidder, AUCTION_END);
+        let amount = Amount::from_micro_ccd(100);
+        host.set_self_balance(amount);
+        expect_error(
+            auction_bid(&bid_ctx, &mut host, amount, &mut TestLogger::init()),
+            ContractError::Blacklisted,
+            "Bidding should fail for a blacklisted account",
+        );
+
+        remove_from_blacklist(&ctx, &mut host, &mut logger).expect_report("Removing should pass");
+        // both changes are logged with the account
+        claim_eq!(
+            logger.logs,
+            [
+                event(
+                    1,
+                    AuctionEvent::Blacklisted {
+                        auction_id: AUCTION_ID,
+                        account: bidder
+                    }
+                ),
+                event(
+                    2,
+                    AuctionEvent::Unblacklisted {
+                        auction_id: AUCTION_ID,
+                        account: bidder
+                    }
+                )
+            ]
+        );
+        bid(&mut host, &bid_ctx, amount, Amount::zero());
+    }
+
+    #[concordium_test]
+    fn test_blacklist_only_admin() {
+        let mut host = initialized_host();
+        let owner = SELLER;
+        let other = new_account();
+        let parameter_bytes = to_bytes(&(AUCTION_ID, other));
+
+        let mut ctx = new_ctx(owner, other, AUCTION_END);
+        ctx.set_parameter(&parameter_bytes);
+        expect_error(
+            add_to_blacklist(&ctx, &mut host, &mut TestLogger::init()),
+            AdminError::MissingRole,
+            "Only the admin can blacklist",
+        );
+    }
+
+    #[concordium_test]
+    fn test_whitelist_mode() {
+        let mut host = host_with_param(&InitParameter {
+            whitelist_only: true,
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder = new_account();
+        let parameter_bytes = to_bytes(&(AUCTION_ID, bidder));
+
+        let bid_ctx = new_ctx(owner, bidder, AUCTION_END);
+        let amount = Amount::from_micro_ccd(100);
+        host.set_self_balance(amount);
+        expect_error(
+            auction_bid(&bid_ctx, &mut host, amount, &mut TestLogger::init()),
+            ContractError::NotWhitelisted,
+            "Bidding should fail for an account not on the whitelist",
+        );
+
+        let mut ctx = new_ctx(owner, owner, AUCTION_END);
+        ctx.set_parameter(&parameter_bytes);
+        add_to_whitelist(&ctx, &mut host).expect_report("Whitelisting should pass");
+        bid(&mut host, &bid_ctx, amount, Amount::zero());
+    }
+
+    #[concordium_test]
+    fn test_finalize_without_bids() {
+        let mut host = initialized_host();
+        let ctx = new_ctx(SELLER, SELLER, AUCTION_END + 1);
+        auction_settle(&ctx, &mut host, &mut TestLogger::init())
+            .expect_report("Finalizing should pass");
+        // the auction ends for good, without a winner
+        claim_eq!(auction(&host).auction_state, AuctionState::NotSold);
+        claim_eq!(
+            view(&ctx, &host).expect_report("View should pass").phase,
+            AuctionPhase::Ended
+        );
+        expect_error(
+            auction_settle(&ctx, &mut host, &mut TestLogger::init()),
+            ContractError::AuctionAlreadyFinalized,
+            "Finalizing twice should fail",
+        );
+    }
+
+    #[concordium_test]
+    fn test_reserve_not_met() {
+        let mut host = host_with_param(&InitParameter {
+            reserve: Some(Amount::from_micro_ccd(1000)),
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder = new_account();
+        let amount = Amount::from_micro_ccd(100);
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder, AUCTION_END),
+            amount,
+            Amount::zero(),
+        );
+
+        let ctx = new_ctx(owner, owner, AUCTION_END + 1);
+        let mut logger = TestLogger::init();
+        auction_settle(&ctx, &mut host, &mut logger).expect_report("Finalizing should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::ReserveNotMet);
+        claim_eq!(host.get_transfers(), [(bidder, amount)]);
+        claim_eq!(
+            logger.logs,
+            [
+                event(
+                    2,
+                    AuctionEvent::RefundIssued {
+                        account: bidder,
+                        amount,
+                        reason: RefundReason::ReserveNotMet,
+                    }
+                ),
+                event(
+                    3,
+                    AuctionEvent::AuctionFinalized {
+                        auction_id: AUCTION_ID,
+                        winner: None,
+                        price: Amount::zero(),
+                    }
+                )
+            ]
+        );
+    }
+
+    #[concordium_test]
+    fn test_reserve_not_met_returns_item() {
+        let mut host = host_with_param(&InitParameter {
+            reserve: Some(Amount::from_ccd(10)),
+            item_tokens: vec![token_item()],
+            ..item_and_param()
+        });
+        host.setup_mock_entrypoint(
+            TOKEN_CONTRACT,
+            OwnedEntrypointName::new_unchecked("transfer".into()),
+            MockFn::returning_ok(()),
+        );
+        deposit(&mut host, SELLER, SELLER, &token_item()).expect_report("Depositing should pass");
+        bid(
+            &mut host,
+            &new_ctx(SELLER, new_account(), AUCTION_END),
+            Amount::from_ccd(5),
+            Amount::zero(),
+        );
+
+        let ctx = new_ctx(SELLER, SELLER, AUCTION_END + 1);
+        auction_settle(&ctx, &mut host, &mut TestLogger::init())
+            .expect_report("Finalizing should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::ReserveNotMet);
+        // the token stays escrowed until the seller takes it back
+        claim!(auction(&host).tokens_deposited[0]);
+        auction_reclaim_item(&ctx, &mut host).expect_report("Reclaiming should pass");
+        claim!(!auction(&host).item_escrowed);
+        claim!(!auction(&host).tokens_deposited[0]);
+    }
+
+    #[concordium_test]
+    fn test_failed_refund_is_credited() {
+        let mut host = host_with_param(&InitParameter {
+            reserve: Some(Amount::from_micro_ccd(1000)),
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder = new_account();
+        let amount = Amount::from_micro_ccd(100);
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder, AUCTION_END),
+            amount,
+            Amount::zero(),
+        );
+        host.make_account_missing(bidder);
+
+        let ctx = new_ctx(owner, owner, AUCTION_END + 1);
+        auction_settle(&ctx, &mut host, &mut TestLogger::init())
+            .expect_report("Finalizing should not abort");
+        claim_eq!(auction(&host).auction_state, AuctionState::ReserveNotMet);
+        claim_eq!(pending_refund(&host, bidder), amount);
+    }
+
+    #[concordium_test]
+    fn test_claim_refund() {
+        let mut host = initialized_host();
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        let (ten, twenty) = (Amount::from_ccd(10), Amount::from_ccd(20));
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder1, AUCTION_END),
+            ten,
+            Amount::zero(),
+        );
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder2, AUCTION_END),
+            twenty,
+            ten,
+        );
+
+        let parameter_bytes = to_bytes(&(AUCTION_ID, bidder1, BASE_CURRENCY));
+        let mut ctx = new_ctx(owner, bidder2, AUCTION_END);
+        ctx.set_parameter(&parameter_bytes);
+        claim_eq!(
+            view_pending_refund(&ctx, &host).expect_report("View should pass"),
+            ten
+        );
+
+        auction_claim_refund(&new_ctx(owner, bidder1, AUCTION_END), &mut host)
+            .expect_report("Claiming should pass");
+        claim_eq!(host.get_transfers(), [(bidder1, ten)]);
+        claim_eq!(
+            view_pending_refund(&ctx, &host).expect_report("View should pass"),
+            Amount::zero()
+        );
+    }
+
+    #[concordium_test]
+    fn test_bid_below_minimum() {
+        let min_bid = Amount::from_micro_ccd(500);
+        let mut host = host_with_param(&InitParameter {
+            min_bid,
+            ..item_and_param()
+        });
+        let (_, ctx) = new_account_ctx();
+        let amount = Amount::from_micro_ccd(100);
+        host.set_self_balance(amount);
+        expect_error(
+            auction_bid(&ctx, &mut host, amount, &mut TestLogger::init()),
+            ContractError::BelowMinimum(min_bid),
+            "Opening bid below the minimum should fail",
+        );
+        bid(&mut host, &ctx, min_bid, Amount::zero());
+    }
+
+    #[concordium_test]
+    fn test_increment_too_small() {
+        let min_increment = Amount::from_ccd(5);
+        let mut host = host_with_param(&InitParameter {
+            min_increment: BidIncrement::Absolute(min_increment),
+            ..item_and_param()
+        });
+        let (_, ctx1) = new_account_ctx();
+        let (_, ctx2) = new_account_ctx();
+        let first = Amount::from_ccd(10);
+        bid(&mut host, &ctx1, first, Amount::zero());
+
+        let amount = Amount::from_ccd(12);
+        host.set_self_balance(first + amount);
+        expect_error(
+            auction_bid(&ctx2, &mut host, amount, &mut TestLogger::init()),
+            ContractError::IncrementTooSmall(first + min_increment),
+            "Raise below the minimum increment should fail",
+        );
+        bid(&mut host, &ctx2, first + min_increment, first);
+    }
+
+    #[concordium_test]
+    fn test_percentage_increment() {
+        let mut host = host_with_param(&InitParameter {
+            min_increment: BidIncrement::BasisPoints(1000),
+            ..item_and_param()
+        });
+        let (_, ctx1) = new_account_ctx();
+        let (_, ctx2) = new_account_ctx();
+        let first = Amount::from_ccd(100);
+        bid(&mut host, &ctx1, first, Amount::zero());
+
+        let amount = Amount::from_ccd(105);
+        host.set_self_balance(first + amount);
+        expect_error(
+            auction_bid(&ctx2, &mut host, amount, &mut TestLogger::init()),
+            ContractError::IncrementTooSmall(Amount::from_ccd(110)),
+            "Raise below 10% should fail",
+        );
+        bid(&mut host, &ctx2, Amount::from_ccd(110), first);
+    }
+
+    #[concordium_test]
+    fn test_buy_now() {
+        let buy_now = Amount::from_ccd(50);
+        let mut host = host_with_param(&InitParameter {
+            buy_now: Some(buy_now),
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        let first = Amount::from_ccd(10);
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder1, AUCTION_END),
+            first,
+            Amount::zero(),
+        );
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder2, AUCTION_END),
+            buy_now,
+            first,
+        );
+
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder2));
+        claim_eq!(pending_refund(&host, bidder1), first);
+        claim_eq!(host.get_transfers(), []);
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(host.get_transfers(), [(owner, buy_now)]);
+    }
+
+    #[concordium_test]
+    fn test_price_cap() {
+        let cap = Amount::from_ccd(30);
+        let mut host = host_with_param(&InitParameter {
+            price_cap: Some(cap),
+            ..item_and_param()
+        });
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        let first = Amount::from_ccd(10);
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder1, 0),
+            first,
+            Amount::zero(),
+        );
+        // the bid above the cap wins at the cap before the end
+        let sent = Amount::from_ccd(45);
+        host.set_self_balance(first + sent);
+        let mut logger = TestLogger::init();
+        auction_bid(&new_ctx(SELLER, bidder2, 0), &mut host, sent, &mut logger)
+            .expect_report("Bidding should pass");
+
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder2));
+        claim_eq!(auction(&host).highest_bid, cap);
+        claim_eq!(pending_refund(&host, bidder1), first);
+        claim_eq!(pending_refund(&host, bidder2), sent - cap);
+        claim_eq!(
+            logger.logs.last(),
+            Some(&event(
+                5,
+                AuctionEvent::InstantWin {
+                    winner: bidder2,
+                    price: cap
+                }
+            ))
+        );
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(host.get_transfers(), [(SELLER, cap)]);
+    }
+
+    #[concordium_test]
+    fn test_overtime_rounds() {
+        let mut host = host_with_param(&InitParameter {
+            end: Timestamp::from_timestamp_millis(1000),
+            overtime_round: Duration::from_millis(100),
+            ..item_and_param()
+        });
+        let (bidder1, bidder2) = (new_account(), new_account());
+        let (ten, twenty, thirty) = (
+            Amount::from_ccd(10),
+            Amount::from_ccd(20),
+            Amount::from_ccd(30),
+        );
+        // any bid before the end opens the first round after it
+        let mut logger = TestLogger::init();
+        host.set_self_balance(ten);
+        auction_bid(&new_ctx(SELLER, bidder1, 500), &mut host, ten, &mut logger)
+            .expect_report("Bidding should pass");
+        claim_eq!(
+            logger.logs[0],
+            event(
+                1,
+                AuctionEvent::OvertimeRound {
+                    round: 1,
+                    ends_at: Timestamp::from_timestamp_millis(1100),
+                    triggered_by: bidder1,
+                }
+            )
+        );
+        // another bid in the same round doesn't open more rounds
+        bid(&mut host, &new_ctx(SELLER, bidder2, 950), twenty, ten);
+        claim_eq!(auction(&host).overtime_rounds, 1);
+        claim_eq!(auction(&host).end, Timestamp::from_timestamp_millis(1100));
+
+        // a bid in the overtime round opens the next one, at the round's end
+        // rather than relative to the bid
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder1, 1050),
+            thirty,
+            ten + twenty,
+        );
+        claim_eq!(auction(&host).overtime_rounds, 2);
+        claim_eq!(auction(&host).end, Timestamp::from_timestamp_millis(1200));
+        claim_eq!(
+            auction(&host).last_bid_at,
+            Some(Timestamp::from_timestamp_millis(1050))
+        );
+
+        // the second round got no bid, so the auction closed at its end
+        host.set_self_balance(ten + twenty + thirty + thirty);
+        expect_error(
+            auction_bid(
+                &new_ctx(SELLER, bidder2, 1201),
+                &mut host,
+                thirty + thirty,
+                &mut TestLogger::init(),
+            ),
+            ContractError::BidTooLate {
+                ended_at: Timestamp::from_timestamp_millis(1200),
+            },
+            "Bidding after a round without bids should fail",
+        );
+        auction_settle(
+            &new_ctx(SELLER, SELLER, 1201),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder1));
+
+        // without any bid the auction closes at its end
+        let mut host = host_with_param(&InitParameter {
+            end: Timestamp::from_timestamp_millis(1000),
+            overtime_round: Duration::from_millis(100),
+            ..item_and_param()
+        });
+        auction_settle(
+            &new_ctx(SELLER, SELLER, 1001),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::NotSold);
+    }
+
+    #[concordium_test]
+    fn test_late_bid_extends_end() {
+        let window = Duration::from_millis(300_000);
+        let mut host = host_with_param(&InitParameter {
+            end: Timestamp::from_timestamp_millis(1_000_000),
+            extension_window: window,
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder = new_account();
+        let amount = Amount::from_ccd(1);
+
+        // a bid well before the window doesn't move the end
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder, 100_000),
+            amount,
+            Amount::zero(),
+        );
+        claim_eq!(
+            auction(&host).end,
+            Timestamp::from_timestamp_millis(1_000_000)
+        );
+
+        let mut logger = TestLogger::init();
+        host.set_self_balance(amount + amount + amount);
+        auction_bid(
+            &new_ctx(owner, bidder, 900_000),
+            &mut host,
+            amount + amount,
+            &mut logger,
+        )
+        .expect_report("Bidding should pass");
+        let new_end = Timestamp::from_timestamp_millis(1_300_000);
+        claim_eq!(auction(&host).end, new_end);
+        claim_eq!(
+            logger.logs,
+            [
+                event(
+                    2,
+                    AuctionEvent::RefundIssued {
+                        account: bidder,
+                        amount,
+                        reason: RefundReason::Outbid,
+                    }
+                ),
+                event(
+                    3,
+                    AuctionEvent::AuctionExtended {
+                        old_end: Timestamp::from_timestamp_millis(1_000_000),
+                        new_end,
+                        triggered_by: bidder,
+                    }
+                ),
+                event(
+                    4,
+                    AuctionEvent::Bid(BidEvent {
+                        bidder,
+                        amount: amount + amount,
+                        new_end,
+                        timestamp: Timestamp::from_timestamp_millis(900_000),
+                        memo: Vec::new(),
+                    })
+                )
+            ]
+        );
+    }
+
+    #[concordium_test]
+    fn test_bid_before_start() {
+        let mut host = host_with_param(&InitParameter {
+            start: Timestamp::from_timestamp_millis(100),
+            end: Timestamp::from_timestamp_millis(200),
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder = new_account();
+        let amount = Amount::from_ccd(1);
+        host.set_self_balance(amount);
+
+        let ctx = new_ctx(owner, bidder, 50);
+        claim_eq!(
+            view(&ctx, &host).expect_report("View should pass").phase,
+            AuctionPhase::Pending
+        );
+        expect_error(
+            auction_bid(&ctx, &mut host, amount, &mut TestLogger::init()),
+            ContractError::AuctionNotStarted {
+                starts_at: Timestamp::from_timestamp_millis(100),
+            },
+            "Bidding before the start should fail",
+        );
+
+        let ctx = new_ctx(owner, bidder, 150);
+        claim_eq!(
+            view(&ctx, &host).expect_report("View should pass").phase,
+            AuctionPhase::Active
+        );
+        bid(&mut host, &ctx, amount, Amount::zero());
+    }
+
+    #[concordium_test]
+    fn test_dutch_auction() {
+        let mut host = host_with_param(&InitParameter {
+            auction_type: AuctionType::Dutch(DutchAuction {
+                start_price: Amount::from_ccd(100),
+                floor_price: Amount::from_ccd(40),
+                curve: DecayCurve::Linear {
+                    decay_per_second: Amount::from_ccd(1),
+                },
+            }),
+            end: Timestamp::from_timestamp_millis(1_000_000),
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder = new_account();
+
+        // after 30 seconds the price is 70 CCD
+        let ctx = new_ctx(owner, bidder, 30_000);
+        let amount = Amount::from_ccd(60);
+        host.set_self_balance(amount);
+        expect_error(
+            auction_bid(&ctx, &mut host, amount, &mut TestLogger::init()),
+            ContractError::BelowCurrentPrice(Amount::from_ccd(70)),
+            "Bidding below the current price should fail",
+        );
+
+        // sending more than the price pays the price, the rest is refunded
+        let sent = Amount::from_ccd(80);
+        host.set_self_balance(sent);
+        let mut logger = TestLogger::init();
+        auction_bid(&ctx, &mut host, sent, &mut logger).expect_report("Bidding should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder));
+        claim_eq!(auction(&host).highest_bid, Amount::from_ccd(70));
+        claim_eq!(pending_refund(&host, bidder), Amount::from_ccd(10));
+        claim_eq!(
+            logger.logs.last(),
+            Some(&event(
+                2,
+                AuctionEvent::RefundIssued {
+                    account: bidder,
+                    amount: Amount::from_ccd(10),
+                    reason: RefundReason::AboveCurrentPrice,
+                }
+            ))
+        );
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(host.get_transfers(), [(owner, Amount::from_ccd(70))]);
+    }
+
+    #[concordium_test]
+    fn test_dutch_decay_curves() {
+        let start = Timestamp::from_timestamp_millis(0);
+        let at = |millis| Timestamp::from_timestamp_millis(millis);
+        let stepped = DutchAuction {
+            start_price: Amount::from_ccd(100),
+            floor_price: Amount::from_ccd(10),
+            curve: DecayCurve::Stepped {
+                step: Duration::from_millis(60_000),
+                decay_per_step: Amount::from_ccd(20),
+            },
+        };
+        claim_eq!(
+            stepped.current_price(start, at(59_999)),
+            Amount::from_ccd(100)
+        );
+        claim_eq!(
+            stepped.current_price(start, at(120_000)),
+            Amount::from_ccd(60)
+        );
+        claim_eq!(
+            stepped.current_price(start, at(600_000)),
+            Amount::from_ccd(10)
+        );
+
+        let exponential = DutchAuction {
+            start_price: Amount::from_ccd(100),
+            floor_price: Amount::from_ccd(10),
+            curve: DecayCurve::Exponential {
+                period: Duration::from_millis(60_000),
+                decay_bps: 5000,
+            },
+        };
+        claim_eq!(
+            exponential.current_price(start, at(60_000)),
+            Amount::from_ccd(50)
+        );
+        claim_eq!(
+            exponential.current_price(start, at(180_000)),
+            Amount::from_micro_ccd(12_500_000)
+        );
+        claim_eq!(
+            exponential.current_price(start, at(600_000)),
+            Amount::from_ccd(10)
+        );
+    }
+
+    #[concordium_test]
+    fn test_sealed_bid_auction() {
+        let mut host = host_with_param(&InitParameter {
+            auction_type: AuctionType::SealedBid {
+                reveal_end: Timestamp::from_timestamp_millis(200),
+            },
+            end: Timestamp::from_timestamp_millis(100),
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        // the mocked hash accepts any reveal
+        let hash = HashSha2256([7; 32]);
+        let crypto_primitives = TestCryptoPrimitives::new();
+        crypto_primitives.setup_hash_sha2_256_mock(move |_| hash);
+        let hash_bytes = to_bytes(&(AUCTION_ID, hash));
+
+        let deposit = Amount::from_ccd(50);
+        let mut logger = TestLogger::init();
+        for bidder in [bidder1, bidder2] {
+            let mut ctx = new_ctx(owner, bidder, 50);
+            ctx.set_parameter(&hash_bytes);
+            auction_commit(&ctx, &mut host, deposit, &mut logger)
+                .expect_report("Commit should pass");
+        }
+
+        let mut reveal = |bidder, amount| {
+            let reveal_bytes = to_bytes(&(
+                AUCTION_ID,
+                RevealParameter {
+                    amount,
+                    salt: vec![1, 2, 3],
+                },
+            ));
+            let mut ctx = new_ctx(owner, bidder, 150);
+            ctx.set_parameter(&reveal_bytes);
+            auction_reveal(&ctx, &mut host, &mut logger, &crypto_primitives)
+        };
+        reveal(bidder1, Amount::from_ccd(20)).expect_report("Reveal should pass");
+        reveal(bidder2, Amount::from_ccd(30)).expect_report("Reveal should pass");
+        let committed = |seq, depositor| {
+            event(
+                seq,
+                AuctionEvent::BidCommitted {
+                    auction_id: AUCTION_ID,
+                    depositor,
+                    deposit,
+                },
+            )
+        };
+        let revealed = |seq, bidder, amount| {
+            event(
+                seq,
+                AuctionEvent::BidRevealed {
+                    auction_id: AUCTION_ID,
+                    bidder,
+                    amount,
+                },
+            )
+        };
+        claim_eq!(
+            logger.logs,
+            [
+                committed(1, bidder1),
+                committed(2, bidder2),
+                revealed(3, bidder1, Amount::from_ccd(20)),
+                revealed(4, bidder2, Amount::from_ccd(30)),
+            ]
+        );
+        // revealed bids show up in the stats
+        claim_eq!(
+            view_stats(&new_ctx(owner, owner, 150), &host).expect_report("View should pass"),
+            AuctionStats {
+                bid_count: 2,
+                unique_bidders: 2,
+                total_volume: Amount::from_ccd(50),
+            }
+        );
+
+        host.set_self_balance(deposit + deposit);
+        auction_settle(
+            &new_ctx(owner, owner, 201),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder2));
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(
+            host.get_transfers(),
+            [
+                (bidder1, deposit),
+                (bidder2, Amount::from_ccd(20)),
+                (owner, Amount::from_ccd(30))
+            ]
+        );
+    }
+
+    #[concordium_test]
+    fn test_sealed_bid_binding() {
+        let mut host = host_with_param(&InitParameter {
+            auction_type: AuctionType::SealedBid {
+                reveal_end: Timestamp::from_timestamp_millis(200),
+            },
+            end: Timestamp::from_timestamp_millis(100),
+            ..item_and_param()
+        });
+        let (bidder1, bidder2, copier) = (new_account(), new_account(), new_account());
+        // stands in for sha256, distinct for every bidder
+        let fold = |data: &[u8]| {
+            let mut hash = [0u8; 32];
+            for (index, byte) in data.iter().enumerate() {
+                hash[index % 32] = hash[index % 32].wrapping_mul(31).wrapping_add(*byte);
+            }
+            HashSha2256(hash)
+        };
+        let crypto_primitives = TestCryptoPrimitives::new();
+        crypto_primitives.setup_hash_sha2_256_mock(fold);
+        let reveal_param = RevealParameter {
+            amount: Amount::from_ccd(20),
+            salt: vec![1, 2, 3],
+        };
+        let hash_of = |bidder| fold(&to_bytes(&(AUCTION_ID, bidder, &reveal_param)));
+
+        // bidder2 commits first, the copier reuses bidder1's published hash
+        let deposit = Amount::from_ccd(50);
+        for (bidder, hash) in [
+            (bidder2, hash_of(bidder2)),
+            (bidder1, hash_of(bidder1)),
+            (copier, hash_of(bidder1)),
+        ] {
+            let hash_bytes = to_bytes(&(AUCTION_ID, hash));
+            let mut ctx = new_ctx(SELLER, bidder, 50);
+            ctx.set_parameter(&hash_bytes);
+            auction_commit(&ctx, &mut host, deposit, &mut TestLogger::init())
+                .expect_report("Commit should pass");
+        }
+
+        let reveal_bytes = to_bytes(&(AUCTION_ID, &reveal_param));
+        let mut reveal = |bidder| {
+            let mut ctx = new_ctx(SELLER, bidder, 150);
+            ctx.set_parameter(&reveal_bytes);
+            auction_reveal(&ctx, &mut host, &mut TestLogger::init(), &crypto_primitives)
+        };
+        expect_error(
+            reveal(copier),
+            SealedBidError::InvalidReveal,
+            "A copied commitment can't be revealed",
+        );
+        reveal(bidder1).expect_report("Reveal should pass");
+        reveal(bidder2).expect_report("Reveal should pass");
+
+        // the bids tie, the earlier commit wins
+        host.set_self_balance(deposit + deposit + deposit);
+        auction_settle(
+            &new_ctx(SELLER, SELLER, 201),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder2));
+    }
+
+    #[concordium_test]
+    fn test_anonymous_bids() {
+        let mut host = host_with_param(&InitParameter {
+            auction_type: AuctionType::SealedBid {
+                reveal_end: Timestamp::from_timestamp_millis(200),
+            },
+            end: Timestamp::from_timestamp_millis(100),
+            anonymous_bids: true,
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let relayer = new_account();
+        let bidder = new_account();
+        let revealed = BlindedReveal {
+            bidder,
+            amount: Amount::from_ccd(20),
+            salt: vec![1, 2, 3],
+        };
+        let (revealed_hash, hidden_hash) = (HashSha2256([1; 32]), HashSha2256([2; 32]));
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let revealed_bytes = to_bytes(&(AUCTION_ID, &revealed));
+        crypto_primitives.setup_hash_sha2_256_mock(move |data| {
+            if data == revealed_bytes.as_slice() {
+                revealed_hash
+            } else {
+                hidden_hash
+            }
+        });
+
+        // one account funds both bids, the bidders stay unknown
+        let deposit = Amount::from_ccd(50);
+        for hash in [revealed_hash, hidden_hash] {
+            let hash_bytes = to_bytes(&(AUCTION_ID, hash));
+            let mut ctx = new_ctx(owner, relayer, 50);
+            ctx.set_parameter(&hash_bytes);
+            auction_commit_blinded(&ctx, &mut host, deposit, &mut TestLogger::init())
+                .expect_report("Blinded commit should pass");
+        }
+        claim!(auction(&host).commitments.get(&bidder).is_none());
+
+        // anyone can reveal once the bid is known
+        let reveal_bytes = to_bytes(&(AUCTION_ID, revealed));
+        let mut ctx = new_ctx(owner, owner, 150);
+        ctx.set_parameter(&reveal_bytes);
+        auction_reveal_blinded(&ctx, &mut host, &mut TestLogger::init(), &crypto_primitives)
+            .expect_report("Blinded reveal should pass");
+        expect_error(
+            auction_reveal_blinded(&ctx, &mut host, &mut TestLogger::init(), &crypto_primitives),
+            SealedBidError::NoCommitment,
+            "A blinded bid can only be revealed once",
+        );
+
+        host.set_self_balance(deposit + deposit);
+        auction_settle(
+            &new_ctx(owner, owner, 201),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Settling should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder));
+        claim!(auction(&host)
+            .blinded_commitments
+            .get(&hidden_hash)
+            .is_none());
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(
+            host.get_transfers(),
+            [
+                (bidder, Amount::from_ccd(30)),
+                (relayer, deposit),
+                (owner, Amount::from_ccd(20))
+            ]
+        );
+    }
+
+    #[concordium_test]
+    fn test_candle_auction() {
+        let secret = vec![42u8; 16];
+        let seed_hash = HashSha2256([9; 32]);
+        let mut host = host_with_param(&InitParameter {
+            auction_type: AuctionType::Candle {
+                closing_window: Duration::from_millis(100),
+                seed_hash,
+            },
+            end: Timestamp::from_timestamp_millis(200),
+            ..item_and_param()
+        });
+        // the secret hashes to the seed and the derived randomness is zero, so
+        // the effective end is the start of the closing window at 100
+        let crypto_primitives = TestCryptoPrimitives::new();
+        let expected_secret = secret.clone();
+        crypto_primitives.setup_hash_sha2_256_mock(move |data| {
+            if data == expected_secret.as_slice() {
+                seed_hash
+            } else {
+                HashSha2256([0; 32])
+            }
+        });
+
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        let first = Amount::from_ccd(10);
+        let second = Amount::from_ccd(20);
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder1, 50),
+            first,
+            Amount::zero(),
+        );
+        host.set_self_balance(first + second);
+        let mut logger = TestLogger::init();
+        auction_bid(
+            &new_ctx(owner, bidder2, 150),
+            &mut host,
+            second,
+            &mut logger,
+        )
+        .expect_report("Bidding should pass");
+        // nobody is refunded while bidding, the bids are logged like any other
+        claim!(host.get_transfers().is_empty());
+        claim_eq!(
+            logger.logs,
+            [event(
+                2,
+                AuctionEvent::Bid(BidEvent {
+                    bidder: bidder2,
+                    amount: second,
+                    new_end: Timestamp::from_timestamp_millis(200),
+                    timestamp: Timestamp::from_timestamp_millis(150),
+                    memo: Vec::new(),
+                })
+            )]
+        );
+
+        let secret_bytes = to_bytes(&(AUCTION_ID, secret));
+        let mut ctx = new_ctx(owner, owner, 201);
+        ctx.set_parameter(&secret_bytes);
+        auction_finalize_candle(&ctx, &mut host, &mut TestLogger::init(), &crypto_primitives)
+            .expect_report("Finalizing should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder1));
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(host.get_transfers(), [(bidder2, second), (owner, first)]);
+    }
+
+    #[concordium_test]
+    fn test_candle_expiry() {
+        let secret = vec![42u8; 16];
+        let seed_hash = HashSha2256([9; 32]);
+        let mut host = host_with_param(&InitParameter {
+            auction_type: AuctionType::Candle {
+                closing_window: Duration::from_millis(100),
+                seed_hash,
+            },
+            end: Timestamp::from_timestamp_millis(200),
+            settle_grace_period: Duration::from_millis(10),
+            ..item_and_param()
+        });
+        let crypto_primitives = TestCryptoPrimitives::new();
+        crypto_primitives.setup_hash_sha2_256_mock(move |_| seed_hash);
+        let (bidder1, bidder2) = (new_account(), new_account());
+        let (ten, twenty) = (Amount::from_ccd(10), Amount::from_ccd(20));
+        bid(
+            &mut host,
+            &new_ctx(SELLER, bidder1, 50),
+            ten,
+            Amount::zero(),
+        );
+        bid(&mut host, &new_ctx(SELLER, bidder2, 150), twenty, ten);
+
+        // the seller gets the first chance to finalize
+        let secret_bytes = to_bytes(&(AUCTION_ID, secret));
+        let mut ctx = new_ctx(SELLER, bidder1, 201);
+        ctx.set_parameter(&secret_bytes);
+        expect_error(
+            auction_finalize_candle(&ctx, &mut host, &mut TestLogger::init(), &crypto_primitives),
+            ContractError::SellerGracePeriod {
+                open_at: Timestamp::from_timestamp_millis(210),
+            },
+            "Others can't finalize during the grace period",
+        );
+
+        // without the secret the bids stay until the reveal timeout
+        let open_at = 200 + CANDLE_REVEAL_TIMEOUT.millis();
+        let expire = |host: &mut TestHost<VersionedState<TestStateApi>>, slot_time| {
+            auction_expire_candle(
+                &new_ctx(SELLER, bidder1, slot_time),
+                host,
+                &mut TestLogger::init(),
+            )
+        };
+        expect_error(
+            expire(&mut host, open_at),
+            ContractError::CandleRevealPending {
+                open_at: Timestamp::from_timestamp_millis(open_at),
+            },
+            "Expiring before the reveal timeout should fail",
+        );
+        expire(&mut host, open_at + 1).expect_report("Expiring should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::NotSold);
+        claim_eq!(host.get_transfers(), [(bidder1, ten), (bidder2, twenty)]);
+    }
+
+    #[concordium_test]
+    fn test_multi_unit_auction() {
+        let mut host = host_with_param(&InitParameter {
+            quantity: 2,
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        let bidder3 = new_account();
+        let (ten, twenty, thirty) = (
+            Amount::from_ccd(10),
+            Amount::from_ccd(20),
+            Amount::from_ccd(30),
+        );
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder1, AUCTION_END),
+            ten,
+            Amount::zero(),
+        );
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder2, AUCTION_END),
+            thirty,
+            ten,
+        );
+
+        // both units have a bid now, so a new bid has to beat the lowest one
+        host.set_self_balance(ten + thirty + ten);
+        expect_error(
+            auction_bid(
+                &new_ctx(owner, bidder3, AUCTION_END),
+                &mut host,
+                ten,
+                &mut TestLogger::init(),
+            ),
+            ContractError::BidTooLow {
+                minimum_required: ten + Amount::from_micro_ccd(1),
+            },
+            "Bid not beating the lowest winning bid should fail",
+        );
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder3, AUCTION_END),
+            twenty,
+            ten + thirty,
+        );
+        claim_eq!(pending_refund(&host, bidder1), ten);
+
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        claim_eq!(
+            auction(&host).auction_state,
+            AuctionState::SoldUnits(vec![bidder2, bidder3])
+        );
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(host.get_transfers(), [(owner, thirty + twenty)]);
+    }
+
+    #[concordium_test]
+    fn test_fractional_sale() {
+        let supply = TokenItem {
+            amount: TokenAmountU64(100),
+            ..token_item()
+        };
+        let mut host = host_with_param(&InitParameter {
+            quantity: 2,
+            item_tokens: vec![supply.clone()],
+            fractional: true,
+            ..item_and_param()
+        });
+        host.setup_mock_entrypoint(
+            TOKEN_CONTRACT,
+            OwnedEntrypointName::new_unchecked("transfer".into()),
+            MockFn::returning_ok(()),
+        );
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        deposit(&mut host, owner, owner, &supply).expect_report("Depositing should pass");
+        let (ten, thirty) = (Amount::from_ccd(10), Amount::from_ccd(30));
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder1, AUCTION_END),
+            thirty,
+            Amount::zero(),
+        );
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder2, AUCTION_END),
+            ten,
+            thirty,
+        );
+
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        // shares follow the bids, 30 and 10 out of 40
+        claim_eq!(
+            auction(&host)
+                .fraction_shares
+                .get(&bidder1)
+                .map(|share| *share),
+            Some(TokenAmountU64(75))
+        );
+        claim_eq!(
+            auction(&host)
+                .fraction_shares
+                .get(&bidder2)
+                .map(|share| *share),
+            Some(TokenAmountU64(25))
+        );
+
+        let ctx = new_ctx(owner, bidder1, AUCTION_END + 1);
+        auction_claim_item(&ctx, &mut host).expect_report("Claiming a share should pass");
+        expect_error(
+            auction_claim_item(&ctx, &mut host),
+            ItemClaimError::NotWinner,
+            "Claiming a share twice should fail",
+        );
+    }
+
+    #[concordium_test]
+    fn test_uniform_clearing_price() {
+        let mut host = host_with_param(&InitParameter {
+            quantity: 2,
+            uniform_price: true,
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        let (ten, thirty) = (Amount::from_ccd(10), Amount::from_ccd(30));
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder1, AUCTION_END),
+            ten,
+            Amount::zero(),
+        );
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder2, AUCTION_END),
+            thirty,
+            ten,
+        );
+
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(
+            host.get_transfers(),
+            [(bidder2, Amount::from_ccd(20)), (owner, ten + ten)]
+        );
+    }
+
+    #[concordium_test]
+    fn test_all_pay_auction() {
+        let mut host = host_with_param(&InitParameter {
+            all_pay: true,
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        let (ten, twenty) = (Amount::from_ccd(10), Amount::from_ccd(20));
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder1, AUCTION_END),
+            ten,
+            Amount::zero(),
+        );
+        bid(
+            &mut host,
+            &new_ctx(owner, bidder2, AUCTION_END),
+            twenty,
+            ten,
+        );
+        claim!(host.get_transfers().is_empty());
+
+        let ctx = new_ctx(owner, owner, AUCTION_END);
+        claim_eq!(
+            view(&ctx, &host)
+                .expect_report("View should pass")
+                .total_raised,
+            ten + twenty
+        );
+
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        claim_eq!(auction(&host).auction_state, AuctionState::Sold(bidder2));
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(host.get_transfers(), [(owner, ten + twenty)]);
+    }
+
+    #[concordium_test]
+    fn test_package_bid_limits() {
+        let mut host = host_with_param(&InitParameter {
+            auction_type: AuctionType::Combinatorial,
+            lots: (0..6).map(|lot| format!("Lot {}", lot)).collect(),
+            ..item_and_param()
+        });
+        let (bidder1, bidder2) = (new_account(), new_account());
+        let mut logger = TestLogger::init();
+        let mut bid_package = |bidder, lots: Vec<u32>, amount| {
+            let lots_bytes = to_bytes(&(AUCTION_ID, lots));
+            let mut ctx = new_ctx(SELLER, bidder, AUCTION_END);
+            ctx.set_parameter(&lots_bytes);
+            auction_bid_package(&ctx, &mut host, amount, &mut logger)
+        };
+        let twenty = Amount::from_ccd(20);
+        bid_package(bidder1, vec![0], twenty).expect_report("Bid should pass");
+        // a package keeps only its best bid
+        expect_error(
+            bid_package(bidder2, vec![0], twenty),
+            ContractError::BidTooLow {
+                minimum_required: twenty + Amount::from_micro_ccd(1),
+            },
+            "Matching the best bid on the package should fail",
+        );
+        bid_package(bidder2, vec![0], Amount::from_ccd(21)).expect_report("Bid should pass");
+
+        // new packages are taken until the limit
+        for lots in 2..=MAX_PACKAGE_BIDS as u32 {
+            let lot_ids = (0..6).filter(|lot| lots & (1 << lot) != 0).collect();
+            bid_package(bidder1, lot_ids, twenty).expect_report("Bid should pass");
+        }
+        expect_error(
+            bid_package(bidder1, vec![0, 5], twenty),
+            ContractError::TooManyPackages,
+            "Bidding on a new package above the limit should fail",
+        );
+        claim_eq!(pending_refund(&host, bidder1), twenty);
+        claim_eq!(auction(&host).package_bids.iter().count(), MAX_PACKAGE_BIDS);
+        // the displaced bid is logged as outbid
+        let placed = |seq, bidder, amount| {
+            event(
+                seq,
+                AuctionEvent::PackageBidPlaced {
+                    auction_id: AUCTION_ID,
+                    bidder,
+                    lots: 1,
+                    amount,
+                },
+            )
+        };
+        claim_eq!(
+            logger.logs[..3],
+            [
+                placed(1, bidder1, twenty),
+                event(
+                    2,
+                    AuctionEvent::RefundIssued {
+                        account: bidder1,
+                        amount: twenty,
+                        reason: RefundReason::Outbid,
+                    }
+                ),
+                placed(3, bidder2, Amount::from_ccd(21)),
+            ]
+        );
+    }
+
+    #[concordium_test]
+    fn test_combinatorial_auction() {
+        let mut host = host_with_param(&InitParameter {
+            auction_type: AuctionType::Combinatorial,
+            lots: vec!["Frame".into(), "Painting".into()],
+            ..item_and_param()
+        });
+        let owner = SELLER;
+        let bidder1 = new_account();
+        let bidder2 = new_account();
+        let bidder3 = new_account();
+
+        let mut bid_package = |bidder, lots: Vec<u32>, amount| {
+            let lots_bytes = to_bytes(&(AUCTION_ID, lots));
+            let mut ctx = new_ctx(owner, bidder, AUCTION_END);
+            ctx.set_parameter(&lots_bytes);
+            auction_bid_package(&ctx, &mut host, amount, &mut TestLogger::init())
+        };
+        bid_package(bidder1, vec![0, 1], Amount::from_ccd(30)).expect_report("Bid should pass");
+        bid_package(bidder2, vec![0], Amount::from_ccd(20)).expect_report("Bid should pass");
+        bid_package(bidder3, vec![1], Amount::from_ccd(15)).expect_report("Bid should pass");
+        expect_error(
+            bid_package(bidder3, vec![2], Amount::from_ccd(15)),
+            ContractError::UnknownLot,
+            "Bidding on a lot that doesn't exist should fail",
+        );
+        claim_eq!(
+            view_stats(&new_ctx(owner, owner, AUCTION_END), &host)
+                .expect_report("View should pass"),
+            AuctionStats {
+                bid_count: 3,
+                unique_bidders: 3,
+                total_volume: Amount::from_ccd(65),
+            }
+        );
+
+        // selling the lots separately raises 35 CCD, more than the 30 CCD package
+        host.set_self_balance(Amount::from_ccd(65));
+        auction_settle(
+            &new_ctx(owner, owner, AUCTION_END + 1),
+            &mut host,
+            &mut TestLogger::init(),
+        )
+        .expect_report("Finalizing should pass");
+        claim_eq!(
+            auction(&host).auction_state,
+            AuctionState::SoldLots(vec![
+                PackageAward {
+                    bidder: bidder2,
+                    lots: vec![0],
+                    price: Amount::from_ccd(20),
+                },
+                PackageAward {
+                    bidder: bidder3,
+                    lots: vec![1],
+                    price: Amount::from_ccd(15),
+                },
+            ])
+        );
+        claim_proceeds(&mut host, &mut TestLogger::init());
+        claim_eq!(
+            host.get_transfers(),
+            [
+                (bidder1, Amount::from_ccd(30)),
+                (owner, Amount::from_ccd(35))
+            ]
+        );
+    }
+    // an auction with one standing bid, stored in the first layout
+    fn state_v1(
+        state_builder: &mut TestStateBuilder,
+        bidder: AccountAddress,
+        bid: Amount,
+    ) -> StateV1<TestStateApi> {
+        let mut bid_history = state_builder.new_map();
+        bid_history.insert(
+            0,
+            BidRecordV1 {
+                bidder,
+                amount: bid,
+                timestamp: Timestamp::from_timestamp_millis(0),
+            },
+        );
+        let mut bids_by_bidder = state_builder.new_map();
+        bids_by_bidder.insert((bidder, 0), 0);
+        let mut bidder_bid_counts = state_builder.new_map();
+        bidder_bid_counts.insert(bidder, 1);
+        let auction = AuctionV1 {
+            auction_state: AuctionState::Continue,
+            auction_type: AuctionType::English,
+            highest_bidder: Some(bidder),
+            item: state_builder.new_box(item()),
+            seller: SELLER,
+            seller_can_bid: false,
+            verifier: None,
+            credential_registry: None,
+            token_gate: None,
+            start: Timestamp::from_timestamp_millis(0),
+            end: Timestamp::from_timestamp_millis(AUCTION_END),
+            blacklist: state_builder.new_set(),
+            whitelist_only: false,
+            whitelist: state_builder.new_set(),
+            reserve: None,
+            min_bid: Amount::zero(),
+            min_increment: BidIncrement::Absolute(Amount::zero()),
+            buy_now: None,
+            extension_window: Duration::from_millis(0),
+            commitments: state_builder.new_map(),
+            candle_bids: state_builder.new_map(),
+            candle_bid_count: 0,
+            quantity: 1,
+            ledger: Vec::new(),
+            uniform_price: false,
+            all_pay: false,
+            highest_bid: bid,
+            total_raised: Amount::zero(),
+            lots: state_builder.new_map(),
+            lot_count: 0,
+            package_bids: state_builder.new_map(),
+            package_bid_count: 0,
+            fee_bps: 0,
+            fee_receiver: FEE_RECEIVER,
+            royalty: None,
+            charity: None,
+            item_tokens: Vec::new(),
+            tokens_deposited: Vec::new(),
+            item_escrowed: false,
+            fractional: false,
+            fraction_shares: state_builder.new_map(),
+            payment_tokens: Vec::new(),
+            highest_currency: BASE_CURRENCY,
+            wccd: None,
+            unwrap_proceeds: false,
+            payees: Vec::new(),
+            pending_refunds: state_builder.new_map(),
+            buyer_premium_bps: 0,
+            highest_premium: Amount::zero(),
+            bid_history,
+            bid_history_count: 1,
+            bids_by_bidder,
+            bidder_bid_counts,
+            unique_bidders: 1,
+            total_volume: bid,
+        };
+        let mut auctions = state_builder.new_map();
+        auctions.insert(AUCTION_ID, auction);
+        StateV1 {
+            auctions,
+            auctions_by_seller: state_builder.new_map(),
+            paused: false,
+            admin: Address::Account(SELLER),
+            pending_admin: None,
+            roles: state_builder.new_map(),
+            operators: state_builder.new_set(),
+            credentials: state_builder.new_map(),
+            next_auction_id: 1,
+            next_event_seq: 2,
+        }
+    }
+
+    #[concordium_test]
+    fn test_migrate_v1_state() {
+        let bidder = new_account();
+        let bid = Amount::from_ccd(5);
+
+        // the balance has to cover the standing bid
+        let mut state_api = TestStateApi::new();
+        let mut state_builder = TestStateBuilder::open(state_api.clone());
+        let legacy = state_v1(&mut state_builder, bidder, bid);
+        state_api.write_root(&VersionedState::V1(legacy));
+        let stored: VersionedState<TestStateApi> = state_api
+            .read_root()
+            .expect_report("Reading the state should pass");
+        expect_error(
+            migrate_root(&mut state_api, &mut state_builder, stored, Amount::zero()),
+            MigrateError::Underfunded,
+            "Migrating without the escrowed bid in the balance should fail",
+        );
+
+        let mut state_api = TestStateApi::new();
+        let mut state_builder = TestStateBuilder::open(state_api.clone());
+        let legacy = state_v1(&mut state_builder, bidder, bid);
+        state_api.write_root(&VersionedState::V1(legacy));
+        let stored: VersionedState<TestStateApi> = state_api
+            .read_root()
+            .expect_report("Reading the state should pass");
+        migrate_root(&mut state_api, &mut state_builder, stored, bid)
+            .expect_report("Migrating should pass");
+
+        // the stored root is now the current layout, with the bid still held
+        let migrated: VersionedState<TestStateApi> = state_api
+            .read_root()
+            .expect_report("Reading the migrated state should pass");
+        let state = match migrated {
+            VersionedState::V2(ref state) => state,
+            VersionedState::V1(_) => fail!("The state should be in the current layout"),
+        };
+        claim_eq!(state.ccd_owed(), bid);
+        claim_eq!(state.next_auction_id, 1);
+        claim_eq!(state.next_event_seq, 2);
+        claim_eq!(state.admin, Address::Account(SELLER));
+        claim_eq!(state.fee_receiver, FEE_RECEIVER);
+        let auction = state
+            .auctions
+            .get(&AUCTION_ID)
+            .expect_report("The auction should be migrated");
+        claim_eq!(auction.highest_bidder, Some(bidder));
+        claim_eq!(auction.highest_bid, bid);
+        claim_eq!(auction.item.name, ITEM);
+        claim_eq!(auction.round, 1);
+        claim_eq!(
+            auction.bid_history.get(&0).map(|record| record.clone()),
+            Some(BidRecord {
+                bidder,
+                amount: bid,
+                timestamp: Timestamp::from_timestamp_millis(0),
+                memo: Vec::new(),
+            })
+        );
     }
 }
 